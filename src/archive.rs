@@ -0,0 +1,699 @@
+//! Streaming encrypted archives of directory trees.
+//!
+//! Directories are stored as ustar archives that are generated and extracted on the fly, so
+//! the cleartext is never held in memory or written to disk as a whole. Only directories and
+//! regular files are archived; symbolic links and special files are skipped.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::fs::File;
+//!
+//! use gpgme::{archive, Context, Protocol};
+//!
+//! let mut ctx = Context::from_protocol(Protocol::OpenPgp).unwrap();
+//! let key = ctx.get_key("[some key fingerprint]").unwrap();
+//! let output = File::create("photos.tar.gpg").unwrap();
+//! archive::encrypt(&mut ctx, Some(&key), "photos", output).unwrap();
+//!
+//! let input = File::open("photos.tar.gpg").unwrap();
+//! let restored = archive::restore(&mut ctx, input, "restored", |_, verification| {
+//!     verification.signatures().all(|s| s.status().is_ok())
+//! })
+//! .unwrap();
+//! println!("restored {} entries", restored.paths.len());
+//! ```
+use std::{
+    cmp, fmt, fs,
+    fs::{File, OpenOptions},
+    io::{self, prelude::*, SeekFrom},
+    mem,
+    path::{Component, Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    Context, Data, DecryptionResult, EncryptFlags, EncryptionResult, Error, IntoData, Key, Result,
+    SigningResult, VerificationResult,
+};
+
+const BLOCK: usize = 512;
+const MAX_OCTAL_SIZE: u64 = 0o777_7777_7777;
+const MAX_METADATA_SIZE: u64 = 1 << 20;
+
+const KIND_FILE: u8 = b'0';
+const KIND_DIR: u8 = b'5';
+const KIND_PAX: u8 = b'x';
+const KIND_GNU_LONG_NAME: u8 = b'L';
+
+fn padding(size: u64) -> usize {
+    ((BLOCK as u64 - (size % BLOCK as u64)) % BLOCK as u64) as usize
+}
+
+fn write_octal(field: &mut [u8], value: u64) {
+    let width = field.len() - 1;
+    let digits = format!("{:0width$o}", value, width = width);
+    field[..width].copy_from_slice(&digits.as_bytes()[(digits.len() - width)..]);
+    field[width] = 0;
+}
+
+fn parse_octal(field: &[u8]) -> Option<u64> {
+    if field.first().map_or(false, |&b| (b & 0x80) != 0) {
+        // GNU base-256 encoding.
+        return Some(field[1..].iter().fold(u64::from(field[0] & 0x7f), |acc, &b| {
+            (acc << 8) | u64::from(b)
+        }));
+    }
+    let s = std::str::from_utf8(field).ok()?;
+    let s = s.trim_matches(|c| (c == '\0') || (c == ' '));
+    if s.is_empty() {
+        return Some(0);
+    }
+    u64::from_str_radix(s, 8).ok()
+}
+
+fn checksum(block: &[u8]) -> u64 {
+    block
+        .iter()
+        .enumerate()
+        .map(|(i, &b)| if (148..156).contains(&i) { u64::from(b' ') } else { u64::from(b) })
+        .sum()
+}
+
+fn ustar_header(name: &str, mode: u32, size: u64, mtime: u64, kind: u8) -> [u8; BLOCK] {
+    let mut end = cmp::min(name.len(), 100);
+    while !name.is_char_boundary(end) {
+        end -= 1;
+    }
+    let mut header = [0u8; BLOCK];
+    header[..end].copy_from_slice(&name.as_bytes()[..end]);
+    write_octal(&mut header[100..108], u64::from(mode & 0o7777));
+    write_octal(&mut header[108..116], 0);
+    write_octal(&mut header[116..124], 0);
+    write_octal(&mut header[124..136], cmp::min(size, MAX_OCTAL_SIZE));
+    write_octal(&mut header[136..148], cmp::min(mtime, MAX_OCTAL_SIZE));
+    header[156] = kind;
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    let sum = format!("{:06o}\0 ", checksum(&header));
+    header[148..156].copy_from_slice(sum.as_bytes());
+    header
+}
+
+fn pax_record(key: &str, value: &str) -> String {
+    let base = key.len() + value.len() + 3;
+    let mut len = base + base.to_string().len();
+    if len.to_string().len() > base.to_string().len() {
+        len += 1;
+    }
+    format!("{} {}={}\n", len, key, value)
+}
+
+/// Returns the headers for an entry, using a pax extended header for long names and large
+/// files.
+fn entry_headers(name: &str, mode: u32, size: u64, mtime: u64, kind: u8) -> Vec<u8> {
+    let mut result = Vec::with_capacity(BLOCK);
+    let mut records = String::new();
+    if name.len() > 100 {
+        records.push_str(&pax_record("path", name));
+    }
+    if size > MAX_OCTAL_SIZE {
+        records.push_str(&pax_record("size", &size.to_string()));
+    }
+    if !records.is_empty() {
+        let len = records.len() as u64;
+        result.extend_from_slice(&ustar_header("././@PaxHeader", 0o644, len, mtime, KIND_PAX));
+        result.extend_from_slice(records.as_bytes());
+        result.resize(result.len() + padding(len), 0);
+    }
+    result.extend_from_slice(&ustar_header(name, mode, size, mtime, kind));
+    result
+}
+
+#[cfg(unix)]
+fn file_mode(metadata: &fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode()
+}
+
+#[cfg(not(unix))]
+fn file_mode(metadata: &fs::Metadata) -> u32 {
+    if metadata.is_dir() {
+        0o755
+    } else if metadata.permissions().readonly() {
+        0o444
+    } else {
+        0o644
+    }
+}
+
+#[cfg(unix)]
+fn set_file_mode(file: &File, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    file.set_permissions(fs::Permissions::from_mode(mode & 0o777))
+}
+
+#[cfg(not(unix))]
+fn set_file_mode(_file: &File, _mode: u32) -> io::Result<()> {
+    Ok(())
+}
+
+struct Entry {
+    path: PathBuf,
+    name: String,
+}
+
+/// A reader producing a tar archive of a directory tree.
+///
+/// The directory is scanned when the reader is created, but files are only opened and read
+/// as the archive is consumed.
+pub struct ArchiveReader {
+    entries: std::vec::IntoIter<Entry>,
+    buf: Vec<u8>,
+    pos: usize,
+    file: Option<(File, u64, u64)>,
+    finished: bool,
+}
+
+impl ArchiveReader {
+    /// Creates a reader for an archive containing `dir` and everything below it. The entries
+    /// are stored under the final component of `dir`.
+    pub fn new(dir: impl AsRef<Path>) -> Result<Self> {
+        fn walk(dir: &Path, name: &str, entries: &mut Vec<Entry>) -> io::Result<()> {
+            entries.push(Entry {
+                path: dir.to_owned(),
+                name: format!("{}/", name),
+            });
+            let mut children = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+            children.sort_by_key(|e| e.file_name());
+            for child in children {
+                let child_name = match child.file_name().into_string() {
+                    Ok(n) => format!("{}/{}", name, n),
+                    Err(_) => return Err(io::ErrorKind::InvalidData.into()),
+                };
+                let file_type = child.file_type()?;
+                if file_type.is_dir() {
+                    walk(&child.path(), &child_name, entries)?;
+                } else if file_type.is_file() {
+                    entries.push(Entry {
+                        path: child.path(),
+                        name: child_name,
+                    });
+                }
+            }
+            Ok(())
+        }
+
+        let dir = dir.as_ref();
+        let root = fs::canonicalize(dir)?
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("archive")
+            .to_owned();
+        let mut entries = Vec::new();
+        walk(dir, &root, &mut entries)?;
+        Ok(ArchiveReader {
+            entries: entries.into_iter(),
+            buf: Vec::new(),
+            pos: 0,
+            file: None,
+            finished: false,
+        })
+    }
+
+    fn next_entry(&mut self) -> io::Result<bool> {
+        let entry = match self.entries.next() {
+            Some(entry) => entry,
+            None if !self.finished => {
+                self.finished = true;
+                self.buf = vec![0; 2 * BLOCK];
+                self.pos = 0;
+                return Ok(true);
+            }
+            None => return Ok(false),
+        };
+        let is_dir = entry.name.ends_with('/');
+        let file = if is_dir { None } else { Some(File::open(&entry.path)?) };
+        let metadata = match file {
+            Some(ref file) => file.metadata()?,
+            None => fs::metadata(&entry.path)?,
+        };
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs());
+        let (size, kind) = if is_dir { (0, KIND_DIR) } else { (metadata.len(), KIND_FILE) };
+        self.buf = entry_headers(&entry.name, file_mode(&metadata), size, mtime, kind);
+        self.pos = 0;
+        self.file = file.filter(|_| size > 0).map(|f| (f, size, size));
+        Ok(true)
+    }
+}
+
+impl fmt::Debug for ArchiveReader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArchiveReader")
+            .field("remaining_entries", &self.entries.len())
+            .field("finished", &self.finished)
+            .finish()
+    }
+}
+
+impl Read for ArchiveReader {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.pos < self.buf.len() {
+                let n = cmp::min(out.len(), self.buf.len() - self.pos);
+                out[..n].copy_from_slice(&self.buf[self.pos..(self.pos + n)]);
+                self.pos += n;
+                return Ok(n);
+            }
+            if let Some((ref mut file, size, ref mut remaining)) = self.file {
+                let max = cmp::min(out.len() as u64, *remaining) as usize;
+                let n = file.read(&mut out[..max])?;
+                if (n == 0) && (max > 0) {
+                    // The file was truncated while it was being archived.
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                *remaining -= n as u64;
+                if *remaining == 0 {
+                    self.buf = vec![0; padding(size)];
+                    self.pos = 0;
+                    self.file = None;
+                }
+                return Ok(n);
+            }
+            if !self.next_entry()? {
+                return Ok(0);
+            }
+        }
+    }
+}
+
+enum Target {
+    File(File),
+    Metadata(u8, Vec<u8>),
+    Skip,
+}
+
+enum State {
+    Header,
+    Data(u64, usize, Target),
+    Padding(usize),
+    Done,
+}
+
+fn invalid_data() -> io::Error {
+    io::ErrorKind::InvalidData.into()
+}
+
+/// A writer extracting a tar archive into a directory.
+///
+/// Entries with absolute paths or paths containing `..` are rejected, as are entries that
+/// would overwrite existing files. Only directories and regular files are extracted.
+pub struct Extractor {
+    dest: PathBuf,
+    state: State,
+    block: Vec<u8>,
+    next_path: Option<String>,
+    next_size: Option<u64>,
+    paths: Vec<PathBuf>,
+}
+
+impl Extractor {
+    pub fn new(dest: impl AsRef<Path>) -> Self {
+        Extractor {
+            dest: dest.as_ref().to_owned(),
+            state: State::Header,
+            block: Vec::with_capacity(BLOCK),
+            next_path: None,
+            next_size: None,
+            paths: Vec::new(),
+        }
+    }
+
+    /// Returns the paths of the entries extracted so far.
+    #[inline]
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Checks that the end of the archive was reached and returns the paths of the extracted
+    /// entries.
+    pub fn finish(self) -> Result<Vec<PathBuf>> {
+        match self.state {
+            State::Done => Ok(self.paths),
+            _ => Err(Error::TRUNCATED),
+        }
+    }
+
+    fn target_path(&self, name: &str) -> io::Result<PathBuf> {
+        let mut path = self.dest.clone();
+        let mut empty = true;
+        for component in Path::new(name).components() {
+            match component {
+                Component::Normal(c) => {
+                    path.push(c);
+                    empty = false;
+                }
+                Component::CurDir => (),
+                _ => return Err(invalid_data()),
+            }
+        }
+        if empty {
+            return Err(invalid_data());
+        }
+        Ok(path)
+    }
+
+    fn start_entry(&mut self, block: &[u8]) -> io::Result<()> {
+        if block.iter().all(|&b| b == 0) {
+            self.state = State::Done;
+            return Ok(());
+        }
+        if parse_octal(&block[148..156]) != Some(checksum(block)) {
+            return Err(invalid_data());
+        }
+        let kind = block[156];
+        let mut size = parse_octal(&block[124..136]).ok_or_else(invalid_data)?;
+        let target = match kind {
+            KIND_PAX | KIND_GNU_LONG_NAME => {
+                if size > MAX_METADATA_SIZE {
+                    return Err(invalid_data());
+                }
+                Target::Metadata(kind, Vec::with_capacity(size as usize))
+            }
+            KIND_FILE | b'\0' | b'7' | KIND_DIR => {
+                size = self.next_size.take().unwrap_or(size);
+                let name = match self.next_path.take() {
+                    Some(name) => name,
+                    None => {
+                        let field = |f: &[u8]| {
+                            let end = f.iter().position(|&b| b == 0).unwrap_or(f.len());
+                            String::from_utf8(f[..end].to_vec()).map_err(|_| invalid_data())
+                        };
+                        let (name, prefix) = (field(&block[..100])?, field(&block[345..500])?);
+                        if prefix.is_empty() {
+                            name
+                        } else {
+                            format!("{}/{}", prefix, name)
+                        }
+                    }
+                };
+                let path = self.target_path(&name)?;
+                if kind == KIND_DIR {
+                    fs::create_dir_all(&path)?;
+                    self.paths.push(path);
+                    Target::Skip
+                } else {
+                    if let Some(parent) = path.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    let file = OpenOptions::new()
+                        .write(true)
+                        .create_new(true)
+                        .open(&path)?;
+                    let mode = parse_octal(&block[100..108]).unwrap_or(0o644) as u32;
+                    set_file_mode(&file, mode)?;
+                    self.paths.push(path);
+                    Target::File(file)
+                }
+            }
+            _ => {
+                self.next_path = None;
+                self.next_size = None;
+                Target::Skip
+            }
+        };
+        self.state = State::Data(size, padding(size), target);
+        if size == 0 {
+            self.finish_entry()?;
+        }
+        Ok(())
+    }
+
+    fn finish_entry(&mut self) -> io::Result<()> {
+        let (padding, target) = match mem::replace(&mut self.state, State::Header) {
+            State::Data(_, padding, target) => (padding, target),
+            state => {
+                self.state = state;
+                return Ok(());
+            }
+        };
+        match target {
+            Target::File(mut file) => file.flush()?,
+            Target::Metadata(KIND_GNU_LONG_NAME, data) => {
+                let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+                let name = String::from_utf8(data[..end].to_vec()).map_err(|_| invalid_data())?;
+                self.next_path = Some(name);
+            }
+            Target::Metadata(_, data) => self.parse_pax(&data)?,
+            Target::Skip => (),
+        }
+        if padding > 0 {
+            self.state = State::Padding(padding);
+        }
+        Ok(())
+    }
+
+    fn parse_pax(&mut self, mut data: &[u8]) -> io::Result<()> {
+        while !data.is_empty() {
+            let space = data.iter().position(|&b| b == b' ').ok_or_else(invalid_data)?;
+            let len = std::str::from_utf8(&data[..space])
+                .ok()
+                .and_then(|s| s.parse::<usize>().ok())
+                .filter(|&len| (len > space + 1) && (len <= data.len()))
+                .ok_or_else(invalid_data)?;
+            let record = std::str::from_utf8(&data[(space + 1)..(len - 1)])
+                .map_err(|_| invalid_data())?;
+            data = &data[len..];
+            let eq = record.find('=').ok_or_else(invalid_data)?;
+            match (&record[..eq], &record[(eq + 1)..]) {
+                ("path", value) => self.next_path = Some(value.to_owned()),
+                ("size", value) => {
+                    self.next_size = Some(value.parse().map_err(|_| invalid_data())?)
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Extractor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Extractor")
+            .field("dest", &self.dest)
+            .field("paths", &self.paths)
+            .finish()
+    }
+}
+
+impl Write for Extractor {
+    fn write(&mut self, mut buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len();
+        while !buf.is_empty() {
+            match self.state {
+                State::Done => break,
+                State::Header => {
+                    let n = cmp::min(BLOCK - self.block.len(), buf.len());
+                    self.block.extend_from_slice(&buf[..n]);
+                    buf = &buf[n..];
+                    if self.block.len() == BLOCK {
+                        let block = mem::replace(&mut self.block, Vec::with_capacity(BLOCK));
+                        self.start_entry(&block)?;
+                    }
+                }
+                State::Data(ref mut remaining, _, ref mut target) => {
+                    let n = cmp::min(*remaining, buf.len() as u64) as usize;
+                    match *target {
+                        Target::File(ref mut file) => file.write_all(&buf[..n])?,
+                        Target::Metadata(_, ref mut data) => data.extend_from_slice(&buf[..n]),
+                        Target::Skip => (),
+                    }
+                    *remaining -= n as u64;
+                    buf = &buf[n..];
+                    if *remaining == 0 {
+                        self.finish_entry()?;
+                    }
+                }
+                State::Padding(ref mut remaining) => {
+                    let n = cmp::min(*remaining, buf.len());
+                    *remaining -= n;
+                    buf = &buf[n..];
+                    if *remaining == 0 {
+                        self.state = State::Header;
+                    }
+                }
+            }
+        }
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.state {
+            State::Data(_, _, Target::File(ref mut file)) => file.flush(),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn plaintext(dir: &Path) -> Result<Data<'static>> {
+    let reader = ArchiveReader::new(dir)?;
+    let name = fs::canonicalize(dir)?
+        .file_name()
+        .and_then(|n| n.to_str())
+        .map(|n| format!("{}.tar", n));
+    let mut data = Data::from_reader(reader).map_err(|e| e.error())?;
+    if let Some(name) = name {
+        data.set_filename(name)?;
+    }
+    Ok(data)
+}
+
+/// Encrypts a tar archive of `dir` for the specified recipients.
+pub fn encrypt<'k, 'c, I, C>(
+    ctx: &mut Context, recp: I, dir: impl AsRef<Path>, ciphertext: C,
+) -> Result<EncryptionResult>
+where
+    I: IntoIterator<Item = &'k Key>,
+    C: IntoData<'c>, {
+    encrypt_with_flags(ctx, recp, dir, ciphertext, EncryptFlags::empty())
+}
+
+pub fn encrypt_with_flags<'k, 'c, I, C>(
+    ctx: &mut Context, recp: I, dir: impl AsRef<Path>, ciphertext: C, flags: EncryptFlags,
+) -> Result<EncryptionResult>
+where
+    I: IntoIterator<Item = &'k Key>,
+    C: IntoData<'c>, {
+    let plaintext = plaintext(dir.as_ref())?;
+    ctx.encrypt_with_flags(recp, plaintext, ciphertext, flags)
+}
+
+/// Signs and encrypts a tar archive of `dir` for the specified recipients.
+pub fn sign_and_encrypt<'k, 'c, I, C>(
+    ctx: &mut Context, recp: I, dir: impl AsRef<Path>, ciphertext: C,
+) -> Result<(EncryptionResult, SigningResult)>
+where
+    I: IntoIterator<Item = &'k Key>,
+    C: IntoData<'c>, {
+    sign_and_encrypt_with_flags(ctx, recp, dir, ciphertext, EncryptFlags::empty())
+}
+
+pub fn sign_and_encrypt_with_flags<'k, 'c, I, C>(
+    ctx: &mut Context, recp: I, dir: impl AsRef<Path>, ciphertext: C, flags: EncryptFlags,
+) -> Result<(EncryptionResult, SigningResult)>
+where
+    I: IntoIterator<Item = &'k Key>,
+    C: IntoData<'c>, {
+    let plaintext = plaintext(dir.as_ref())?;
+    ctx.sign_and_encrypt_with_flags(recp, plaintext, ciphertext, flags)
+}
+
+/// The result of restoring an archive.
+#[derive(Debug, Clone)]
+pub struct Restored {
+    pub decryption: DecryptionResult,
+    pub verification: VerificationResult,
+    /// The paths of the extracted directories and files.
+    pub paths: Vec<PathBuf>,
+}
+
+/// Decrypts an archive created by `encrypt` or `sign_and_encrypt` and extracts it into
+/// `dest`.
+///
+/// The ciphertext is processed twice. The first pass only decrypts and verifies it, without
+/// writing anything, and passes the results to `check`. If `check` returns `false` the
+/// restoration is aborted with `Error::CANCELED`. Otherwise the ciphertext is decrypted again
+/// and extracted. This ensures that nothing is extracted from a message that fails its
+/// integrity check or has unacceptable signatures.
+///
+/// The results of the second pass are passed to `check` as well, and the ciphertext must not
+/// change between the passes. If the second pass fails, its results are rejected or the
+/// ciphertext changed, the extracted entries are removed again before the error is returned.
+pub fn restore<R, F>(
+    ctx: &mut Context, mut ciphertext: R, dest: impl AsRef<Path>, check: F,
+) -> Result<Restored>
+where
+    R: Read + Seek + Send,
+    F: Fn(&DecryptionResult, &VerificationResult) -> bool, {
+    let start = ciphertext.seek(SeekFrom::Current(0))?;
+    let digest = {
+        let mut input = HashingReader::new(&mut ciphertext);
+        {
+            let data = Data::from_reader(&mut input).map_err(|e| e.error())?;
+            let sink = Data::from_writer(io::sink()).map_err(|e| e.error())?;
+            let (decryption, verification) = ctx.decrypt_and_verify(data, sink)?;
+            if !check(&decryption, &verification) {
+                return Err(Error::CANCELED);
+            }
+        }
+        input.hasher.result()
+    };
+
+    ciphertext.seek(SeekFrom::Start(start))?;
+    let mut extractor = Extractor::new(dest);
+    let mut input = HashingReader::new(&mut ciphertext);
+    let result = Data::from_reader(&mut input)
+        .map_err(|e| e.error())
+        .and_then(|data| {
+            let output = Data::from_writer(&mut extractor).map_err(|e| e.error())?;
+            ctx.decrypt_and_verify(data, output)
+        })
+        .and_then(|(decryption, verification)| {
+            if input.hasher.result() != digest {
+                return Err(Error::BAD_DATA);
+            }
+            if !check(&decryption, &verification) {
+                return Err(Error::CANCELED);
+            }
+            match extractor.state {
+                State::Done => Ok((decryption, verification)),
+                _ => Err(Error::TRUNCATED),
+            }
+        });
+    match result {
+        Ok((decryption, verification)) => Ok(Restored {
+            decryption,
+            verification,
+            paths: extractor.paths,
+        }),
+        Err(e) => {
+            // Directories that still contain entries not extracted here are left in place.
+            for path in extractor.paths.iter().rev() {
+                if path.is_dir() {
+                    let _ = fs::remove_dir(path);
+                } else {
+                    let _ = fs::remove_file(path);
+                }
+            }
+            Err(e)
+        }
+    }
+}
+
+struct HashingReader<R> {
+    inner: R,
+    hasher: Sha256,
+}
+
+impl<R: Read> HashingReader<R> {
+    fn new(inner: R) -> Self {
+        HashingReader {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.input(&buf[..n]);
+        Ok(n)
+    }
+}
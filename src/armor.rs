@@ -0,0 +1,558 @@
+//! OpenPGP ASCII armor (RFC 4880, section 6) without an engine.
+//!
+//! Armored blocks can be decoded from and encoded to text in memory using `Block`, `decode`
+//! and `blocks`, or streamed using `Reader` and `Writer`, which can be passed to operations
+//! as data.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use gpgme::armor::{self, Kind};
+//!
+//! let mail = std::fs::read("message.eml").unwrap();
+//! for block in armor::blocks(&mail) {
+//!     let block = block.unwrap();
+//!     if *block.kind() == Kind::Signature {
+//!         println!("signature created by {:?}", block.header("Comment"));
+//!     }
+//! }
+//! ```
+use std::{
+    fmt,
+    io::{self, prelude::*, BufReader},
+};
+
+use crate::{base64, Data, Error, IntoData, Result};
+
+const LINE_LENGTH: usize = 64;
+const CRC24_INIT: u32 = 0x00B7_04CE;
+const CRC24_POLY: u32 = 0x0186_4CFB;
+
+fn crc24(mut crc: u32, data: &[u8]) -> u32 {
+    for &b in data {
+        crc ^= u32::from(b) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if (crc & 0x0100_0000) != 0 {
+                crc ^= CRC24_POLY;
+            }
+        }
+    }
+    crc & 0x00FF_FFFF
+}
+
+/// The type of an armored block, as given in its header line.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Kind {
+    Message,
+    Signature,
+    PublicKey,
+    PrivateKey,
+    /// The cleartext of a cleartext signed message. The signature follows in a separate
+    /// block.
+    SignedMessage,
+    /// Any other block, e.g. `PGP MESSAGE, PART 1/2`.
+    Other(String),
+}
+
+impl Kind {
+    /// Returns the label used in the header line, e.g. `PGP SIGNATURE`.
+    pub fn label(&self) -> &str {
+        match *self {
+            Kind::Message => "PGP MESSAGE",
+            Kind::Signature => "PGP SIGNATURE",
+            Kind::PublicKey => "PGP PUBLIC KEY BLOCK",
+            Kind::PrivateKey => "PGP PRIVATE KEY BLOCK",
+            Kind::SignedMessage => "PGP SIGNED MESSAGE",
+            Kind::Other(ref label) => label,
+        }
+    }
+
+    fn from_label(label: &str) -> Kind {
+        match label {
+            "PGP MESSAGE" => Kind::Message,
+            "PGP SIGNATURE" => Kind::Signature,
+            "PGP PUBLIC KEY BLOCK" => Kind::PublicKey,
+            "PGP PRIVATE KEY BLOCK" => Kind::PrivateKey,
+            "PGP SIGNED MESSAGE" => Kind::SignedMessage,
+            _ => Kind::Other(label.to_owned()),
+        }
+    }
+}
+
+impl fmt::Display for Kind {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// Returns the kind of block started by `line`, if it is an armor header line.
+fn parse_begin(line: &[u8]) -> Option<Kind> {
+    let line = trim(line);
+    if !line.starts_with(b"-----BEGIN ") || !line.ends_with(b"-----") || (line.len() < 17) {
+        return None;
+    }
+    let label = std::str::from_utf8(&line[11..(line.len() - 5)]).ok()?;
+    Some(Kind::from_label(label))
+}
+
+fn parse_header(line: &[u8]) -> Result<(String, String)> {
+    let line = std::str::from_utf8(line).map_err(|_| Error::BAD_DATA)?;
+    let i = line.find(": ").ok_or(Error::BAD_DATA)?;
+    Ok((line[..i].to_owned(), line[(i + 2)..].to_owned()))
+}
+
+fn trim(mut line: &[u8]) -> &[u8] {
+    while let Some((&last, rest)) = line.split_last() {
+        if !last.is_ascii_whitespace() {
+            break;
+        }
+        line = rest;
+    }
+    line
+}
+
+/// Decodes the base64 body of an armored block line by line.
+#[derive(Debug)]
+struct Decoder {
+    pending: Vec<u8>,
+    crc: u32,
+    checksum: Option<u32>,
+}
+
+impl Decoder {
+    fn new() -> Self {
+        Decoder {
+            pending: Vec::new(),
+            crc: CRC24_INIT,
+            checksum: None,
+        }
+    }
+
+    /// Processes a line of the body, appending the decoded bytes to `out`. Returns `true`
+    /// once the armor tail line has been processed.
+    fn line(&mut self, line: &[u8], out: &mut Vec<u8>) -> Result<bool> {
+        let line = trim(line);
+        if line.starts_with(b"-----END ") {
+            if (self.pending.len() % 4) != 0 {
+                return Err(Error::BAD_DATA);
+            }
+            self.decode(self.pending.len(), out)?;
+            if self.checksum.map_or(false, |c| c != self.crc) {
+                return Err(Error::CHECKSUM);
+            }
+            return Ok(true);
+        }
+        if self.checksum.is_some() {
+            return Err(Error::BAD_DATA);
+        }
+        if line.starts_with(b"=") && (line.len() == 5) {
+            let crc = base64::decode(&line[1..]).ok_or(Error::BAD_DATA)?;
+            self.checksum = Some(crc.iter().fold(0, |acc, &b| (acc << 8) | u32::from(b)));
+            return Ok(false);
+        }
+        self.pending.extend_from_slice(line);
+        self.decode(self.pending.len() / 4 * 4, out)?;
+        Ok(false)
+    }
+
+    fn decode(&mut self, len: usize, out: &mut Vec<u8>) -> Result<()> {
+        let decoded = base64::decode(&self.pending[..len]).ok_or(Error::BAD_DATA)?;
+        self.crc = crc24(self.crc, &decoded);
+        out.extend_from_slice(&decoded);
+        self.pending.drain(..len);
+        Ok(())
+    }
+}
+
+/// An armored block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    kind: Kind,
+    headers: Vec<(String, String)>,
+    data: Vec<u8>,
+}
+
+impl Block {
+    /// Creates a block containing the binary `data`, or the cleartext for
+    /// `Kind::SignedMessage`.
+    pub fn new(kind: Kind, data: impl Into<Vec<u8>>) -> Self {
+        Block {
+            kind,
+            headers: Vec::new(),
+            data: data.into(),
+        }
+    }
+
+    /// Adds an armor header, e.g. `Comment` or `Hash`.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    #[inline]
+    pub fn kind(&self) -> &Kind {
+        &self.kind
+    }
+
+    /// Returns the armor headers in the order they appear.
+    #[inline]
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// Returns the value of the first header named `name`, ignoring case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| &**v)
+    }
+
+    /// Returns the decoded data, or the cleartext for `Kind::SignedMessage`.
+    #[inline]
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    #[inline]
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+impl fmt::Display for Block {
+    /// Encodes the block. Cleartext signed messages are written up to the start of the
+    /// signature, with lines starting with a dash escaped.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "-----BEGIN {}-----", self.kind)?;
+        for (name, value) in &self.headers {
+            writeln!(f, "{}: {}", name, value)?;
+        }
+        writeln!(f)?;
+        if self.kind == Kind::SignedMessage {
+            for line in String::from_utf8_lossy(&self.data).split('\n') {
+                if line.starts_with('-') {
+                    f.write_str("- ")?;
+                }
+                writeln!(f, "{}", line)?;
+            }
+            return Ok(());
+        }
+
+        let encoded = base64::encode(&self.data, 0);
+        for line in encoded.as_bytes().chunks(LINE_LENGTH) {
+            writeln!(f, "{}", String::from_utf8_lossy(line))?;
+        }
+        let crc = crc24(CRC24_INIT, &self.data);
+        let crc = [(crc >> 16) as u8, (crc >> 8) as u8, crc as u8];
+        writeln!(f, "={}", base64::encode(&crc, 0))?;
+        writeln!(f, "-----END {}-----", self.kind)
+    }
+}
+
+/// Returns the lines of `text` without line endings, together with their offsets.
+fn lines(text: &[u8]) -> impl Iterator<Item = (usize, &[u8])> {
+    let mut offset = 0;
+    text.split(|&b| b == b'\n').map(move |line| {
+        let start = offset;
+        offset += line.len() + 1;
+        (start, line)
+    })
+}
+
+/// An iterator over the armored blocks in a text, as returned by `blocks`.
+#[derive(Debug, Clone)]
+pub struct Blocks<'a> {
+    text: &'a [u8],
+}
+
+impl Blocks<'_> {
+    fn parse(&mut self) -> Option<Result<Block>> {
+        let mut lines = lines(self.text);
+        let kind = lines.by_ref().find_map(|(_, line)| parse_begin(line))?;
+        let mut block = Block::new(kind, Vec::new());
+        let mut has_body = false;
+        for (_, line) in &mut lines {
+            let line = trim(line);
+            if line.is_empty() {
+                has_body = true;
+                break;
+            }
+            match parse_header(line) {
+                Ok(header) => block.headers.push(header),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        if !has_body {
+            return Some(Err(Error::TRUNCATED));
+        }
+
+        if block.kind == Kind::SignedMessage {
+            let mut text = Vec::new();
+            for (offset, line) in lines {
+                let line = if line.ends_with(b"\r") { &line[..(line.len() - 1)] } else { line };
+                if parse_begin(line) == Some(Kind::Signature) {
+                    // Leave the signature for the next block. The line ending before it is
+                    // not part of the cleartext.
+                    self.text = &self.text[offset..];
+                    block.data = text.join(&b'\n');
+                    return Some(Ok(block));
+                }
+                text.push(if line.starts_with(b"- ") { &line[2..] } else { line });
+            }
+            return Some(Err(Error::TRUNCATED));
+        }
+
+        let mut decoder = Decoder::new();
+        for (offset, line) in lines {
+            match decoder.line(line, &mut block.data) {
+                Ok(true) => {
+                    self.text = self.text.get((offset + line.len() + 1)..).unwrap_or(&[]);
+                    return Some(Ok(block));
+                }
+                Ok(false) => (),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        Some(Err(Error::TRUNCATED))
+    }
+}
+
+impl Iterator for Blocks<'_> {
+    type Item = Result<Block>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = self.parse();
+        if result.as_ref().map_or(true, |r| r.is_err()) {
+            self.text = &[];
+        }
+        result
+    }
+}
+
+/// Returns an iterator over the armored blocks in `text`, skipping any text around them.
+///
+/// Cleartext signed messages produce a block of kind `Kind::SignedMessage` containing the
+/// dash-unescaped cleartext, followed by the block containing the signature. Iteration stops
+/// after the first malformed block.
+#[inline]
+pub fn blocks(text: &[u8]) -> Blocks<'_> {
+    Blocks { text }
+}
+
+/// Decodes the first armored block in `text`.
+pub fn decode(text: impl AsRef<[u8]>) -> Result<Block> {
+    blocks(text.as_ref()).next().unwrap_or(Err(Error::NO_DATA))
+}
+
+/// Encodes `data` as an armored block of the specified kind.
+pub fn encode(kind: Kind, data: impl Into<Vec<u8>>) -> String {
+    Block::new(kind, data).to_string()
+}
+
+/// A reader that decodes the first armored block containing binary data in its input.
+///
+/// Any text before the block, including the cleartext of cleartext signed messages, is
+/// skipped. Reading fails with `Error::CHECKSUM` if the checksum does not match.
+#[derive(Debug)]
+pub struct Reader<R> {
+    inner: BufReader<R>,
+    kind: Kind,
+    headers: Vec<(String, String)>,
+    decoder: Decoder,
+    buf: Vec<u8>,
+    pos: usize,
+    done: bool,
+}
+
+impl<R: Read> Reader<R> {
+    /// Reads the input up to the start of the armored data.
+    pub fn new(inner: R) -> Result<Self> {
+        let mut inner = BufReader::new(inner);
+        let mut line = Vec::new();
+        let kind = loop {
+            line.clear();
+            if inner.read_until(b'\n', &mut line)? == 0 {
+                return Err(Error::NO_DATA);
+            }
+            match parse_begin(&line) {
+                Some(Kind::SignedMessage) | None => (),
+                Some(kind) => break kind,
+            }
+        };
+        let mut headers = Vec::new();
+        loop {
+            line.clear();
+            if inner.read_until(b'\n', &mut line)? == 0 {
+                return Err(Error::TRUNCATED);
+            }
+            let line = trim(&line);
+            if line.is_empty() {
+                break;
+            }
+            headers.push(parse_header(line)?);
+        }
+        Ok(Reader {
+            inner,
+            kind,
+            headers,
+            decoder: Decoder::new(),
+            buf: Vec::new(),
+            pos: 0,
+            done: false,
+        })
+    }
+
+    #[inline]
+    pub fn kind(&self) -> &Kind {
+        &self.kind
+    }
+
+    #[inline]
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// Returns the underlying reader. Any input following the block may have been buffered.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner()
+    }
+}
+
+impl<R: Read> Read for Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut line = Vec::new();
+        while (self.pos == self.buf.len()) && !self.done {
+            self.buf.clear();
+            self.pos = 0;
+            line.clear();
+            if self.inner.read_until(b'\n', &mut line)? == 0 {
+                return Err(Error::TRUNCATED.into());
+            }
+            self.done = self.decoder.line(&line, &mut self.buf)?;
+        }
+        let n = (&self.buf[self.pos..]).read(buf)?;
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+impl<'a, R: Read + Send + 'a> IntoData<'a> for Reader<R> {
+    type Output = Data<'a>;
+
+    fn into_data(self) -> Result<Data<'a>> {
+        Data::from_reader(self).map_err(|e| e.error())
+    }
+}
+
+/// A writer that encodes its input as an armored block.
+///
+/// The armor tail is written by `finish`, or when the writer is dropped, in which case any
+/// error is ignored.
+#[derive(Debug)]
+pub struct Writer<W: Write> {
+    inner: Option<W>,
+    kind: Kind,
+    headers: Vec<(String, String)>,
+    started: bool,
+    pending: Vec<u8>,
+    crc: u32,
+}
+
+impl<W: Write> Writer<W> {
+    #[inline]
+    pub fn new(inner: W, kind: Kind) -> Self {
+        Self::with_headers(inner, kind, Vec::new())
+    }
+
+    pub fn with_headers(inner: W, kind: Kind, headers: Vec<(String, String)>) -> Self {
+        Writer {
+            inner: Some(inner),
+            kind,
+            headers,
+            started: false,
+            pending: Vec::new(),
+            crc: CRC24_INIT,
+        }
+    }
+
+    fn start(&mut self) -> io::Result<()> {
+        if self.started {
+            return Ok(());
+        }
+        let inner = self.inner.as_mut().ok_or(Error::INV_VALUE)?;
+        writeln!(inner, "-----BEGIN {}-----", self.kind)?;
+        for (name, value) in &self.headers {
+            writeln!(inner, "{}: {}", name, value)?;
+        }
+        writeln!(inner)?;
+        self.started = true;
+        Ok(())
+    }
+
+    /// Writes the complete lines in the pending input, or all of it if `all` is set.
+    fn write_lines(&mut self, all: bool) -> io::Result<()> {
+        // Each line of 64 characters encodes 48 bytes.
+        let chunk = LINE_LENGTH / 4 * 3;
+        let len = if all {
+            self.pending.len()
+        } else {
+            self.pending.len() / chunk * chunk
+        };
+        let inner = self.inner.as_mut().ok_or(Error::INV_VALUE)?;
+        for line in self.pending[..len].chunks(chunk) {
+            writeln!(inner, "{}", base64::encode(line, 0))?;
+        }
+        self.pending.drain(..len);
+        Ok(())
+    }
+
+    fn finish_(&mut self) -> io::Result<()> {
+        self.start()?;
+        self.write_lines(true)?;
+        let crc = [(self.crc >> 16) as u8, (self.crc >> 8) as u8, self.crc as u8];
+        let inner = self.inner.as_mut().ok_or(Error::INV_VALUE)?;
+        writeln!(inner, "={}", base64::encode(&crc, 0))?;
+        writeln!(inner, "-----END {}-----", self.kind)?;
+        inner.flush()
+    }
+
+    /// Writes the armor tail and returns the underlying writer.
+    pub fn finish(mut self) -> Result<W> {
+        self.finish_()?;
+        self.inner.take().ok_or(Error::INV_VALUE)
+    }
+}
+
+impl<W: Write> Write for Writer<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.start()?;
+        self.crc = crc24(self.crc, buf);
+        self.pending.extend_from_slice(buf);
+        self.write_lines(false)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.as_mut().map_or(Ok(()), |w| w.flush())
+    }
+}
+
+impl<W: Write> Drop for Writer<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.finish_();
+        }
+    }
+}
+
+impl<'a, W: Write + Send> IntoData<'a> for &'a mut Writer<W> {
+    type Output = Data<'a>;
+
+    fn into_data(self) -> Result<Data<'a>> {
+        Data::from_writer(self).map_err(|e| e.error())
+    }
+}
@@ -0,0 +1,440 @@
+//! Adapters between asynchronous streams and [`Data`] objects.
+//!
+//! GPGME operations block the calling thread and access their data objects through
+//! synchronous callbacks. The functions in this module split an `AsyncRead` or `AsyncWrite`
+//! stream into two halves connected by a bounded buffer:
+//!
+//! * a blocking half that implements [`IntoData`] and is passed to the operation, which is
+//!   run on a thread where blocking is allowed (e.g. `tokio::task::spawn_blocking`), and
+//! * a pump future that moves data between the buffer and the stream and has to be polled
+//!   by the async runtime while the operation runs.
+//!
+//! The blocking half waits while the buffer is empty (when reading) or full (when writing)
+//! and the pump waits for the other direction, so neither side can run ahead of the other
+//! by more than the buffer capacity.
+//!
+//! This module requires the `futures-io` feature.
+//!
+//! # Examples
+//!
+//! ```ignore
+//! use gpgme::{async_io, Context, Protocol};
+//!
+//! let (ciphertext, pump_in) = async_io::reader(request_body, 64 * 1024);
+//! let (plaintext, pump_out) = async_io::writer(output_file, 64 * 1024);
+//! let op = tokio::task::spawn_blocking(move || {
+//!     let mut ctx = Context::from_protocol(Protocol::OpenPgp)?;
+//!     ctx.decrypt(ciphertext, plaintext)
+//! });
+//! let (result, read, written) = futures::join!(op, pump_in, pump_out);
+//! ```
+use std::{
+    collections::VecDeque,
+    fmt,
+    future::Future,
+    io::{self, prelude::*},
+    pin::Pin,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    task::{Context as TaskContext, Poll, Waker},
+};
+
+use futures_io::{AsyncRead, AsyncWrite};
+
+use crate::{Data, IntoData, Result};
+
+const MAX_CHUNK: usize = 16 * 1024;
+
+#[derive(Default)]
+struct State {
+    buf: VecDeque<u8>,
+    /// No more data will be added to the buffer.
+    eof: bool,
+    /// No more data will be taken from the buffer.
+    closed: bool,
+    error: Option<(io::ErrorKind, String)>,
+    waker: Option<Waker>,
+}
+
+impl State {
+    fn wake(&mut self) {
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn error(&self) -> Option<io::Error> {
+        self.error
+            .as_ref()
+            .map(|&(kind, ref msg)| io::Error::new(kind, msg.clone()))
+    }
+}
+
+struct Shared {
+    state: Mutex<State>,
+    cond: Condvar,
+    capacity: usize,
+}
+
+impl Shared {
+    fn new(capacity: usize) -> Arc<Self> {
+        Arc::new(Shared {
+            state: Mutex::default(),
+            cond: Condvar::new(),
+            capacity: capacity.max(1),
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn wait<'a>(&self, guard: MutexGuard<'a, State>) -> MutexGuard<'a, State> {
+        self.cond.wait(guard).unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn fail(&self, err: &io::Error) {
+        let mut state = self.lock();
+        state.error = Some((err.kind(), err.to_string()));
+        state.eof = true;
+        state.closed = true;
+        self.cond.notify_all();
+    }
+}
+
+fn broken_pipe() -> io::Error {
+    io::Error::new(io::ErrorKind::BrokenPipe, "stream pump dropped")
+}
+
+/// Splits `stream` into a blocking [`Reader`] and the [`ReadPump`] that feeds it.
+///
+/// At most `capacity` bytes are read from `stream` ahead of the consumer.
+pub fn reader<R>(stream: R, capacity: usize) -> (Reader, ReadPump<R>)
+where R: AsyncRead + Unpin {
+    let shared = Shared::new(capacity);
+    let reader = Reader {
+        shared: shared.clone(),
+    };
+    let pump = ReadPump {
+        stream,
+        shared,
+        chunk: Vec::new(),
+        done: false,
+    };
+    (reader, pump)
+}
+
+/// Splits `stream` into a blocking [`Writer`] and the [`WritePump`] that drains it.
+///
+/// Writes to the [`Writer`] block once `capacity` bytes are waiting to be written to
+/// `stream`. The stream is flushed and closed after the writer has been dropped and all
+/// buffered data has been written.
+pub fn writer<W>(stream: W, capacity: usize) -> (Writer, WritePump<W>)
+where W: AsyncWrite + Unpin {
+    let shared = Shared::new(capacity);
+    let writer = Writer {
+        shared: shared.clone(),
+    };
+    let pump = WritePump {
+        stream,
+        shared,
+        chunk: Vec::new(),
+        closing: false,
+        done: false,
+    };
+    (writer, pump)
+}
+
+/// The blocking half of an [`async_io::reader`](reader).
+pub struct Reader {
+    shared: Arc<Shared>,
+}
+
+impl fmt::Debug for Reader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Reader")
+            .field("capacity", &self.shared.capacity)
+            .finish()
+    }
+}
+
+impl Read for Reader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let mut state = self.shared.lock();
+        loop {
+            if !state.buf.is_empty() {
+                let n = state.buf.len().min(buf.len());
+                for (dst, src) in buf.iter_mut().zip(state.buf.drain(..n)) {
+                    *dst = src;
+                }
+                state.wake();
+                return Ok(n);
+            } else if let Some(err) = state.error() {
+                return Err(err);
+            } else if state.eof {
+                return Ok(0);
+            }
+            state = self.shared.wait(state);
+        }
+    }
+}
+
+impl Drop for Reader {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.closed = true;
+        state.wake();
+    }
+}
+
+impl IntoData<'static> for Reader {
+    type Output = Data<'static>;
+
+    fn into_data(self) -> Result<Data<'static>> {
+        Data::from_reader(self).map_err(|e| e.error())
+    }
+}
+
+/// Reads from an `AsyncRead` stream into the buffer of a [`Reader`].
+///
+/// The future completes once the stream is exhausted or the reader has been dropped.
+pub struct ReadPump<R> {
+    stream: R,
+    shared: Arc<Shared>,
+    chunk: Vec<u8>,
+    done: bool,
+}
+
+impl<R> fmt::Debug for ReadPump<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReadPump")
+            .field("capacity", &self.shared.capacity)
+            .field("done", &self.done)
+            .finish()
+    }
+}
+
+impl<R: AsyncRead + Unpin> Future for ReadPump<R> {
+    type Output = io::Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        loop {
+            let space = {
+                let mut state = this.shared.lock();
+                if state.closed {
+                    this.done = true;
+                    return Poll::Ready(Ok(()));
+                }
+                let space = this.shared.capacity.saturating_sub(state.buf.len());
+                if space == 0 {
+                    state.waker = Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+                space
+            };
+
+            this.chunk.resize(space.min(MAX_CHUNK), 0);
+            match Pin::new(&mut this.stream).poll_read(cx, &mut this.chunk) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Ok(0)) => {
+                    let mut state = this.shared.lock();
+                    state.eof = true;
+                    this.shared.cond.notify_all();
+                    this.done = true;
+                    return Poll::Ready(Ok(()));
+                }
+                Poll::Ready(Ok(n)) => {
+                    let mut state = this.shared.lock();
+                    state.buf.extend(&this.chunk[..n]);
+                    this.shared.cond.notify_all();
+                }
+                Poll::Ready(Err(ref e)) if e.kind() == io::ErrorKind::Interrupted => (),
+                Poll::Ready(Err(e)) => {
+                    this.shared.fail(&e);
+                    this.done = true;
+                    return Poll::Ready(Err(e));
+                }
+            }
+        }
+    }
+}
+
+impl<R> Drop for ReadPump<R> {
+    fn drop(&mut self) {
+        if !self.done {
+            self.shared.fail(&broken_pipe());
+        }
+    }
+}
+
+/// The blocking half of an [`async_io::writer`](writer).
+pub struct Writer {
+    shared: Arc<Shared>,
+}
+
+impl fmt::Debug for Writer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Writer")
+            .field("capacity", &self.shared.capacity)
+            .finish()
+    }
+}
+
+impl Write for Writer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let mut state = self.shared.lock();
+        loop {
+            if let Some(err) = state.error() {
+                return Err(err);
+            } else if state.closed {
+                return Err(broken_pipe());
+            }
+            let space = self.shared.capacity.saturating_sub(state.buf.len());
+            if space > 0 {
+                let n = space.min(buf.len());
+                state.buf.extend(&buf[..n]);
+                state.wake();
+                return Ok(n);
+            }
+            state = self.shared.wait(state);
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let mut state = self.shared.lock();
+        loop {
+            if let Some(err) = state.error() {
+                return Err(err);
+            } else if state.buf.is_empty() {
+                return Ok(());
+            } else if state.closed {
+                return Err(broken_pipe());
+            }
+            state = self.shared.wait(state);
+        }
+    }
+}
+
+impl Drop for Writer {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.eof = true;
+        state.wake();
+    }
+}
+
+impl IntoData<'static> for Writer {
+    type Output = Data<'static>;
+
+    fn into_data(self) -> Result<Data<'static>> {
+        Data::from_writer(self).map_err(|e| e.error())
+    }
+}
+
+/// Writes the contents of the buffer of a [`Writer`] to an `AsyncWrite` stream.
+///
+/// The future completes once the writer has been dropped and the stream has been flushed
+/// and closed.
+pub struct WritePump<W> {
+    stream: W,
+    shared: Arc<Shared>,
+    chunk: Vec<u8>,
+    closing: bool,
+    done: bool,
+}
+
+impl<W> fmt::Debug for WritePump<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WritePump")
+            .field("capacity", &self.shared.capacity)
+            .field("done", &self.done)
+            .finish()
+    }
+}
+
+impl<W: AsyncWrite + Unpin> WritePump<W> {
+    fn finish(&mut self, result: io::Result<()>) -> Poll<io::Result<()>> {
+        self.done = true;
+        match result {
+            Ok(()) => {
+                let mut state = self.shared.lock();
+                state.closed = true;
+                self.shared.cond.notify_all();
+                Poll::Ready(Ok(()))
+            }
+            Err(e) => {
+                self.shared.fail(&e);
+                Poll::Ready(Err(e))
+            }
+        }
+    }
+}
+
+impl<W: AsyncWrite + Unpin> Future for WritePump<W> {
+    type Output = io::Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        while !this.closing {
+            {
+                let mut state = this.shared.lock();
+                if state.buf.is_empty() {
+                    if !state.eof {
+                        state.waker = Some(cx.waker().clone());
+                        return Poll::Pending;
+                    }
+                    this.closing = true;
+                    break;
+                }
+
+                // The stream is polled without holding the lock, so that a slow stream does
+                // not block the writer.
+                let (chunk, _) = state.buf.as_slices();
+                let len = chunk.len().min(MAX_CHUNK);
+                this.chunk.clear();
+                this.chunk.extend_from_slice(&chunk[..len]);
+            }
+
+            match Pin::new(&mut this.stream).poll_write(cx, &this.chunk) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Ok(0)) => {
+                    let err = io::Error::new(io::ErrorKind::WriteZero, "failed to write data");
+                    return this.finish(Err(err));
+                }
+                Poll::Ready(Ok(n)) => {
+                    // Only the pump removes data, so the buffer still starts with the chunk.
+                    this.shared.lock().buf.drain(..n);
+                    this.shared.cond.notify_all();
+                }
+                Poll::Ready(Err(ref e)) if e.kind() == io::ErrorKind::Interrupted => (),
+                Poll::Ready(Err(e)) => return this.finish(Err(e)),
+            }
+        }
+
+        let result = match Pin::new(&mut this.stream).poll_flush(cx) {
+            Poll::Ready(Ok(())) => match Pin::new(&mut this.stream).poll_close(cx) {
+                Poll::Ready(result) => result,
+                Poll::Pending => return Poll::Pending,
+            },
+            Poll::Ready(Err(e)) => Err(e),
+            Poll::Pending => return Poll::Pending,
+        };
+        this.finish(result)
+    }
+}
+
+impl<W> Drop for WritePump<W> {
+    fn drop(&mut self) {
+        if !self.done {
+            self.shared.fail(&broken_pipe());
+        }
+    }
+}
@@ -0,0 +1,458 @@
+//! Encrypted backups of a whole keyring.
+//!
+//! A backup bundle contains all public keys, all secret keys and the owner trust of a
+//! keyring, together with a manifest listing the backed up keys. The bundle is encrypted
+//! either symmetrically with a passphrase or to a set of recipients.
+//!
+//! The owner trust is stored in the format of `gpg --export-ownertrust`.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::fs::File;
+//!
+//! use gpgme::{backup::Protection, Context, Protocol};
+//!
+//! let mut ctx = Context::from_protocol(Protocol::OpenPgp).unwrap();
+//! let output = File::create("keyring.backup").unwrap();
+//! let manifest = ctx.backup(output, Protection::Passphrase(b"correct horse")).unwrap();
+//! println!("backed up {} keys", manifest.keys().len());
+//!
+//! let mut ctx = Context::from_protocol(Protocol::OpenPgp).unwrap();
+//! let input = File::open("keyring.backup").unwrap();
+//! let restored = ctx.restore(input, Some(b"correct horse"), true).unwrap();
+//! for key in restored.manifest.keys() {
+//!     println!("would restore {} ({})", key.fingerprint(), key.user_id());
+//! }
+//! ```
+use std::{
+    collections::{HashMap, HashSet},
+    io::prelude::*,
+    str,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    callbacks::with_passphrase,
+    edit::{self, Editor},
+    Context, Data, EditInteractionStatus, EncryptFlags, Error, ExportMode, ImportResult, Key,
+    Result, Validity,
+};
+
+const MAGIC: &str = "gpgme-backup";
+const VERSION: u32 = 1;
+
+const SECTION_MANIFEST: &str = "manifest";
+const SECTION_PUBLIC: &str = "public";
+const SECTION_SECRET: &str = "secret";
+const SECTION_OWNER_TRUST: &str = "ownertrust";
+
+/// How a backup bundle is protected.
+#[derive(Debug, Copy, Clone)]
+pub enum Protection<'a> {
+    /// Symmetric encryption using a passphrase.
+    Passphrase(&'a [u8]),
+    /// Encryption to the specified keys.
+    Recipients(&'a [Key]),
+}
+
+/// A key listed in the manifest of a backup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyEntry {
+    fingerprint: String,
+    user_id: String,
+    has_secret: bool,
+    owner_trust: Validity,
+}
+
+impl KeyEntry {
+    #[inline]
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    /// Returns the primary user id of the key, or an empty string if it has none.
+    #[inline]
+    pub fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    /// Returns `true` if the backup contains the secret key.
+    #[inline]
+    pub fn has_secret(&self) -> bool {
+        self.has_secret
+    }
+
+    /// Returns the owner trust stored in the backup, or `Validity::Unknown` if none was set.
+    #[inline]
+    pub fn owner_trust(&self) -> Validity {
+        self.owner_trust
+    }
+}
+
+/// The list of keys contained in a backup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    created: SystemTime,
+    keys: Vec<KeyEntry>,
+}
+
+impl Manifest {
+    /// Returns the time the backup was created.
+    #[inline]
+    pub fn created(&self) -> SystemTime {
+        self.created
+    }
+
+    #[inline]
+    pub fn keys(&self) -> &[KeyEntry] {
+        &self.keys
+    }
+
+    /// Returns the keys whose secret key is contained in the backup.
+    pub fn secret_keys(&self) -> impl Iterator<Item = &KeyEntry> {
+        self.keys.iter().filter(|k| k.has_secret)
+    }
+
+    fn to_text(&self) -> String {
+        let created = self
+            .created
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let mut text = format!("created {}\n", created);
+        for key in &self.keys {
+            text.push_str(&format!(
+                "key {} {} {}\n",
+                key.fingerprint,
+                if key.has_secret { "sec" } else { "pub" },
+                key.user_id.replace('\n', " ")
+            ));
+        }
+        text
+    }
+
+    fn parse(text: &str, owner_trust: &HashMap<String, Validity>) -> Result<Self> {
+        let mut created = None;
+        let mut keys = Vec::new();
+        for line in text.lines() {
+            let mut fields = line.splitn(4, ' ');
+            match (fields.next(), fields.next(), fields.next(), fields.next()) {
+                (Some("created"), Some(secs), None, None) => {
+                    let secs = secs.parse().map_err(|_| Error::BAD_DATA)?;
+                    created = Some(UNIX_EPOCH + Duration::from_secs(secs));
+                }
+                (Some("key"), Some(fpr), Some(kind), user_id) => {
+                    let has_secret = match kind {
+                        "sec" => true,
+                        "pub" => false,
+                        _ => return Err(Error::BAD_DATA),
+                    };
+                    keys.push(KeyEntry {
+                        fingerprint: fpr.to_owned(),
+                        user_id: user_id.unwrap_or("").to_owned(),
+                        has_secret,
+                        owner_trust: owner_trust
+                            .get(fpr)
+                            .cloned()
+                            .unwrap_or(Validity::Unknown),
+                    });
+                }
+                _ => return Err(Error::BAD_DATA),
+            }
+        }
+        Ok(Manifest {
+            created: created.ok_or(Error::BAD_DATA)?,
+            keys,
+        })
+    }
+}
+
+/// The result of restoring a backup.
+#[derive(Debug, Clone)]
+pub struct Restored {
+    pub manifest: Manifest,
+    /// The result of importing the keys, or `None` for a dry run.
+    pub import: Option<ImportResult>,
+    /// The number of keys whose owner trust was changed.
+    pub owner_trust_changed: usize,
+}
+
+/// Maps an owner trust to the value used by `gpg --export-ownertrust`.
+fn trust_value(trust: Validity) -> Option<u8> {
+    match trust {
+        Validity::Undefined => Some(2),
+        Validity::Never => Some(3),
+        Validity::Marginal => Some(4),
+        Validity::Full => Some(5),
+        Validity::Ultimate => Some(6),
+        _ => None,
+    }
+}
+
+fn trust_from_value(value: u8) -> Option<Validity> {
+    match value & 0x0f {
+        2 => Some(Validity::Undefined),
+        3 => Some(Validity::Never),
+        4 => Some(Validity::Marginal),
+        5 => Some(Validity::Full),
+        6 => Some(Validity::Ultimate),
+        _ => None,
+    }
+}
+
+fn parse_owner_trust(text: &str) -> Result<HashMap<String, Validity>> {
+    let mut result = HashMap::new();
+    for line in text.lines() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.split(':');
+        let fpr = fields.next().unwrap_or("");
+        let value = fields
+            .next()
+            .and_then(|v| v.parse().ok())
+            .ok_or(Error::BAD_DATA)?;
+        if let Some(trust) = trust_from_value(value) {
+            result.insert(fpr.to_owned(), trust);
+        }
+    }
+    Ok(result)
+}
+
+fn push_section(bundle: &mut Vec<u8>, name: &str, data: &[u8]) {
+    bundle.extend_from_slice(format!("{} {}\n", name, data.len()).as_bytes());
+    bundle.extend_from_slice(data);
+    bundle.push(b'\n');
+}
+
+fn parse_sections(mut bundle: &[u8]) -> Result<HashMap<&str, &[u8]>> {
+    fn next_line<'a>(data: &mut &'a [u8]) -> Result<&'a str> {
+        let end = data
+            .iter()
+            .position(|&b| b == b'\n')
+            .ok_or(Error::TRUNCATED)?;
+        let line = str::from_utf8(&data[..end]).map_err(|_| Error::BAD_DATA)?;
+        *data = &data[(end + 1)..];
+        Ok(line)
+    }
+
+    let header = next_line(&mut bundle)?;
+    if !header.starts_with(MAGIC) {
+        return Err(Error::BAD_DATA);
+    }
+    if header[MAGIC.len()..].trim().parse::<u32>() != Ok(VERSION) {
+        return Err(Error::UNSUPPORTED_PROTOCOL);
+    }
+
+    let mut sections = HashMap::new();
+    while !bundle.is_empty() {
+        let line = next_line(&mut bundle)?;
+        let mut fields = line.splitn(2, ' ');
+        let name = fields.next().unwrap_or("");
+        let len = fields
+            .next()
+            .and_then(|l| l.parse::<usize>().ok())
+            .ok_or(Error::BAD_DATA)?;
+        if bundle.len() <= len {
+            return Err(Error::TRUNCATED);
+        }
+        if bundle[len] != b'\n' {
+            return Err(Error::BAD_DATA);
+        }
+        sections.insert(name, &bundle[..len]);
+        bundle = &bundle[(len + 1)..];
+    }
+    Ok(sections)
+}
+
+pub(crate) fn create(
+    ctx: &mut Context, dst: &mut Data<'_>, protection: Protection<'_>, flags: EncryptFlags,
+) -> Result<Manifest> {
+    let secret = ctx
+        .secret_keys()?
+        .filter_map(|k| k.ok())
+        .filter_map(|k| k.fingerprint().ok().map(|f| f.to_owned()))
+        .collect::<HashSet<_>>();
+    let mut keys = Vec::new();
+    let mut owner_trust = String::new();
+    for key in ctx.keys()? {
+        let key = key?;
+        let fpr = match key.fingerprint() {
+            Ok(fpr) => fpr.to_owned(),
+            Err(_) => continue,
+        };
+        if let Some(value) = trust_value(key.owner_trust()) {
+            owner_trust.push_str(&format!("{}:{}:\n", fpr, value));
+        }
+        keys.push(KeyEntry {
+            has_secret: secret.contains(&fpr),
+            user_id: key
+                .user_ids()
+                .next()
+                .and_then(|u| u.id().ok().map(|s| s.to_owned()))
+                .unwrap_or_default(),
+            owner_trust: key.owner_trust(),
+            fingerprint: fpr,
+        });
+    }
+    // The manifest only records whole seconds.
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let manifest = Manifest {
+        created: UNIX_EPOCH + Duration::from_secs(now),
+        keys,
+    };
+
+    let mut public = Vec::new();
+    ctx.export_all(ExportMode::empty(), &mut public)?;
+    let mut secret = Vec::new();
+    if manifest.secret_keys().next().is_some() {
+        ctx.export_all(ExportMode::SECRET, &mut secret)?;
+    }
+
+    let mut bundle = format!("{} {}\n", MAGIC, VERSION).into_bytes();
+    push_section(&mut bundle, SECTION_MANIFEST, manifest.to_text().as_bytes());
+    push_section(&mut bundle, SECTION_PUBLIC, &public);
+    push_section(&mut bundle, SECTION_SECRET, &secret);
+    push_section(&mut bundle, SECTION_OWNER_TRUST, owner_trust.as_bytes());
+
+    match protection {
+        Protection::Passphrase(passphrase) => with_passphrase(ctx, passphrase, |ctx| {
+            ctx.encrypt_symmetric_with_flags(&bundle, dst, flags)
+        })?,
+        Protection::Recipients(recipients) => {
+            if recipients.is_empty() {
+                return Err(Error::INV_VALUE);
+            }
+            ctx.encrypt_with_flags(recipients, &bundle, dst, flags)?;
+        }
+    }
+    Ok(manifest)
+}
+
+pub(crate) fn restore(
+    ctx: &mut Context, src: &mut Data<'_>, passphrase: Option<&[u8]>, dry_run: bool,
+) -> Result<Restored> {
+    let mut bundle = Vec::new();
+    match passphrase {
+        Some(passphrase) => {
+            with_passphrase(ctx, passphrase, |ctx| ctx.decrypt(src, &mut bundle))?;
+        }
+        None => {
+            ctx.decrypt(src, &mut bundle)?;
+        }
+    }
+    let sections = parse_sections(&bundle)?;
+    let section = |name| sections.get(name).cloned().ok_or(Error::BAD_DATA);
+    let owner_trust = str::from_utf8(section(SECTION_OWNER_TRUST)?)
+        .map_err(|_| Error::BAD_DATA)
+        .and_then(parse_owner_trust)?;
+    let manifest = str::from_utf8(section(SECTION_MANIFEST)?)
+        .map_err(|_| Error::BAD_DATA)
+        .and_then(|m| Manifest::parse(m, &owner_trust))?;
+    if dry_run {
+        return Ok(Restored {
+            manifest,
+            import: None,
+            owner_trust_changed: 0,
+        });
+    }
+
+    let mut keys = section(SECTION_PUBLIC)?.to_vec();
+    keys.extend_from_slice(section(SECTION_SECRET)?);
+    let import = ctx.import(keys)?;
+
+    let mut owner_trust_changed = 0;
+    for entry in &manifest.keys {
+        let value = match trust_value(entry.owner_trust) {
+            Some(value) => value,
+            None => continue,
+        };
+        let key = ctx.get_key(&entry.fingerprint)?;
+        if key.owner_trust() == entry.owner_trust {
+            continue;
+        }
+        // The menu of the key edit interface starts at "I don't know" (undefined).
+        let editor = OwnerTrustEditor { value: value - 1 };
+        ctx.edit_key_with(&key, editor, Vec::new())?;
+        owner_trust_changed += 1;
+    }
+    Ok(Restored {
+        manifest,
+        import: Some(import),
+        owner_trust_changed,
+    })
+}
+
+const TRUST_VALUE: &str = "edit_ownertrust.value";
+const CONFIRM_ULTIMATE: &str = "edit_ownertrust.set_ultimate.okay";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum OwnerTrustEditorState {
+    Start,
+    Trust,
+    Value,
+    ConfirmUltimate,
+    Quit,
+    Save,
+}
+
+impl Default for OwnerTrustEditorState {
+    fn default() -> Self {
+        OwnerTrustEditorState::Start
+    }
+}
+
+/// Sets the owner trust of a key using the key edit interface.
+#[derive(Debug)]
+struct OwnerTrustEditor {
+    value: u8,
+}
+
+impl Editor for OwnerTrustEditor {
+    type State = OwnerTrustEditorState;
+
+    fn next_state(
+        state: Result<Self::State>, status: EditInteractionStatus<'_>, need_response: bool,
+    ) -> Result<Self::State> {
+        use self::OwnerTrustEditorState as State;
+
+        if !need_response {
+            return state;
+        }
+
+        if status.args() == Ok(edit::PROMPT) {
+            match state {
+                Ok(State::Start) => Ok(State::Trust),
+                Ok(State::Value) | Ok(State::ConfirmUltimate) | Ok(State::Quit) => {
+                    Ok(State::Quit)
+                }
+                Err(_) => Ok(State::Quit),
+                _ => Err(Error::GENERAL),
+            }
+        } else if (status.args() == Ok(TRUST_VALUE)) && (state == Ok(State::Trust)) {
+            Ok(State::Value)
+        } else if (status.args() == Ok(CONFIRM_ULTIMATE)) && (state == Ok(State::Value)) {
+            Ok(State::ConfirmUltimate)
+        } else if (status.args() == Ok(edit::CONFIRM_SAVE)) && (state == Ok(State::Quit)) {
+            Ok(State::Save)
+        } else {
+            state.and(Err(Error::GENERAL))
+        }
+    }
+
+    fn action<W: Write>(&self, state: Self::State, mut out: W) -> Result<()> {
+        use self::OwnerTrustEditorState as State;
+
+        match state {
+            State::Trust => out.write_all(b"trust")?,
+            State::Value => write!(out, "{}", self.value)?,
+            State::ConfirmUltimate => out.write_all(edit::YES.as_bytes())?,
+            State::Quit => out.write_all(edit::QUIT.as_bytes())?,
+            State::Save => out.write_all(edit::YES.as_bytes())?,
+            _ => return Err(Error::GENERAL),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes `data` using the standard alphabet with padding, breaking lines after `wrap`
+/// characters using CRLF if `wrap` is not zero.
+pub fn encode(data: &[u8], wrap: usize) -> String {
+    let mut result = String::with_capacity((data.len() + 2) / 3 * 4 + data.len() / 24);
+    let mut line = 0;
+    for chunk in data.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (u32::from(b) << (16 - 8 * i)));
+        for i in 0..4 {
+            if (wrap != 0) && (line == wrap) {
+                result.push_str("\r\n");
+                line = 0;
+            }
+            if i <= chunk.len() {
+                result.push(ALPHABET[((n >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                result.push('=');
+            }
+            line += 1;
+        }
+    }
+    result
+}
+
+/// Decodes `data`, ignoring whitespace. Returns `None` if the input is malformed.
+pub fn decode(data: &[u8]) -> Option<Vec<u8>> {
+    let mut result = Vec::with_capacity(data.len() / 4 * 3);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    let mut padding = 0;
+    for &c in data.iter().filter(|c| !c.is_ascii_whitespace()) {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => {
+                padding += 1;
+                continue;
+            }
+            _ => return None,
+        };
+        if padding > 0 {
+            return None;
+        }
+        acc = (acc << 6) | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            result.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    if (padding > 2) || (bits >= 6) {
+        return None;
+    }
+    Some(result)
+}
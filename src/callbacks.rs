@@ -9,7 +9,7 @@ use std::{
 use ffi;
 use libc;
 
-use crate::{edit, utils::FdWriter, Data, Error};
+use crate::{edit, secure::SecureBuffer, utils::FdWriter, Context, Data, Error, PinentryMode};
 
 #[derive(Debug, Copy, Clone)]
 pub struct PassphraseRequest<'a> {
@@ -37,6 +37,11 @@ impl<'a> PassphraseRequest<'a> {
     }
 }
 
+/// Supplies passphrases requested by the engine.
+///
+/// The passphrase written to `out` is collected in a
+/// [`SecureBuffer`](crate::secure::SecureBuffer), which is wiped once it has been handed to the
+/// engine.
 pub trait PassphraseProvider: UnwindSafe + Send {
     fn get_passphrase<W: io::Write>(
         &mut self, request: PassphraseRequest<'_>, out: W,
@@ -91,10 +96,14 @@ pub extern "C" fn passphrase_cb<P: PassphraseProvider>(
             desc: info.as_ref().map(|s| CStr::from_ptr(s)),
             prev_attempt_failed: was_bad != 0,
         };
-        let mut writer = FdWriter::new(fd);
+        let mut passphrase = SecureBuffer::new();
         let result = provider
-            .get_passphrase(info, &mut writer)
-            .and_then(|_| writer.write_all(b"\n").map_err(Error::from))
+            .get_passphrase(info, &mut passphrase)
+            .and_then(|_| {
+                passphrase.write_all(b"\n")?;
+                FdWriter::new(fd).write_all(passphrase.as_bytes())?;
+                Ok(())
+            })
             .err()
             .map_or(0, |err| err.raw());
         (provider, result)
@@ -110,6 +119,25 @@ pub extern "C" fn passphrase_cb<P: PassphraseProvider>(
     }
 }
 
+/// Runs `f` with a passphrase provider that always returns `passphrase`.
+pub(crate) fn with_passphrase<R>(
+    ctx: &mut Context, passphrase: &[u8], f: impl FnOnce(&mut Context) -> Result<R, Error>,
+) -> Result<R, Error> {
+    let mode = ctx.pinentry_mode();
+    // Older engines always use the passphrase callback.
+    let _ = ctx.set_pinentry_mode(PinentryMode::Loopback);
+    let passphrase = SecureBuffer::from(passphrase);
+    let result = ctx.with_passphrase_provider(
+        move |_: PassphraseRequest<'_>, out: &mut dyn io::Write| {
+            out.write_all(passphrase.as_bytes())?;
+            Ok(())
+        },
+        f,
+    );
+    let _ = ctx.set_pinentry_mode(mode);
+    result
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct ProgressInfo<'a> {
     what: Option<&'a CStr>,
@@ -0,0 +1,359 @@
+//! Declarative context configuration.
+//!
+//! A [`ContextConfig`] captures the settings that are otherwise applied to a [`Context`]
+//! one call at a time, so that every part of a program can create identically configured
+//! contexts. With the `serde` feature enabled it can be deserialized from any format
+//! supported by serde, e.g. TOML:
+//!
+//! ```toml
+//! protocol = "openpgp"
+//! home_dir = "/var/lib/service/gnupg"
+//! armor = true
+//! pinentry_mode = "loopback"
+//! key_list_mode = ["local", "sigs"]
+//! sender = "service@example.org"
+//! signers = ["A0FF4590BB6122EDEF6E3C542D727CC768697734"]
+//!
+//! [flags]
+//! no-symkey-cache = "1"
+//! ```
+use std::collections::BTreeMap;
+
+use crate::{Context, Error, KeyListMode, PinentryMode, Protocol, Result};
+
+const PROTOCOLS: [(&str, Protocol); 7] = [
+    ("openpgp", Protocol::OpenPgp),
+    ("cms", Protocol::Cms),
+    ("gpgconf", Protocol::GpgConf),
+    ("assuan", Protocol::Assuan),
+    ("g13", Protocol::G13),
+    ("uiserver", Protocol::UiServer),
+    ("spawn", Protocol::Spawn),
+];
+
+#[cfg(feature = "serde")]
+const PINENTRY_MODES: [(&str, PinentryMode); 5] = [
+    ("default", PinentryMode::Default),
+    ("ask", PinentryMode::Ask),
+    ("cancel", PinentryMode::Cancel),
+    ("error", PinentryMode::Error),
+    ("loopback", PinentryMode::Loopback),
+];
+
+const KEY_LIST_MODES: [(&str, KeyListMode); 9] = [
+    ("local", KeyListMode::LOCAL),
+    ("extern", KeyListMode::EXTERN),
+    ("sigs", KeyListMode::SIGS),
+    ("sig-notations", KeyListMode::SIG_NOTATIONS),
+    ("with-secret", KeyListMode::WITH_SECRET),
+    ("with-tofu", KeyListMode::WITH_TOFU),
+    ("ephemeral", KeyListMode::EPHEMERAL),
+    ("validate", KeyListMode::VALIDATE),
+    ("locate", KeyListMode::LOCATE),
+];
+
+/// Settings used to create a [`Context`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use gpgme::{config::ContextConfig, PinentryMode, Protocol};
+///
+/// let config = ContextConfig::new(Protocol::OpenPgp)
+///     .home_dir("/var/lib/service/gnupg")
+///     .armor(true)
+///     .pinentry_mode(PinentryMode::Loopback)
+///     .signer("A0FF4590BB6122EDEF6E3C542D727CC768697734")
+///     .flag("no-symkey-cache", "1");
+/// let mut ctx = config.build().unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+pub struct ContextConfig {
+    #[cfg_attr(feature = "serde", serde(with = "names::protocol"))]
+    protocol: Protocol,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    engine_path: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    home_dir: Option<String>,
+    armor: bool,
+    text_mode: bool,
+    offline: bool,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "names::pinentry_mode", skip_serializing_if = "Option::is_none")
+    )]
+    pinentry_mode: Option<PinentryMode>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "names::key_list_mode", skip_serializing_if = "Option::is_none")
+    )]
+    key_list_mode: Option<KeyListMode>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    sender: Option<String>,
+    signers: Vec<String>,
+    flags: BTreeMap<String, String>,
+}
+
+impl Default for ContextConfig {
+    fn default() -> Self {
+        ContextConfig::new(Protocol::OpenPgp)
+    }
+}
+
+impl ContextConfig {
+    pub fn new(protocol: Protocol) -> Self {
+        ContextConfig {
+            protocol,
+            engine_path: None,
+            home_dir: None,
+            armor: false,
+            text_mode: false,
+            offline: false,
+            pinentry_mode: None,
+            key_list_mode: None,
+            sender: None,
+            signers: Vec::new(),
+            flags: BTreeMap::new(),
+        }
+    }
+
+    #[inline]
+    pub fn protocol(&self) -> Protocol {
+        self.protocol
+    }
+
+    /// Sets the path of the engine executable.
+    pub fn engine_path(mut self, path: impl Into<String>) -> Self {
+        self.engine_path = Some(path.into());
+        self
+    }
+
+    /// Sets the home directory used by the engine.
+    pub fn home_dir(mut self, home_dir: impl Into<String>) -> Self {
+        self.home_dir = Some(home_dir.into());
+        self
+    }
+
+    pub fn armor(mut self, enabled: bool) -> Self {
+        self.armor = enabled;
+        self
+    }
+
+    pub fn text_mode(mut self, enabled: bool) -> Self {
+        self.text_mode = enabled;
+        self
+    }
+
+    pub fn offline(mut self, enabled: bool) -> Self {
+        self.offline = enabled;
+        self
+    }
+
+    pub fn pinentry_mode(mut self, mode: PinentryMode) -> Self {
+        self.pinentry_mode = Some(mode);
+        self
+    }
+
+    pub fn key_list_mode(mut self, mode: KeyListMode) -> Self {
+        self.key_list_mode = Some(mode);
+        self
+    }
+
+    pub fn sender(mut self, sender: impl Into<String>) -> Self {
+        self.sender = Some(sender.into());
+        self
+    }
+
+    /// Adds the secret key with the specified fingerprint to the list of signers.
+    pub fn signer(mut self, fpr: impl Into<String>) -> Self {
+        self.signers.push(fpr.into());
+        self
+    }
+
+    /// Sets a context flag, see [`Context::set_flag`].
+    pub fn flag(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.flags.insert(name.into(), value.into());
+        self
+    }
+
+    /// Checks the configuration for values that can never be applied.
+    ///
+    /// Requirements on the engine version are checked by [`build`](ContextConfig::build).
+    pub fn validate(&self) -> Result<()> {
+        if !PROTOCOLS.iter().any(|p| p.1 == self.protocol) {
+            return Err(Error::UNSUPPORTED_PROTOCOL);
+        }
+        let empty = |s: &Option<String>| s.as_ref().map_or(false, |s| s.is_empty());
+        if empty(&self.engine_path)
+            || empty(&self.home_dir)
+            || empty(&self.sender)
+            || self.signers.iter().any(|s| s.is_empty())
+            || self.flags.keys().any(|s| s.is_empty())
+        {
+            return Err(Error::INV_VALUE);
+        }
+        let known = KEY_LIST_MODES
+            .iter()
+            .fold(KeyListMode::empty(), |acc, m| acc | m.1);
+        if self.key_list_mode.map_or(false, |m| !known.contains(m)) {
+            return Err(Error::INV_VALUE);
+        }
+        Ok(())
+    }
+
+    /// Returns the minimum engine version required by the configuration, if any.
+    fn required_version(&self) -> Option<&'static str> {
+        match self.protocol {
+            Protocol::OpenPgp | Protocol::Cms => (),
+            _ => return None,
+        }
+        let mode = self.key_list_mode.unwrap_or_else(KeyListMode::empty);
+        if mode.contains(KeyListMode::WITH_TOFU) {
+            Some("2.1.10")
+        } else if mode.contains(KeyListMode::WITH_SECRET)
+            || self.pinentry_mode.map_or(false, |m| m != PinentryMode::Default)
+        {
+            Some("2.1")
+        } else {
+            None
+        }
+    }
+
+    /// Creates a context with this configuration.
+    ///
+    /// Returns [`Error::NOT_SUPPORTED`] if the configured engine is too old for one of the
+    /// settings.
+    pub fn build(&self) -> Result<Context> {
+        self.validate()?;
+        let mut ctx = Context::from_protocol(self.protocol)?;
+        if self.engine_path.is_some() || self.home_dir.is_some() {
+            ctx.set_engine_info(
+                self.engine_path.as_ref().map(|s| &**s),
+                self.home_dir.as_ref().map(|s| &**s),
+            )?;
+        }
+        if let Some(version) = self.required_version() {
+            if !ctx.engine_info().check_version(version) {
+                return Err(Error::NOT_SUPPORTED);
+            }
+        }
+
+        ctx.set_armor(self.armor);
+        ctx.set_text_mode(self.text_mode);
+        ctx.set_offline(self.offline);
+        if let Some(mode) = self.pinentry_mode {
+            ctx.set_pinentry_mode(mode)?;
+        }
+        if let Some(mode) = self.key_list_mode {
+            ctx.set_key_list_mode(mode)?;
+        }
+        for (name, value) in &self.flags {
+            ctx.set_flag(&**name, &**value)?;
+        }
+        if let Some(ref sender) = self.sender {
+            ctx.set_sender(&**sender)?;
+        }
+        for fpr in &self.signers {
+            ctx.add_signer_by_fingerprint(&**fpr)?;
+        }
+        Ok(ctx)
+    }
+}
+
+#[cfg(feature = "serde")]
+mod names {
+    use serde::{de, ser, Deserialize, Deserializer, Serializer};
+
+    fn name<T: PartialEq + Copy>(table: &[(&'static str, T)], value: T) -> Option<&'static str> {
+        table.iter().find(|e| e.1 == value).map(|e| e.0)
+    }
+
+    fn value<T: Copy>(table: &[(&str, T)], name: &str) -> Option<T> {
+        table
+            .iter()
+            .find(|e| e.0.eq_ignore_ascii_case(name))
+            .map(|e| e.1)
+    }
+
+    pub mod protocol {
+        use super::*;
+        use crate::Protocol;
+
+        pub fn serialize<S: Serializer>(proto: &Protocol, s: S) -> Result<S::Ok, S::Error> {
+            let name = name(&super::super::PROTOCOLS, *proto)
+                .ok_or_else(|| ser::Error::custom("unsupported protocol"))?;
+            s.serialize_str(name)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Protocol, D::Error> {
+            let s = String::deserialize(d)?;
+            value(&super::super::PROTOCOLS, &s)
+                .ok_or_else(|| de::Error::custom(format!("unknown protocol `{}`", s)))
+        }
+    }
+
+    pub mod pinentry_mode {
+        use super::*;
+        use crate::PinentryMode;
+
+        pub fn serialize<S: Serializer>(
+            mode: &Option<PinentryMode>, s: S,
+        ) -> Result<S::Ok, S::Error> {
+            match *mode {
+                Some(mode) => {
+                    let name = name(&super::super::PINENTRY_MODES, mode)
+                        .ok_or_else(|| ser::Error::custom("unknown pinentry mode"))?;
+                    s.serialize_some(name)
+                }
+                None => s.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            d: D,
+        ) -> Result<Option<PinentryMode>, D::Error> {
+            let s = String::deserialize(d)?;
+            value(&super::super::PINENTRY_MODES, &s)
+                .map(Some)
+                .ok_or_else(|| de::Error::custom(format!("unknown pinentry mode `{}`", s)))
+        }
+    }
+
+    pub mod key_list_mode {
+        use super::*;
+        use crate::KeyListMode;
+
+        pub fn serialize<S: Serializer>(
+            mode: &Option<KeyListMode>, s: S,
+        ) -> Result<S::Ok, S::Error> {
+            match *mode {
+                Some(mode) => {
+                    let names: Vec<_> = super::super::KEY_LIST_MODES
+                        .iter()
+                        .filter(|e| mode.contains(e.1))
+                        .map(|e| e.0)
+                        .collect();
+                    s.serialize_some(&names)
+                }
+                None => s.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            d: D,
+        ) -> Result<Option<KeyListMode>, D::Error> {
+            let mut mode = KeyListMode::empty();
+            for s in Vec::<String>::deserialize(d)? {
+                mode |= value(&super::super::KEY_LIST_MODES, &s).ok_or_else(|| {
+                    de::Error::custom(format!("unknown key list mode `{}`", s))
+                })?;
+            }
+            Ok(Some(mode))
+        }
+    }
+}
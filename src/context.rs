@@ -1,10 +1,10 @@
 use std::{
     borrow::BorrowMut,
     ffi::CStr,
-    fmt,
+    fmt, io,
     iter::FusedIterator,
     str::Utf8Error,
-    time::{SystemTime, UNIX_EPOCH},
+    time::{Duration, SystemTime, UNIX_EPOCH},
     {mem, ptr, result},
 };
 
@@ -13,11 +13,12 @@ use ffi::{self, require_gpgme_ver};
 use libc;
 
 use crate::{
-    callbacks, edit,
+    backup, callbacks, edit,
     engine::EngineInfo,
     error::return_err,
     notation::SignatureNotations,
     results,
+    stream::{DecryptReader, EncryptWriter},
     utils::{CStrArgument, SmallVec},
     Data, EditInteractor, Error, ExportMode, IntoData, Key, KeyListMode, NonNull,
     PassphraseProvider, ProgressHandler, Protocol, Result, SignMode, TrustItem,
@@ -27,6 +28,11 @@ use crate::{
 #[must_use]
 pub struct Context(NonNull<ffi::gpgme_ctx_t>);
 
+// SAFETY: A GPGME context may be used from any thread as long as it is only used by one
+// thread at a time, which `&mut self` receivers guarantee. The callbacks installed by the
+// `with_*` methods are required to be `Send` and only live for the duration of the call.
+unsafe impl Send for Context {}
+
 impl Drop for Context {
     #[inline]
     fn drop(&mut self) {
@@ -97,6 +103,26 @@ impl Context {
         }
     }
 
+    /// Returns the number of certificates included in S/MIME signatures.
+    #[inline]
+    pub fn include_certs(&self) -> i32 {
+        unsafe { ffi::gpgme_get_include_certs(self.as_raw()) as i32 }
+    }
+
+    /// Sets the number of certificates to include in S/MIME signatures, or restores the
+    /// engine's default if `certs` is `None`.
+    ///
+    /// `-2` includes all certificates, `-1` all certificates except the root certificate, `0`
+    /// no certificates and any positive value that many certificates of the chain starting
+    /// with the signer's certificate.
+    #[inline]
+    pub fn set_include_certs(&mut self, certs: Option<i32>) {
+        let certs = certs.map_or(ffi::GPGME_INCLUDE_CERTS_DEFAULT, |n| n as libc::c_int);
+        unsafe {
+            ffi::gpgme_set_include_certs(self.as_raw(), certs);
+        }
+    }
+
     #[inline]
     pub fn get_flag(&self, name: impl CStrArgument) -> result::Result<&str, Option<Utf8Error>> {
         self.get_flag_raw(name)
@@ -723,6 +749,34 @@ impl Context {
         Ok(())
     }
 
+    /// Sets the expiration of a key to `expires` from now, or removes it if `expires` is
+    /// `None`.
+    ///
+    /// If no subkey fingerprints are given, the expiration of the primary key is changed.
+    /// Otherwise, the expiration of each of the specified subkeys is changed.
+    pub fn set_expire<I>(&mut self, key: &Key, expires: Option<Duration>, subkeys: I) -> Result<()>
+    where
+        I: IntoIterator,
+        I::Item: CStrArgument, {
+        let mut indices = Vec::new();
+        for fpr in subkeys {
+            let fpr = fpr.into_cstr();
+            let fpr = fpr.as_ref();
+            let index = key
+                .subkeys()
+                .position(|s| s.fingerprint_raw() == Some(fpr))
+                .ok_or(Error::NOT_FOUND)?;
+            indices.push(index);
+        }
+        if indices.is_empty() {
+            indices.push(0);
+        }
+        for index in indices {
+            self.edit_key_with(key, crate::expiry::ExpireEditor { index, expires }, Vec::new())?;
+        }
+        Ok(())
+    }
+
     #[inline]
     pub fn edit_key<'a, E, D>(&mut self, key: &Key, interactor: E, data: D) -> Result<()>
     where
@@ -887,6 +941,42 @@ impl Context {
         Ok(self.get_result().unwrap())
     }
 
+    /// Writes an encrypted backup of all public keys, secret keys and owner trust values
+    /// to `dst`, returning the manifest of the backup.
+    ///
+    /// Exporting the secret keys may require their passphrases. See the `backup` module for
+    /// details.
+    #[inline]
+    pub fn backup<'a, D>(
+        &mut self, dst: D, protection: backup::Protection<'_>,
+    ) -> Result<backup::Manifest>
+    where D: IntoData<'a> {
+        self.backup_with_flags(dst, protection, crate::EncryptFlags::empty())
+    }
+
+    #[inline]
+    pub fn backup_with_flags<'a, D>(
+        &mut self, dst: D, protection: backup::Protection<'_>, flags: crate::EncryptFlags,
+    ) -> Result<backup::Manifest>
+    where D: IntoData<'a> {
+        let mut dst = dst.into_data()?;
+        backup::create(self, dst.borrow_mut(), protection, flags)
+    }
+
+    /// Restores a backup created by `backup`, importing its keys and owner trust values.
+    ///
+    /// `passphrase` must be given if the backup was encrypted with a passphrase. If
+    /// `dry_run` is `true`, the backup is only decrypted and its manifest is returned
+    /// without changing the keyring.
+    #[inline]
+    pub fn restore<'a, D>(
+        &mut self, src: D, passphrase: Option<&[u8]>, dry_run: bool,
+    ) -> Result<backup::Restored>
+    where D: IntoData<'a> {
+        let mut src = src.into_data()?;
+        backup::restore(self, src.borrow_mut(), passphrase, dry_run)
+    }
+
     #[inline]
     pub fn export_all_extern<I>(&mut self, mode: ExportMode) -> Result<()>
     where
@@ -1033,6 +1123,14 @@ impl Context {
         Ok(())
     }
 
+    /// Looks up the secret key with the specified fingerprint and adds it to the list of
+    /// signers.
+    #[inline]
+    pub fn add_signer_by_fingerprint(&mut self, fpr: impl CStrArgument) -> Result<()> {
+        let key = self.get_secret_key(fpr)?;
+        self.add_signer(&key)
+    }
+
     #[inline]
     pub fn signers(&self) -> Signers<'_> {
         Signers {
@@ -1257,6 +1355,43 @@ impl Context {
         Ok(self.get_result().unwrap())
     }
 
+    /// Returns a writer that encrypts the data written to it for the specified recipients.
+    ///
+    /// The operation runs on a helper thread that takes ownership of the context and writes
+    /// the ciphertext to `ciphertext`. It is aborted if the writer is dropped before
+    /// [`EncryptWriter::finish`] or [`EncryptWriter::into_inner`] is called.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use gpgme::{Context, Protocol};
+    /// use std::{fs::File, io};
+    ///
+    /// let mut ctx = Context::from_protocol(Protocol::OpenPgp).unwrap();
+    /// let key = ctx.find_key("[some key fingerprint]").unwrap();
+    /// let output = File::create("message.gpg").unwrap();
+    /// let mut writer = ctx.encrypt_writer(Some(&key), output);
+    /// io::copy(&mut io::stdin(), &mut writer).unwrap();
+    /// writer.finish().unwrap();
+    /// ```
+    #[inline]
+    pub fn encrypt_writer<'k, I, W>(self, recp: I, ciphertext: W) -> EncryptWriter<W>
+    where
+        I: IntoIterator<Item = &'k Key>,
+        W: io::Write + Send + 'static, {
+        self.encrypt_writer_with_flags(recp, ciphertext, crate::EncryptFlags::empty())
+    }
+
+    pub fn encrypt_writer_with_flags<'k, I, W>(
+        self, recp: I, ciphertext: W, flags: crate::EncryptFlags,
+    ) -> EncryptWriter<W>
+    where
+        I: IntoIterator<Item = &'k Key>,
+        W: io::Write + Send + 'static, {
+        let recp = recp.into_iter().cloned().collect();
+        EncryptWriter::new(self, recp, ciphertext, flags)
+    }
+
     #[inline]
     pub fn encrypt_symmetric<'p, 'c, P, C>(&mut self, plaintext: P, ciphertext: C) -> Result<()>
     where
@@ -1359,6 +1494,28 @@ impl Context {
         Ok(self.get_result().unwrap())
     }
 
+    /// Returns a reader over the plaintext of a message.
+    ///
+    /// The operation runs on a helper thread that takes ownership of the context. See
+    /// [`DecryptReader`] for how the result of the operation is reported.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use gpgme::{Context, Protocol};
+    /// use std::{fs::File, io};
+    ///
+    /// let ctx = Context::from_protocol(Protocol::OpenPgp).unwrap();
+    /// let mut reader = ctx.decrypt_reader(File::open("message.gpg").unwrap());
+    /// io::copy(&mut reader, &mut io::stdout()).unwrap();
+    /// let result = reader.finish().unwrap();
+    /// ```
+    #[inline]
+    pub fn decrypt_reader<C>(self, ciphertext: C) -> DecryptReader
+    where C: IntoData<'static> + Send + 'static {
+        DecryptReader::new(self, ciphertext)
+    }
+
     #[inline]
     pub fn decrypt_with_flags<'c, 'p, C, P>(
         &mut self, ciphertext: C, plaintext: P, flags: crate::DecryptFlags,
@@ -600,3 +600,106 @@ impl IntoData<'static> for File {
         Data::from_seekable_stream(self).map_err(|e| e.error())
     }
 }
+
+/// A sink that accepts at most a fixed number of bytes.
+///
+/// Once the limit is reached, further writes fail with [`Error::EFBIG`], which causes the
+/// operation writing to the sink to be aborted with the same error. This protects against
+/// small inputs that expand to huge outputs, e.g. compressed messages passed to
+/// [`Context::decrypt`](crate::Context::decrypt) or
+/// [`Context::verify_opaque`](crate::Context::verify_opaque).
+///
+/// # Examples
+///
+/// ```no_run
+/// use gpgme::{data::Limited, Context, Error, Protocol};
+///
+/// let mut ctx = Context::from_protocol(Protocol::OpenPgp).unwrap();
+/// let ciphertext = std::fs::read("message.gpg").unwrap();
+/// let mut plaintext = Limited::new(Vec::new(), 10 << 20);
+/// match ctx.decrypt(&ciphertext, &mut plaintext) {
+///     Err(e) if plaintext.is_exceeded() => assert_eq!(e.code(), Error::EFBIG.code()),
+///     result => println!("{:?}", result),
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Limited<W> {
+    inner: W,
+    limit: u64,
+    written: u64,
+    exceeded: bool,
+}
+
+impl<W> Limited<W> {
+    #[inline]
+    pub fn new(inner: W, limit: u64) -> Self {
+        Limited {
+            inner,
+            limit,
+            written: 0,
+            exceeded: false,
+        }
+    }
+
+    #[inline]
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Returns the number of bytes passed on to the underlying writer.
+    #[inline]
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+
+    /// Returns `true` if a write was rejected because the limit was reached.
+    #[inline]
+    pub fn is_exceeded(&self) -> bool {
+        self.exceeded
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for Limited<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let remaining = self.limit - self.written;
+        if remaining == 0 {
+            self.exceeded = true;
+            return Err(Error::EFBIG.into());
+        }
+        let len: u64 = buf.len().value_into().unwrap_or_saturate();
+        let n = self.inner.write(&buf[..len.min(remaining) as usize])?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<'a, W: Write + Send + 'a> IntoData<'a> for &'a mut Limited<W> {
+    type Output = Data<'a>;
+
+    fn into_data(self) -> Result<Data<'a>> {
+        Data::from_writer(self).map_err(|e| e.error())
+    }
+}
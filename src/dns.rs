@@ -0,0 +1,219 @@
+//! Helpers for publishing keys in the DNS using OPENPGPKEY (RFC 7929) and PKA records.
+//!
+//! The records are rendered as zone-file text.
+use std::fmt;
+
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use crate::{base64, openpgp, Context, Error, ExportMode, Fingerprint, Key, Result, UserId};
+
+const ZBASE32: &[u8; 32] = b"ybndrfg8ejkmcpqxot1uwisza345h769";
+
+fn zbase32(data: &[u8]) -> String {
+    let mut result = String::with_capacity(data.len() * 8 / 5 + 1);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for &b in data {
+        acc = (acc << 8) | u32::from(b);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            result.push(ZBASE32[((acc >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        result.push(ZBASE32[((acc << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    result
+}
+
+fn split_addr(addr: &str) -> Result<(&str, &str)> {
+    let i = addr.rfind('@').ok_or(Error::INV_VALUE)?;
+    let (local, domain) = (&addr[..i], addr[(i + 1)..].trim_end_matches('.'));
+    if local.is_empty() || domain.is_empty() {
+        return Err(Error::INV_VALUE);
+    }
+    Ok((local, domain))
+}
+
+fn user_id_addr(user_id: &UserId<'_>) -> Result<String> {
+    user_id
+        .email()
+        .ok()
+        .filter(|e| !e.is_empty())
+        .map(|e| e.to_owned())
+        .ok_or(Error::INV_VALUE)
+}
+
+/// Returns the owner name of the OPENPGPKEY record for `addr`, as specified in RFC 7929.
+///
+/// The local part is hashed as is. Returns an error if `addr` is not an email address.
+pub fn openpgpkey_owner(addr: &str) -> Result<String> {
+    let (local, domain) = split_addr(addr)?;
+    let digest = Sha256::digest(local.as_bytes());
+    Ok(format!(
+        "{}._openpgpkey.{}.",
+        openpgp::hex(&digest[..28]).to_lowercase(),
+        domain
+    ))
+}
+
+/// Returns the owner name of the PKA record for `addr`, as used by GnuPG.
+///
+/// The owner name is the z-base-32 encoded SHA-1 hash of the lowercased local part.
+pub fn pka_owner(addr: &str) -> Result<String> {
+    let (local, domain) = split_addr(addr)?;
+    let digest = Sha1::digest(local.to_lowercase().as_bytes());
+    Ok(format!("{}._pka.{}.", zbase32(&digest), domain))
+}
+
+/// An OPENPGPKEY resource record.
+///
+/// # Examples
+///
+/// ```no_run
+/// use gpgme::{dns::OpenPgpKeyRecord, Context, Protocol};
+///
+/// let mut ctx = Context::from_protocol(Protocol::OpenPgp).unwrap();
+/// let key = ctx.get_key("[some key fingerprint]").unwrap();
+/// let uid = key.user_ids().next().unwrap();
+/// let record = OpenPgpKeyRecord::from_key(&mut ctx, &key, &uid).unwrap();
+/// println!("{}", record);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenPgpKeyRecord {
+    owner: String,
+    keydata: Vec<u8>,
+}
+
+impl OpenPgpKeyRecord {
+    /// Creates a record for `addr` from binary OpenPGP key data.
+    pub fn new(addr: &str, keydata: impl Into<Vec<u8>>) -> Result<Self> {
+        Ok(OpenPgpKeyRecord {
+            owner: openpgpkey_owner(addr)?,
+            keydata: keydata.into(),
+        })
+    }
+
+    /// Creates a record for the address of `user_id` containing a minimal export of `key`.
+    ///
+    /// All user ids other than `user_id` are removed from the exported key to keep the record
+    /// small and to avoid disclosing unrelated addresses.
+    pub fn from_key(ctx: &mut Context, key: &Key, user_id: &UserId<'_>) -> Result<Self> {
+        let addr = user_id_addr(user_id)?;
+        let uid = user_id.id_raw().ok_or(Error::INV_VALUE)?.to_bytes();
+
+        let armor = ctx.armor();
+        ctx.set_armor(false);
+        let mut exported = Vec::new();
+        let result = ctx.export_keys(Some(key), ExportMode::MINIMAL, &mut exported);
+        ctx.set_armor(armor);
+        result?;
+
+        let keydata =
+            openpgp::filter_key(&exported, |u| u == uid, |_| true).ok_or(Error::NOT_FOUND)?;
+        Self::new(&addr, keydata)
+    }
+
+    /// Returns the fully qualified owner name of the record.
+    #[inline]
+    pub fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    #[inline]
+    pub fn keydata(&self) -> &[u8] {
+        &self.keydata
+    }
+
+    /// Returns the record in the generic format of RFC 3597, which is understood by name
+    /// servers that do not know about the OPENPGPKEY type.
+    pub fn to_generic(&self) -> String {
+        let mut result = format!("{} IN TYPE61 \\# {} (", self.owner, self.keydata.len());
+        for chunk in self.keydata.chunks(32) {
+            result.push_str("\n\t");
+            result.push_str(&openpgp::hex(chunk));
+        }
+        result.push_str(" )");
+        result
+    }
+}
+
+impl fmt::Display for OpenPgpKeyRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} IN OPENPGPKEY (", self.owner)?;
+        for line in base64::encode(&self.keydata, 64).split("\r\n") {
+            write!(f, "\n\t{}", line)?;
+        }
+        f.write_str(" )")
+    }
+}
+
+/// A PKA (Public Key Association) TXT record.
+///
+/// These records are checked by the engine during verification, see
+/// `Signature::pka_trust` and `Signature::pka_address`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PkaRecord {
+    owner: String,
+    fingerprint: Fingerprint,
+    uri: Option<String>,
+}
+
+impl PkaRecord {
+    /// Creates a record associating `addr` with the key having the fingerprint `fpr`.
+    pub fn new(addr: &str, fpr: Fingerprint) -> Result<Self> {
+        Ok(PkaRecord {
+            owner: pka_owner(addr)?,
+            fingerprint: fpr,
+            uri: None,
+        })
+    }
+
+    /// Creates a record for the address of `user_id` and the primary key of `key`.
+    pub fn from_key(key: &Key, user_id: &UserId<'_>) -> Result<Self> {
+        let fpr = key.to_fingerprint().ok_or(Error::INV_VALUE)?;
+        Self::new(&user_id_addr(user_id)?, fpr)
+    }
+
+    /// Sets the URI where the key can be retrieved.
+    #[inline]
+    pub fn with_uri(mut self, uri: impl Into<String>) -> Self {
+        self.uri = Some(uri.into());
+        self
+    }
+
+    /// Returns the fully qualified owner name of the record.
+    #[inline]
+    pub fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    #[inline]
+    pub fn fingerprint(&self) -> &Fingerprint {
+        &self.fingerprint
+    }
+
+    #[inline]
+    pub fn uri(&self) -> Option<&str> {
+        self.uri.as_ref().map(|s| &**s)
+    }
+
+    /// Returns the contents of the TXT record.
+    pub fn text(&self) -> String {
+        let mut result = format!("v=pka1;fpr={}", self.fingerprint.as_str());
+        if let Some(ref uri) = self.uri {
+            result.push_str(";uri=");
+            result.push_str(uri);
+        }
+        result
+    }
+}
+
+impl fmt::Display for PkaRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = self.text().replace('\\', "\\\\").replace('"', "\\\"");
+        write!(f, "{} IN TXT \"{}\"", self.owner, text)
+    }
+}
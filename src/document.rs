@@ -0,0 +1,246 @@
+//! Per-value encryption of structured documents, in the style of
+//! [sops](https://github.com/mozilla/sops).
+//!
+//! Selected leaves of a JSON document are replaced with ASCII armored messages containing
+//! their JSON serialization, so the structure of the document and any unencrypted values
+//! stay readable. A metadata block stored under the `"gpgme"` key of the top-level object
+//! records the fingerprints of the recipients, the paths of the encrypted leaves and an
+//! encrypted SHA-256 MAC over all values and those paths, which is checked on decryption.
+//! Only the recorded leaves are decrypted, so plaintext values that merely look like
+//! encrypted ones are left alone.
+//!
+//! The MAC detects values that were removed, reordered or modified, but only provides
+//! authenticity if it is signed, i.e. if signers were configured when encrypting. Without a
+//! signature, anyone with access to the recipients' public keys can produce a valid document.
+//!
+//! This module requires the `serde_json` feature.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use gpgme::{document, Context, Protocol};
+//!
+//! let mut ctx = Context::from_protocol(Protocol::OpenPgp).unwrap();
+//! let key = ctx.get_key("[some key fingerprint]").unwrap();
+//! let mut config: serde_json::Value =
+//!     serde_json::from_str(r#"{"user": "admin", "password": "hunter2"}"#).unwrap();
+//! document::encrypt(&mut ctx, Some(&key), &mut config, |path| path == "password").unwrap();
+//! println!("{}", serde_json::to_string_pretty(&config).unwrap());
+//! ```
+use std::collections::BTreeSet;
+
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    openpgp, utils, Context, EncryptFlags, Error, Fingerprint, Key, Result, VerificationResult,
+};
+
+/// The key of the metadata block in the top-level object.
+pub const METADATA_KEY: &str = "gpgme";
+
+const VERSION: u64 = 1;
+
+fn child_path(parent: &str, key: &str) -> String {
+    if parent.is_empty() {
+        key.to_owned()
+    } else {
+        format!("{}.{}", parent, key)
+    }
+}
+
+/// Calls `f` for every leaf of `value` with the leaf's path, in document order.
+fn visit_leaves(
+    value: &mut Value, path: &str, f: &mut dyn FnMut(&str, &mut Value) -> Result<()>,
+) -> Result<()> {
+    match *value {
+        Value::Object(ref mut map) => {
+            for (key, child) in map.iter_mut() {
+                visit_leaves(child, &child_path(path, key), f)?;
+            }
+            Ok(())
+        }
+        Value::Array(ref mut array) => {
+            for (i, child) in array.iter_mut().enumerate() {
+                visit_leaves(child, &format!("{}[{}]", path, i), f)?;
+            }
+            Ok(())
+        }
+        _ => f(path, value),
+    }
+}
+
+/// Computes the MAC over the leaves of the plaintext document, excluding the metadata, and
+/// the paths of the encrypted leaves.
+fn mac(doc: &mut Value, encrypted: &BTreeSet<String>) -> Result<String> {
+    let mut hasher = Sha256::new();
+    visit_leaves(doc, "", &mut |path, value| {
+        // Quoting the path keeps keys containing `=` or newlines unambiguous.
+        hasher.input(Value::from(path).to_string().as_bytes());
+        hasher.input(b"=");
+        hasher.input(value.to_string().as_bytes());
+        hasher.input(b"\n");
+        Ok(())
+    })?;
+    hasher.input(b"encrypted=");
+    hasher.input(encrypted_paths(encrypted).to_string().as_bytes());
+    hasher.input(b"\n");
+    Ok(openpgp::hex(&hasher.result()))
+}
+
+fn encrypted_paths(encrypted: &BTreeSet<String>) -> Value {
+    Value::Array(encrypted.iter().map(|p| Value::from(p.as_str())).collect())
+}
+
+fn take_metadata(doc: &mut Value) -> Result<Option<Map<String, Value>>> {
+    match doc.as_object_mut() {
+        Some(map) => match map.remove(METADATA_KEY) {
+            Some(Value::Object(metadata)) => Ok(Some(metadata)),
+            Some(_) => Err(Error::INV_VALUE),
+            None => Ok(None),
+        },
+        None => Err(Error::INV_VALUE),
+    }
+}
+
+fn encrypt_armored(
+    ctx: &mut Context, recipients: &[Key], plaintext: &[u8], flags: EncryptFlags, sign: bool,
+) -> Result<String> {
+    let mut ciphertext = Vec::new();
+    utils::with_armor(ctx, true, |ctx| {
+        if sign {
+            ctx.sign_and_encrypt_with_flags(recipients, plaintext, &mut ciphertext, flags)
+                .map(|_| ())
+        } else {
+            ctx.encrypt_with_flags(recipients, plaintext, &mut ciphertext, flags)
+                .map(|_| ())
+        }
+    })?;
+    String::from_utf8(ciphertext).map_err(|_| Error::BAD_DATA)
+}
+
+/// Encrypts the leaves of `doc` whose path is selected by `select` for the specified
+/// recipients and adds the metadata block.
+///
+/// Paths are formed by joining object keys with `.` and appending `[i]` for array elements,
+/// e.g. `servers[0].token`. `null` values are never encrypted. If `ctx` has signers, the
+/// MAC is signed.
+///
+/// `doc` must be an object that is not already encrypted, otherwise `Error::INV_VALUE` is
+/// returned.
+pub fn encrypt<'k, I, F>(ctx: &mut Context, recp: I, doc: &mut Value, select: F) -> Result<()>
+where
+    I: IntoIterator<Item = &'k Key>,
+    F: FnMut(&str) -> bool, {
+    encrypt_with_flags(ctx, recp, doc, EncryptFlags::empty(), select)
+}
+
+/// Like `encrypt`, but passes `flags` to the underlying encryption operations.
+pub fn encrypt_with_flags<'k, I, F>(
+    ctx: &mut Context, recp: I, doc: &mut Value, flags: EncryptFlags, mut select: F,
+) -> Result<()>
+where
+    I: IntoIterator<Item = &'k Key>,
+    F: FnMut(&str) -> bool, {
+    let recipients = recp.into_iter().cloned().collect::<Vec<_>>();
+    let fingerprints = recipients
+        .iter()
+        .map(|k| k.to_fingerprint().map(|f| Value::from(f.as_str())))
+        .collect::<Option<Vec<_>>>()
+        .ok_or(Error::INV_VALUE)?;
+    if fingerprints.is_empty() || doc.get(METADATA_KEY).is_some() || !doc.is_object() {
+        return Err(Error::INV_VALUE);
+    }
+
+    let mut result = doc.clone();
+    let mut encrypted = BTreeSet::new();
+    visit_leaves(&mut result, "", &mut |path, value| {
+        if !value.is_null() && select(path) {
+            encrypted.insert(path.to_owned());
+        }
+        Ok(())
+    })?;
+    let mac = mac(&mut result, &encrypted)?;
+    let sign = ctx.signers().next().is_some();
+    visit_leaves(&mut result, "", &mut |path, value| {
+        if !encrypted.contains(path) {
+            return Ok(());
+        }
+        let plaintext = value.to_string();
+        *value = Value::String(encrypt_armored(
+            ctx,
+            &recipients,
+            plaintext.as_bytes(),
+            flags,
+            false,
+        )?);
+        Ok(())
+    })?;
+
+    let mut metadata = Map::new();
+    metadata.insert("version".to_owned(), VERSION.into());
+    metadata.insert("recipients".to_owned(), Value::Array(fingerprints));
+    metadata.insert("encrypted".to_owned(), encrypted_paths(&encrypted));
+    let mac = encrypt_armored(ctx, &recipients, mac.as_bytes(), flags, sign)?;
+    metadata.insert("mac".to_owned(), Value::String(mac));
+    if let Value::Object(ref mut map) = result {
+        map.insert(METADATA_KEY.to_owned(), Value::Object(metadata));
+    }
+    *doc = result;
+    Ok(())
+}
+
+/// Returns the fingerprints of the recipients recorded in the metadata block of `doc`.
+pub fn recipients(doc: &Value) -> Result<Vec<Fingerprint>> {
+    doc.get(METADATA_KEY)
+        .and_then(|m| m.get("recipients"))
+        .and_then(|r| r.as_array())
+        .ok_or(Error::NOT_FOUND)?
+        .iter()
+        .map(|f| f.as_str().ok_or(Error::INV_VALUE).and_then(Fingerprint::new))
+        .collect()
+}
+
+/// Decrypts the leaves of `doc` recorded as encrypted in the metadata block, verifies the MAC
+/// and removes the metadata block.
+///
+/// Returns the result of verifying the signature of the MAC, which contains no signatures
+/// if the MAC was not signed. `doc` is left unchanged if an error occurs. If the MAC does not
+/// match, `Error::CHECKSUM` is returned.
+pub fn decrypt(ctx: &mut Context, doc: &mut Value) -> Result<VerificationResult> {
+    let mut result = doc.clone();
+    let metadata = take_metadata(&mut result)?.ok_or(Error::NO_DATA)?;
+    if metadata.get("version").and_then(|v| v.as_u64()) != Some(VERSION) {
+        return Err(Error::UNSUPPORTED_PROTOCOL);
+    }
+    let expected = metadata
+        .get("mac")
+        .and_then(|m| m.as_str())
+        .ok_or(Error::NO_DATA)?;
+    let encrypted = metadata
+        .get("encrypted")
+        .and_then(|e| e.as_array())
+        .ok_or(Error::NO_DATA)?
+        .iter()
+        .map(|p| p.as_str().map(|s| s.to_owned()).ok_or(Error::INV_VALUE))
+        .collect::<Result<BTreeSet<_>>>()?;
+
+    visit_leaves(&mut result, "", &mut |path, value| {
+        if !encrypted.contains(path) {
+            return Ok(());
+        }
+        let ciphertext = value.as_str().ok_or(Error::BAD_DATA)?;
+        let mut plaintext = Vec::new();
+        ctx.decrypt(ciphertext, &mut plaintext)?;
+        *value = serde_json::from_slice(&plaintext).map_err(|_| Error::BAD_DATA)?;
+        Ok(())
+    })?;
+
+    let mut mac_plaintext = Vec::new();
+    let (_, verification) = ctx.decrypt_and_verify(expected, &mut mac_plaintext)?;
+    if mac_plaintext != mac(&mut result, &encrypted)?.as_bytes() {
+        return Err(Error::CHECKSUM);
+    }
+    *doc = result;
+    Ok(verification)
+}
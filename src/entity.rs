@@ -0,0 +1,147 @@
+//! Helpers for building and parsing canonical MIME entities, shared by the modules that
+//! produce or consume MIME messages.
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{Error, Result};
+
+pub const CRLF: &[u8] = b"\r\n";
+
+pub fn boundary(contents: &[&[u8]]) -> String {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u64 ^ d.as_secs())
+        .unwrap_or(0);
+    loop {
+        let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let boundary = format!("=-=gpgme-{:x}-{:x}=-=", nanos, count);
+        if !contents
+            .iter()
+            .any(|c| find(c, boundary.as_bytes()).is_some())
+        {
+            return boundary;
+        }
+    }
+}
+
+pub fn push_delimiter(body: &mut Vec<u8>, boundary: &str, close: bool) {
+    if !body.is_empty() && !body.ends_with(CRLF) {
+        body.extend_from_slice(CRLF);
+    }
+    if !body.is_empty() {
+        // The CRLF preceding a delimiter is part of the delimiter.
+        body.extend_from_slice(CRLF);
+    }
+    body.extend_from_slice(b"--");
+    body.extend_from_slice(boundary.as_bytes());
+    if close {
+        body.extend_from_slice(b"--");
+    }
+    body.extend_from_slice(CRLF);
+}
+
+pub fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    if needle.is_empty() || (needle.len() > haystack.len()) {
+        return None;
+    }
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// Splits a canonical entity into its header block and its body.
+pub fn split_entity(entity: &[u8]) -> (&[u8], &[u8]) {
+    if entity.starts_with(CRLF) {
+        return (&[], &entity[2..]);
+    }
+    match find(entity, b"\r\n\r\n") {
+        Some(i) => (&entity[..(i + 2)], &entity[(i + 4)..]),
+        None => (entity, &[]),
+    }
+}
+
+/// Returns the unfolded value of the first header with the specified lower case name.
+pub fn header(headers: &[u8], name: &str) -> Option<String> {
+    let headers = String::from_utf8_lossy(headers);
+    let mut current: Option<String> = None;
+    for line in headers.split("\r\n") {
+        if line.starts_with(' ') || line.starts_with('\t') {
+            if let Some(ref mut value) = current {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+            continue;
+        }
+        if current.is_some() {
+            break;
+        }
+        if let Some(i) = line.find(':') {
+            if line[..i].trim().eq_ignore_ascii_case(name) {
+                current = Some(line[(i + 1)..].trim().to_owned());
+            }
+        }
+    }
+    current
+}
+
+/// Parses a `Content-Type` header value into the lower case MIME type and its parameters.
+pub fn parse_content_type(value: &str) -> (String, Vec<(String, String)>) {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut escaped = false;
+    for c in value.chars() {
+        if escaped {
+            current.push(c);
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ';' if !quoted => fields.push(std::mem::replace(&mut current, String::new())),
+            c => current.push(c),
+        }
+    }
+    fields.push(current);
+
+    let mut fields = fields.into_iter();
+    let mime_type = fields.next().unwrap_or_default().trim().to_ascii_lowercase();
+    let params = fields
+        .filter_map(|f| {
+            let i = f.find('=')?;
+            Some((
+                f[..i].trim().to_ascii_lowercase(),
+                f[(i + 1)..].trim().to_owned(),
+            ))
+        })
+        .collect();
+    (mime_type, params)
+}
+
+/// Splits the body of a canonical multipart entity into its parts.
+pub fn split_multipart<'a>(body: &'a [u8], boundary: &str) -> Result<Vec<&'a [u8]>> {
+    let mut delimiter = b"\r\n--".to_vec();
+    delimiter.extend_from_slice(boundary.as_bytes());
+
+    // Treat the start of the body as if it was preceded by a line break.
+    let mut pos = if body.starts_with(&delimiter[2..]) {
+        delimiter.len() - 2
+    } else {
+        find(body, &delimiter).ok_or(Error::INV_VALUE)? + delimiter.len()
+    };
+    let mut parts = Vec::new();
+    loop {
+        let rest = &body[pos..];
+        if rest.starts_with(b"--") {
+            return Ok(parts);
+        }
+        // Skip transport padding and the line break ending the delimiter line.
+        let start = pos + find(rest, CRLF).ok_or(Error::INV_VALUE)? + 2;
+        let len = find(&body[start..], &delimiter).ok_or(Error::INV_VALUE)?;
+        parts.push(&body[start..(start + len)]);
+        pos = start + len + delimiter.len();
+    }
+}
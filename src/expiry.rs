@@ -0,0 +1,272 @@
+use std::{
+    collections::HashSet,
+    ffi::CString,
+    io::prelude::*,
+    time::{Duration, SystemTime},
+};
+
+use bitflags::bitflags;
+
+use crate::{
+    edit::{self, Editor},
+    Context, EditInteractionStatus, Error, Key, Result, Subkey,
+};
+
+bitflags! {
+    /// The capabilities of a subkey that are lost when it expires.
+    pub struct Capabilities: u32 {
+        const SIGN = 1;
+        const ENCRYPT = 1 << 1;
+        const CERTIFY = 1 << 2;
+        const AUTHENTICATE = 1 << 3;
+    }
+}
+
+impl Capabilities {
+    fn of(subkey: &Subkey<'_>) -> Self {
+        let mut caps = Capabilities::empty();
+        caps.set(Capabilities::SIGN, subkey.can_sign());
+        caps.set(Capabilities::ENCRYPT, subkey.can_encrypt());
+        caps.set(Capabilities::CERTIFY, subkey.can_certify());
+        caps.set(Capabilities::AUTHENTICATE, subkey.can_authenticate());
+        caps
+    }
+}
+
+/// A primary key or subkey that has expired or is about to.
+#[derive(Debug, Clone)]
+pub struct ExpiringKey {
+    key: Key,
+    fingerprint: CString,
+    is_primary: bool,
+    expires: SystemTime,
+    capabilities: Capabilities,
+    has_secret: bool,
+}
+
+impl ExpiringKey {
+    /// Returns the key the expiring (sub)key belongs to.
+    #[inline]
+    pub fn key(&self) -> &Key {
+        &self.key
+    }
+
+    #[inline]
+    pub fn fingerprint(&self) -> &str {
+        self.fingerprint.to_str().unwrap_or("")
+    }
+
+    #[inline]
+    pub fn is_primary(&self) -> bool {
+        self.is_primary
+    }
+
+    #[inline]
+    pub fn expiration_time(&self) -> SystemTime {
+        self.expires
+    }
+
+    #[inline]
+    pub fn is_expired(&self) -> bool {
+        self.expires <= SystemTime::now()
+    }
+
+    /// Returns the time remaining until expiration, or `None` if the key has expired.
+    #[inline]
+    pub fn remaining(&self) -> Option<Duration> {
+        self.expires.duration_since(SystemTime::now()).ok()
+    }
+
+    #[inline]
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
+    /// Returns `true` if the secret part of the (sub)key is available, i.e. the expiration
+    /// can be extended using this keyring.
+    #[inline]
+    pub fn has_secret(&self) -> bool {
+        self.has_secret
+    }
+
+    /// Sets the expiration of the (sub)key to `valid_for` from now.
+    pub fn renew(&self, ctx: &mut Context, valid_for: Duration) -> Result<()> {
+        if !self.has_secret {
+            return Err(Error::NO_SECKEY);
+        }
+        if self.is_primary {
+            ctx.set_expire(&self.key, Some(valid_for), None::<&str>)
+        } else {
+            ctx.set_expire(&self.key, Some(valid_for), Some(&*self.fingerprint))
+        }
+    }
+}
+
+/// A report of the keys in a keyring that expire within a given time.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use gpgme::{expiry::{Capabilities, ExpiryReport}, Context, Protocol};
+///
+/// const DAY: u64 = 24 * 60 * 60;
+///
+/// let mut ctx = Context::from_protocol(Protocol::OpenPgp).unwrap();
+/// let report = ExpiryReport::scan(&mut ctx, Duration::from_secs(30 * DAY)).unwrap();
+/// for entry in report.entries() {
+///     if entry.has_secret() && entry.capabilities().contains(Capabilities::SIGN) {
+///         entry.renew(&mut ctx, Duration::from_secs(365 * DAY)).unwrap();
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ExpiryReport {
+    entries: Vec<ExpiringKey>,
+}
+
+impl ExpiryReport {
+    /// Lists all keys using `ctx` and reports the valid primary keys and subkeys that
+    /// expire within `within` from now, including those that have already expired.
+    pub fn scan(ctx: &mut Context, within: Duration) -> Result<Self> {
+        let secret = ctx
+            .secret_keys()?
+            .filter_map(|k| k.ok())
+            .flat_map(|k| {
+                k.subkeys()
+                    .filter(|s| s.is_secret())
+                    .filter_map(|s| s.fingerprint_raw().map(|f| f.to_owned()))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<HashSet<_>>();
+        let keys = ctx.keys()?.collect::<Result<Vec<_>>>()?;
+        Ok(Self::from_keys(keys, &secret, within))
+    }
+
+    fn from_keys(
+        keys: impl IntoIterator<Item = Key>, secret: &HashSet<CString>, within: Duration,
+    ) -> Self {
+        // A period too large to represent includes every key that expires at all.
+        let deadline = SystemTime::now().checked_add(within);
+        let mut entries = Vec::new();
+        for key in keys {
+            if key.is_revoked() || key.is_invalid() {
+                continue;
+            }
+            for (i, subkey) in key.subkeys().enumerate() {
+                if subkey.is_revoked() || subkey.is_invalid() {
+                    continue;
+                }
+                let (expires, fpr) = match (subkey.expiration_time(), subkey.fingerprint_raw()) {
+                    (Some(expires), Some(fpr)) if deadline.map_or(true, |d| expires <= d) => {
+                        (expires, fpr)
+                    }
+                    _ => continue,
+                };
+                entries.push(ExpiringKey {
+                    key: key.clone(),
+                    fingerprint: fpr.to_owned(),
+                    is_primary: i == 0,
+                    expires,
+                    capabilities: Capabilities::of(&subkey),
+                    has_secret: secret.contains(fpr),
+                });
+            }
+        }
+        entries.sort_by_key(|e| e.expires);
+        ExpiryReport { entries }
+    }
+
+    /// Returns the entries of the report, ordered by expiration time.
+    #[inline]
+    pub fn entries(&self) -> &[ExpiringKey] {
+        &self.entries
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the entries that have already expired.
+    pub fn expired(&self) -> impl Iterator<Item = &ExpiringKey> {
+        self.entries.iter().filter(|e| e.is_expired())
+    }
+
+    /// Returns the entries whose capabilities intersect `caps`.
+    pub fn affecting(&self, caps: Capabilities) -> impl Iterator<Item = &ExpiringKey> {
+        self.entries
+            .iter()
+            .filter(move |e| e.capabilities.intersects(caps))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum ExpireEditorState {
+    Start,
+    Select,
+    Expire,
+    Valid,
+    Quit,
+    Save,
+}
+
+impl Default for ExpireEditorState {
+    fn default() -> Self {
+        ExpireEditorState::Start
+    }
+}
+
+/// Changes the expiration of a single (sub)key using the key edit interface.
+#[derive(Debug)]
+pub(crate) struct ExpireEditor {
+    pub index: usize,
+    pub expires: Option<Duration>,
+}
+
+impl Editor for ExpireEditor {
+    type State = ExpireEditorState;
+
+    fn next_state(
+        state: Result<Self::State>, status: EditInteractionStatus<'_>, need_response: bool,
+    ) -> Result<Self::State> {
+        use self::ExpireEditorState as State;
+
+        if !need_response {
+            return state;
+        }
+
+        if status.args() == Ok(edit::PROMPT) {
+            match state {
+                Ok(State::Start) => Ok(State::Select),
+                Ok(State::Select) => Ok(State::Expire),
+                Ok(State::Valid) | Ok(State::Quit) => Ok(State::Quit),
+                Err(_) => Ok(State::Quit),
+                _ => Err(Error::GENERAL),
+            }
+        } else if (status.args() == Ok(edit::KEY_VALID)) && (state == Ok(State::Expire)) {
+            Ok(State::Valid)
+        } else if (status.args() == Ok(edit::CONFIRM_SAVE)) && (state == Ok(State::Quit)) {
+            Ok(State::Save)
+        } else {
+            state.and(Err(Error::GENERAL))
+        }
+    }
+
+    fn action<W: Write>(&self, state: Self::State, mut out: W) -> Result<()> {
+        use self::ExpireEditorState as State;
+
+        match state {
+            State::Select => write!(out, "key {}", self.index)?,
+            State::Expire => out.write_all(b"expire")?,
+            State::Valid => match self.expires {
+                Some(expires) => write!(out, "seconds={}", expires.as_secs().max(1))?,
+                None => out.write_all(b"0")?,
+            },
+            State::Quit => out.write_all(edit::QUIT.as_bytes())?,
+            State::Save => out.write_all(edit::YES.as_bytes())?,
+            _ => return Err(Error::GENERAL),
+        }
+        Ok(())
+    }
+}
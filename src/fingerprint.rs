@@ -0,0 +1,306 @@
+use std::{
+    convert::TryFrom,
+    ffi::{CStr, CString},
+    fmt,
+    str::FromStr,
+};
+
+use cstr_argument::NulError;
+
+use crate::{utils::CStrArgument, Error, Result};
+
+fn normalize(s: &str) -> Option<String> {
+    let s = s.trim();
+    let s = if s.starts_with("0x") || s.starts_with("0X") {
+        &s[2..]
+    } else {
+        s
+    };
+    let mut result = String::with_capacity(s.len());
+    for c in s.chars().filter(|c| !c.is_whitespace()) {
+        if !c.is_ascii_hexdigit() {
+            return None;
+        }
+        result.push(c.to_ascii_uppercase());
+    }
+    Some(result)
+}
+
+fn to_cstring(hex: String) -> CString {
+    // Only contains hex digits.
+    CString::new(hex).unwrap()
+}
+
+/// A validated OpenPGP v4 (40 hex digits) or v5 (64 hex digits) fingerprint.
+///
+/// Fingerprints are stored in upper case without any separators, so equality is
+/// case-insensitive. The `Display` implementation groups the digits for readability, while
+/// [`as_str`] returns the compact form expected by the engine.
+///
+/// [`as_str`]: #method.as_str
+///
+/// # Examples
+///
+/// ```
+/// use gpgme::Fingerprint;
+///
+/// let fpr: Fingerprint = "0x a0fe f7a4 4a15 0ef7 c35f  b91f 33f2 c51c 9b01 a27c"
+///     .parse()
+///     .unwrap();
+/// assert_eq!(fpr.as_str(), "A0FEF7A44A150EF7C35FB91F33F2C51C9B01A27C");
+/// assert_eq!(fpr.key_id(), "33F2C51C9B01A27C".parse().unwrap());
+/// ```
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fingerprint(CString);
+
+impl Fingerprint {
+    /// Parses a fingerprint, ignoring whitespace and an optional `0x` prefix.
+    pub fn new(s: &str) -> Result<Self> {
+        match normalize(s) {
+            Some(hex) if (hex.len() == 40) || (hex.len() == 64) => {
+                Ok(Fingerprint(to_cstring(hex)))
+            }
+            _ => Err(Error::INV_VALUE),
+        }
+    }
+
+    /// Returns the key version implied by the length of the fingerprint.
+    #[inline]
+    pub fn version(&self) -> u8 {
+        if self.as_str().len() == 64 {
+            5
+        } else {
+            4
+        }
+    }
+
+    /// Returns the fingerprint as upper case hex digits without separators.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        self.0.to_str().unwrap()
+    }
+
+    #[inline]
+    pub fn as_cstr(&self) -> &CStr {
+        &self.0
+    }
+
+    /// Returns the long key id derived from the fingerprint.
+    ///
+    /// For v4 keys this is the low 64 bits of the fingerprint and for v5 keys the high 64
+    /// bits.
+    pub fn key_id(&self) -> KeyId {
+        let hex = self.as_str();
+        let id = if self.version() == 5 {
+            &hex[..16]
+        } else {
+            &hex[(hex.len() - 16)..]
+        };
+        KeyId(to_cstring(id.to_owned()))
+    }
+
+    /// Returns `true` if `id` is the key id of this fingerprint.
+    #[inline]
+    pub fn matches(&self, id: &KeyId) -> bool {
+        self.key_id() == *id
+    }
+}
+
+impl fmt::Debug for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Fingerprint").field(&self.as_str()).finish()
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hex = self.as_str();
+        let (group, half) = if self.version() == 5 {
+            (8, 32)
+        } else {
+            (4, 20)
+        };
+        for (i, chunk) in hex.as_bytes().chunks(group).enumerate() {
+            if i > 0 {
+                f.write_str(if (i * group) == half { "  " } else { " " })?;
+            }
+            f.write_str(std::str::from_utf8(chunk).unwrap())?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Fingerprint {
+    type Err = Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self> {
+        Fingerprint::new(s)
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Fingerprint {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(s: &'a str) -> Result<Self> {
+        Fingerprint::new(s)
+    }
+}
+
+impl PartialEq<str> for Fingerprint {
+    fn eq(&self, other: &str) -> bool {
+        normalize(other).map_or(false, |o| o == self.as_str())
+    }
+}
+
+impl<'a> PartialEq<&'a str> for Fingerprint {
+    #[inline]
+    fn eq(&self, other: &&'a str) -> bool {
+        *self == **other
+    }
+}
+
+impl AsRef<CStr> for Fingerprint {
+    #[inline]
+    fn as_ref(&self) -> &CStr {
+        &self.0
+    }
+}
+
+impl CStrArgument for Fingerprint {
+    type Output = CString;
+
+    #[inline]
+    fn try_into_cstr(self) -> std::result::Result<Self::Output, NulError<Self>> {
+        Ok(self.0)
+    }
+}
+
+impl<'a> CStrArgument for &'a Fingerprint {
+    type Output = &'a CStr;
+
+    #[inline]
+    fn try_into_cstr(self) -> std::result::Result<Self::Output, NulError<Self>> {
+        Ok(&self.0)
+    }
+}
+
+/// A validated long (64-bit) OpenPGP key id.
+///
+/// Short (32-bit) key ids are rejected as they are trivially forgeable. Use
+/// [`Fingerprint::key_id`] to compare a key id against a fingerprint.
+///
+/// [`Fingerprint::key_id`]: struct.Fingerprint.html#method.key_id
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct KeyId(CString);
+
+impl KeyId {
+    /// Parses a key id, ignoring whitespace and an optional `0x` prefix.
+    pub fn new(s: &str) -> Result<Self> {
+        match normalize(s) {
+            Some(hex) if hex.len() == 16 => Ok(KeyId(to_cstring(hex))),
+            _ => Err(Error::INV_VALUE),
+        }
+    }
+
+    /// Returns the key id as upper case hex digits without separators.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        self.0.to_str().unwrap()
+    }
+
+    #[inline]
+    pub fn as_cstr(&self) -> &CStr {
+        &self.0
+    }
+
+    /// Returns `true` if this is the key id of `fpr`.
+    #[inline]
+    pub fn matches(&self, fpr: &Fingerprint) -> bool {
+        fpr.matches(self)
+    }
+}
+
+impl fmt::Debug for KeyId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("KeyId").field(&self.as_str()).finish()
+    }
+}
+
+impl fmt::Display for KeyId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hex = self.as_str();
+        write!(f, "{} {} {} {}", &hex[..4], &hex[4..8], &hex[8..12], &hex[12..])
+    }
+}
+
+impl FromStr for KeyId {
+    type Err = Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self> {
+        KeyId::new(s)
+    }
+}
+
+impl<'a> TryFrom<&'a str> for KeyId {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(s: &'a str) -> Result<Self> {
+        KeyId::new(s)
+    }
+}
+
+impl From<Fingerprint> for KeyId {
+    #[inline]
+    fn from(fpr: Fingerprint) -> Self {
+        fpr.key_id()
+    }
+}
+
+impl<'a> From<&'a Fingerprint> for KeyId {
+    #[inline]
+    fn from(fpr: &'a Fingerprint) -> Self {
+        fpr.key_id()
+    }
+}
+
+impl PartialEq<str> for KeyId {
+    fn eq(&self, other: &str) -> bool {
+        normalize(other).map_or(false, |o| o == self.as_str())
+    }
+}
+
+impl<'a> PartialEq<&'a str> for KeyId {
+    #[inline]
+    fn eq(&self, other: &&'a str) -> bool {
+        *self == **other
+    }
+}
+
+impl AsRef<CStr> for KeyId {
+    #[inline]
+    fn as_ref(&self) -> &CStr {
+        &self.0
+    }
+}
+
+impl CStrArgument for KeyId {
+    type Output = CString;
+
+    #[inline]
+    fn try_into_cstr(self) -> std::result::Result<Self::Output, NulError<Self>> {
+        Ok(self.0)
+    }
+}
+
+impl<'a> CStrArgument for &'a KeyId {
+    type Output = &'a CStr;
+
+    #[inline]
+    fn try_into_cstr(self) -> std::result::Result<Self::Output, NulError<Self>> {
+        Ok(&self.0)
+    }
+}
@@ -0,0 +1,258 @@
+//! Signing and verification of git commit and tag objects.
+//!
+//! Commits carry their signature in a `gpgsig` header, while tags have the signature appended
+//! to the tag message. The functions in this module operate on raw objects as printed by
+//! `git cat-file commit` or `git cat-file tag`.
+use std::fmt;
+
+use crate::{
+    entity, utils, Context, Error, Fingerprint, Result, Signature, SignatureSummary,
+    SigningResult, Validity, VerificationResult,
+};
+
+const SIGNATURE_HEADERS: [&[u8]; 2] = [b"gpgsig", b"gpgsig-sha256"];
+const SIGNATURE_MARKERS: [&[u8]; 4] = [
+    b"-----BEGIN PGP SIGNATURE-----",
+    b"-----BEGIN PGP MESSAGE-----",
+    b"-----BEGIN SIGNED MESSAGE-----",
+    b"-----BEGIN SSH SIGNATURE-----",
+];
+
+/// The status of a signature as reported by git's `%G?` format placeholder.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Status {
+    /// A good signature (`G`).
+    Good,
+    /// A bad signature (`B`).
+    Bad,
+    /// A good signature with unknown validity (`U`).
+    UnknownValidity,
+    /// A good signature that has expired (`X`).
+    ExpiredSignature,
+    /// A good signature made by an expired key (`Y`).
+    ExpiredKey,
+    /// A good signature made by a revoked key (`R`).
+    RevokedKey,
+    /// The signature cannot be checked, e.g. because the key is missing (`E`).
+    Error,
+    /// The object is not signed (`N`).
+    None,
+}
+
+impl Status {
+    /// Determines the status of a signature from its summary, validity and status.
+    pub fn from_signature(sig: &Signature<'_>) -> Status {
+        let summary = sig.summary();
+        let status = sig.status();
+        if status.err().map(|e| e.code()) == Some(Error::BAD_SIGNATURE.code()) {
+            Status::Bad
+        } else if summary.contains(SignatureSummary::KEY_REVOKED) {
+            Status::RevokedKey
+        } else if summary.contains(SignatureSummary::KEY_EXPIRED) {
+            Status::ExpiredKey
+        } else if summary.contains(SignatureSummary::SIG_EXPIRED) {
+            Status::ExpiredSignature
+        } else if status.is_err() {
+            Status::Error
+        } else {
+            match sig.validity() {
+                Validity::Marginal | Validity::Full | Validity::Ultimate => Status::Good,
+                _ => Status::UnknownValidity,
+            }
+        }
+    }
+
+    /// Returns the letter used by git for the status.
+    pub fn as_char(self) -> char {
+        match self {
+            Status::Good => 'G',
+            Status::Bad => 'B',
+            Status::UnknownValidity => 'U',
+            Status::ExpiredSignature => 'X',
+            Status::ExpiredKey => 'Y',
+            Status::RevokedKey => 'R',
+            Status::Error => 'E',
+            Status::None => 'N',
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_char())
+    }
+}
+
+/// The result of verifying a signed object.
+#[derive(Debug, Clone)]
+pub struct Verification {
+    status: Status,
+    fingerprint: Option<Fingerprint>,
+    result: Option<VerificationResult>,
+}
+
+impl Verification {
+    /// Returns the status of the first signature, or `Status::None` if the object is not
+    /// signed.
+    #[inline]
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    /// Returns the fingerprint of the key that made the first signature, if known.
+    #[inline]
+    pub fn fingerprint(&self) -> Option<&Fingerprint> {
+        self.fingerprint.as_ref()
+    }
+
+    /// Returns the result of the verification, or `None` if the object is not signed.
+    #[inline]
+    pub fn result(&self) -> Option<&VerificationResult> {
+        self.result.as_ref()
+    }
+}
+
+fn to_lf(data: &[u8]) -> Vec<u8> {
+    data.iter()
+        .enumerate()
+        .filter(|&(i, &b)| (b != b'\r') || (data.get(i + 1) != Some(&b'\n')))
+        .map(|(_, &b)| b)
+        .collect()
+}
+
+/// Creates an armored detached signature over `payload` in the form expected by git.
+pub fn sign(ctx: &mut Context, payload: &[u8]) -> Result<(Vec<u8>, SigningResult)> {
+    let mut signature = Vec::new();
+    let result = utils::with_armor(ctx, true, |ctx| ctx.sign_detached(payload, &mut signature))?;
+    Ok((to_lf(&signature), result))
+}
+
+/// Signs an unsigned commit object, returning the object with a `gpgsig` header added.
+pub fn sign_commit(ctx: &mut Context, commit: &[u8]) -> Result<(Vec<u8>, SigningResult)> {
+    let (signature, result) = sign(ctx, commit)?;
+    let end = entity::find(commit, b"\n\n").map_or(commit.len(), |i| i + 1);
+    let mut signed = Vec::with_capacity(commit.len() + signature.len() + 64);
+    signed.extend_from_slice(&commit[..end]);
+    if !signed.is_empty() && !signed.ends_with(b"\n") {
+        signed.push(b'\n');
+    }
+    signed.extend_from_slice(b"gpgsig");
+    for line in lines(&signature) {
+        signed.push(b' ');
+        signed.extend_from_slice(line);
+    }
+    if !signed.ends_with(b"\n") {
+        signed.push(b'\n');
+    }
+    signed.extend_from_slice(&commit[end..]);
+    Ok((signed, result))
+}
+
+/// Signs an unsigned tag object, returning the object with the signature appended.
+pub fn sign_tag(ctx: &mut Context, tag: &[u8]) -> Result<(Vec<u8>, SigningResult)> {
+    let mut signed = tag.to_vec();
+    if !signed.is_empty() && !signed.ends_with(b"\n") {
+        signed.push(b'\n');
+    }
+    let (signature, result) = sign(ctx, &signed)?;
+    signed.extend_from_slice(&signature);
+    Ok((signed, result))
+}
+
+/// Splits a signed commit or tag object into the signed payload and the signature.
+///
+/// Returns `None` if the object is not signed.
+pub fn split_signature(object: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+    split_commit_signature(object).or_else(|| split_tag_signature(object))
+}
+
+fn split_commit_signature(object: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+    let end = entity::find(object, b"\n\n").map_or(object.len(), |i| i + 1);
+    let mut payload = Vec::with_capacity(object.len());
+    let mut signature: Option<Vec<u8>> = None;
+    // Whether the current header is a signature header and, if so, whether it is the one
+    // being collected.
+    let mut skipping = false;
+    let mut collecting = false;
+    for line in lines(&object[..end]) {
+        if skipping && line.starts_with(b" ") {
+            if collecting {
+                signature.get_or_insert_with(Vec::new).extend_from_slice(&line[1..]);
+            }
+            continue;
+        }
+        let name = line.split(|&b| b == b' ').next().unwrap_or(&[]);
+        skipping = (name.len() < line.len()) && SIGNATURE_HEADERS.contains(&name);
+        collecting = skipping && signature.is_none();
+        if collecting {
+            signature = Some(line[(name.len() + 1)..].to_vec());
+        } else if !skipping {
+            payload.extend_from_slice(line);
+        }
+    }
+    payload.extend_from_slice(&object[end..]);
+    signature.map(|s| (payload, s))
+}
+
+fn split_tag_signature(object: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+    let mut start = None;
+    let mut pos = 0;
+    for line in lines(object) {
+        if SIGNATURE_MARKERS.iter().any(|m| line.starts_with(m)) {
+            start = Some(pos);
+        }
+        pos += line.len();
+    }
+    start.map(|i| (object[..i].to_vec(), object[i..].to_vec()))
+}
+
+/// Returns the lines of `data` including their line feeds.
+fn lines(data: &[u8]) -> Lines<'_> {
+    Lines(data)
+}
+
+struct Lines<'a>(&'a [u8]);
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<&'a [u8]> {
+        if self.0.is_empty() {
+            return None;
+        }
+        let end = self.0.iter().position(|&b| b == b'\n').map_or(self.0.len(), |i| i + 1);
+        let (line, rest) = self.0.split_at(end);
+        self.0 = rest;
+        Some(line)
+    }
+}
+
+/// Verifies a detached `signature` over `payload`.
+pub fn verify_signature(
+    ctx: &mut Context, payload: &[u8], signature: &[u8],
+) -> Result<Verification> {
+    let result = ctx.verify_detached(signature, payload)?;
+    let (status, fingerprint) = match result.signatures().next() {
+        Some(sig) => (Status::from_signature(&sig), sig.to_fingerprint()),
+        None => (Status::Error, None),
+    };
+    Ok(Verification {
+        status,
+        fingerprint,
+        result: Some(result),
+    })
+}
+
+/// Verifies a signed commit or tag object.
+///
+/// Returns a verification with `Status::None` if the object is not signed.
+pub fn verify(ctx: &mut Context, object: &[u8]) -> Result<Verification> {
+    match split_signature(object) {
+        Some((payload, signature)) => verify_signature(ctx, &payload, &signature),
+        None => Ok(Verification {
+            status: Status::None,
+            fingerprint: None,
+            result: None,
+        }),
+    }
+}
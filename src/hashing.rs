@@ -0,0 +1,241 @@
+//! Computing digests of data while it is processed by an operation.
+//!
+//! A [`HashingData`] wraps a data object and hashes every byte read from or written to it.
+//! Used as the plaintext of [`Context::decrypt`](crate::Context::decrypt),
+//! [`Context::verify_opaque`](crate::Context::verify_opaque) or
+//! [`Context::encrypt`](crate::Context::encrypt), it yields digests of the plaintext without
+//! a second pass over the data.
+use std::{
+    borrow::BorrowMut,
+    fmt,
+    io::{self, prelude::*},
+    marker::PhantomData,
+};
+
+use sha1::Sha1;
+use sha2::{Digest, Sha224, Sha256, Sha384, Sha512};
+
+use crate::{openpgp, Data, Error, HashAlgorithm, IntoData, Result};
+
+#[derive(Clone)]
+enum Hasher {
+    Sha1(Sha1),
+    Sha224(Sha224),
+    Sha256(Sha256),
+    Sha384(Sha384),
+    Sha512(Sha512),
+}
+
+impl Hasher {
+    fn new(algo: HashAlgorithm) -> Result<Self> {
+        match algo {
+            HashAlgorithm::Sha1 => Ok(Hasher::Sha1(Sha1::new())),
+            HashAlgorithm::Sha224 => Ok(Hasher::Sha224(Sha224::new())),
+            HashAlgorithm::Sha256 => Ok(Hasher::Sha256(Sha256::new())),
+            HashAlgorithm::Sha384 => Ok(Hasher::Sha384(Sha384::new())),
+            HashAlgorithm::Sha512 => Ok(Hasher::Sha512(Sha512::new())),
+            _ => Err(Error::DIGEST_ALGO),
+        }
+    }
+
+    fn algorithm(&self) -> HashAlgorithm {
+        match *self {
+            Hasher::Sha1(_) => HashAlgorithm::Sha1,
+            Hasher::Sha224(_) => HashAlgorithm::Sha224,
+            Hasher::Sha256(_) => HashAlgorithm::Sha256,
+            Hasher::Sha384(_) => HashAlgorithm::Sha384,
+            Hasher::Sha512(_) => HashAlgorithm::Sha512,
+        }
+    }
+
+    fn input(&mut self, data: &[u8]) {
+        match *self {
+            Hasher::Sha1(ref mut h) => h.input(data),
+            Hasher::Sha224(ref mut h) => h.input(data),
+            Hasher::Sha256(ref mut h) => h.input(data),
+            Hasher::Sha384(ref mut h) => h.input(data),
+            Hasher::Sha512(ref mut h) => h.input(data),
+        }
+    }
+
+    fn result(self) -> Vec<u8> {
+        match self {
+            Hasher::Sha1(h) => h.result().to_vec(),
+            Hasher::Sha224(h) => h.result().to_vec(),
+            Hasher::Sha256(h) => h.result().to_vec(),
+            Hasher::Sha384(h) => h.result().to_vec(),
+            Hasher::Sha512(h) => h.result().to_vec(),
+        }
+    }
+}
+
+/// The digests computed by a [`HashingData`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Digests {
+    len: u64,
+    values: Vec<(HashAlgorithm, Vec<u8>)>,
+}
+
+impl Digests {
+    /// Returns the number of bytes that were hashed.
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, algo: HashAlgorithm) -> Option<&[u8]> {
+        self.values
+            .iter()
+            .find(|v| v.0 == algo)
+            .map(|v| &v.1[..])
+    }
+
+    /// Returns the digest computed with `algo` as a lowercase hex string.
+    pub fn get_hex(&self, algo: HashAlgorithm) -> Option<String> {
+        self.get(algo).map(|d| openpgp::hex(d).to_lowercase())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (HashAlgorithm, &[u8])> + '_ {
+        self.values.iter().map(|v| (v.0, &v.1[..]))
+    }
+}
+
+/// A data object that computes digests of the data passing through it.
+///
+/// Only the SHA-1 and SHA-2 algorithms are supported.
+///
+/// # Examples
+///
+/// ```no_run
+/// use gpgme::{hashing::HashingData, Context, HashAlgorithm, Protocol};
+///
+/// let mut ctx = Context::from_protocol(Protocol::OpenPgp).unwrap();
+/// let ciphertext = std::fs::read("message.gpg").unwrap();
+/// let mut plaintext = Vec::new();
+/// let mut sink = HashingData::new(&mut plaintext, &[HashAlgorithm::Sha256]).unwrap();
+/// let result = ctx.decrypt(&ciphertext, &mut sink).unwrap();
+/// let digests = sink.finish();
+/// println!("{:?} {}", result, digests.get_hex(HashAlgorithm::Sha256).unwrap());
+/// ```
+pub struct HashingData<'a, D = Data<'a>> {
+    data: D,
+    hashers: Vec<Hasher>,
+    len: u64,
+    _phantom: PhantomData<Data<'a>>,
+}
+
+impl<'a, D> fmt::Debug for HashingData<'a, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HashingData")
+            .field(
+                "algorithms",
+                &self.hashers.iter().map(Hasher::algorithm).collect::<Vec<_>>(),
+            )
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl<'a, D: BorrowMut<Data<'a>>> HashingData<'a, D> {
+    /// Wraps `data`, computing a digest with each of the algorithms in `algos`.
+    ///
+    /// Returns [`Error::DIGEST_ALGO`] if one of the algorithms is not supported.
+    pub fn new<T>(data: T, algos: &[HashAlgorithm]) -> Result<Self>
+    where T: IntoData<'a, Output = D> {
+        let hashers = algos
+            .iter()
+            .map(|&algo| Hasher::new(algo))
+            .collect::<Result<_>>()?;
+        Ok(HashingData {
+            data: data.into_data()?,
+            hashers,
+            len: 0,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Wraps `data`, computing its SHA-256 digest.
+    #[inline]
+    pub fn sha256<T>(data: T) -> Result<Self>
+    where T: IntoData<'a, Output = D> {
+        HashingData::new(data, &[HashAlgorithm::Sha256])
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &Data<'a> {
+        self.data.borrow()
+    }
+
+    pub fn algorithms(&self) -> impl Iterator<Item = HashAlgorithm> + '_ {
+        self.hashers.iter().map(Hasher::algorithm)
+    }
+
+    /// Returns the digests of the data processed so far.
+    pub fn digests(&self) -> Digests {
+        Digests {
+            len: self.len,
+            values: self
+                .hashers
+                .iter()
+                .map(|h| (h.algorithm(), h.clone().result()))
+                .collect(),
+        }
+    }
+
+    /// Returns the digests of all processed data, releasing the wrapped data object.
+    pub fn finish(self) -> Digests {
+        Digests {
+            len: self.len,
+            values: self
+                .hashers
+                .into_iter()
+                .map(|h| (h.algorithm(), h.result()))
+                .collect(),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        for hasher in &mut self.hashers {
+            hasher.input(data);
+        }
+        self.len += data.len() as u64;
+    }
+}
+
+impl<'a, D: BorrowMut<Data<'a>>> Read for HashingData<'a, D> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.data.borrow_mut().read(buf)?;
+        self.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+impl<'a, D: BorrowMut<Data<'a>>> Write for HashingData<'a, D> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.data.borrow_mut().write(buf)?;
+        self.update(&buf[..n]);
+        Ok(n)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.data.borrow_mut().flush()
+    }
+}
+
+impl<'a, 'b, D> IntoData<'b> for &'b mut HashingData<'a, D>
+where
+    'a: 'b,
+    D: BorrowMut<Data<'a>> + Send,
+{
+    type Output = Data<'b>;
+
+    fn into_data(self) -> Result<Data<'b>> {
+        Data::from_stream(self).map_err(|e| e.error())
+    }
+}
@@ -218,6 +218,18 @@ impl Key {
         }
     }
 
+    /// Returns the fingerprint as a validated `Fingerprint`.
+    #[inline]
+    pub fn to_fingerprint(&self) -> Option<crate::Fingerprint> {
+        self.fingerprint().ok().and_then(|s| s.parse().ok())
+    }
+
+    /// Returns the key id as a validated `KeyId`.
+    #[inline]
+    pub fn to_key_id(&self) -> Option<crate::KeyId> {
+        self.id().ok().and_then(|s| s.parse().ok())
+    }
+
     #[inline]
     pub fn key_list_mode(&self) -> KeyListMode {
         unsafe { KeyListMode::from_bits_truncate((*self.as_raw()).keylist_mode) }
@@ -308,6 +320,18 @@ impl<'key> Subkey<'key> {
         unsafe { (*self.as_raw()).fpr.as_ref().map(|s| CStr::from_ptr(s)) }
     }
 
+    /// Returns the fingerprint as a validated `Fingerprint`.
+    #[inline]
+    pub fn to_fingerprint(&self) -> Option<crate::Fingerprint> {
+        self.fingerprint().ok().and_then(|s| s.parse().ok())
+    }
+
+    /// Returns the key id as a validated `KeyId`.
+    #[inline]
+    pub fn to_key_id(&self) -> Option<crate::KeyId> {
+        self.id().ok().and_then(|s| s.parse().ok())
+    }
+
     #[inline]
     pub fn creation_time(&self) -> Option<SystemTime> {
         let timestamp = unsafe { (*self.as_raw()).timestamp };
@@ -19,6 +19,7 @@ pub use self::{
     data::{Data, IntoData},
     engine::EngineInfo,
     error::{Error, Result},
+    fingerprint::{Fingerprint, KeyId},
     flags::*,
     keys::{Key, Subkey, UserId, UserIdSignature},
     notation::SignatureNotation,
@@ -35,17 +36,46 @@ pub use ffi::require_gpgme_ver;
 
 #[macro_use]
 mod utils;
+pub mod archive;
+#[cfg(feature = "futures-io")]
+pub mod async_io;
+pub mod autocrypt;
+pub mod backup;
+pub mod armor;
+mod base64;
 mod callbacks;
+pub mod config;
 pub mod context;
 pub mod data;
+pub mod dns;
+#[cfg(feature = "serde_json")]
+pub mod document;
 pub mod edit;
 pub mod engine;
+mod entity;
+pub mod expiry;
+pub mod fingerprint;
+pub mod git;
+pub mod hashing;
 mod flags;
 pub mod keys;
+pub mod lint;
+pub mod manifest;
+pub mod mime;
 pub mod notation;
+mod openpgp;
+pub mod packets;
+pub mod policy;
+pub mod pool;
+pub mod resolver;
 pub mod results;
+pub mod secure;
+pub mod smime;
+pub mod store;
+pub mod stream;
 pub mod tofu;
 pub mod trust;
+pub mod wot;
 
 ffi_enum_wrapper! {
     #[doc="A cryptographic protocol that may be used with the library."]
@@ -0,0 +1,394 @@
+use std::{
+    fmt,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    openpgp::{self, be_len, hex, next_packet, next_subpacket},
+    Context, ExportMode, HashAlgorithm, Key, KeyAlgorithm, KeyListMode, Result, Subkey,
+};
+
+/// How serious a `Finding` is.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Severity::Info => f.write_str("info"),
+            Severity::Warning => f.write_str("warning"),
+            Severity::Error => f.write_str("error"),
+        }
+    }
+}
+
+/// A problem found by a `Linter`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Issue {
+    /// The key is shorter than the configured minimum for its algorithm.
+    ShortKey {
+        algorithm: KeyAlgorithm,
+        length: usize,
+        required: usize,
+    },
+    /// The key uses DSA or ElGamal.
+    LegacyAlgorithm(KeyAlgorithm),
+    /// The key has no valid subkey that can be used for encryption.
+    NoEncryptionSubkey,
+    /// The primary key is also used for encryption.
+    PrimaryKeyEncrypts,
+    /// The key does not expire.
+    NoExpiration,
+    /// The key expires later than the configured horizon.
+    ExpiresTooLate(SystemTime),
+    /// The key has already expired.
+    Expired(SystemTime),
+    /// A self-signature was made using SHA-1 (or a weaker hash algorithm).
+    WeakSelfSignature(HashAlgorithm),
+    /// The user id has no valid self-signature.
+    MissingSelfSignature,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Issue::ShortKey {
+                algorithm,
+                length,
+                required,
+            } => write!(
+                f,
+                "{} key is {} bits, at least {} required",
+                algorithm, length, required
+            ),
+            Issue::LegacyAlgorithm(algo) => write!(f, "uses legacy algorithm {}", algo),
+            Issue::NoEncryptionSubkey => f.write_str("no usable encryption subkey"),
+            Issue::PrimaryKeyEncrypts => f.write_str("primary key is used for encryption"),
+            Issue::NoExpiration => f.write_str("does not expire"),
+            Issue::ExpiresTooLate(t) => write!(f, "expires too far in the future ({:?})", t),
+            Issue::Expired(t) => write!(f, "expired ({:?})", t),
+            Issue::WeakSelfSignature(h) => write!(f, "self-signature uses {}", h),
+            Issue::MissingSelfSignature => f.write_str("no valid self-signature"),
+        }
+    }
+}
+
+/// A single result of linting a key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    severity: Severity,
+    issue: Issue,
+    subkey: Option<String>,
+    user_id: Option<String>,
+}
+
+impl Finding {
+    #[inline]
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    #[inline]
+    pub fn issue(&self) -> &Issue {
+        &self.issue
+    }
+
+    /// Returns the fingerprint of the subkey the finding applies to, if any.
+    #[inline]
+    pub fn subkey(&self) -> Option<&str> {
+        self.subkey.as_ref().map(|s| &**s)
+    }
+
+    /// Returns the user id the finding applies to, if any.
+    #[inline]
+    pub fn user_id(&self) -> Option<&str> {
+        self.user_id.as_ref().map(|s| &**s)
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.severity)?;
+        if let Some(ref subkey) = self.subkey {
+            write!(f, "subkey {}: ", subkey)?;
+        }
+        if let Some(ref uid) = self.user_id {
+            write!(f, "user id {:?}: ", uid)?;
+        }
+        write!(f, "{}", self.issue)
+    }
+}
+
+/// Audits keys for weak or misconfigured settings.
+///
+/// # Examples
+///
+/// ```no_run
+/// use gpgme::{lint::{Linter, Severity}, Context, Protocol};
+///
+/// let mut ctx = Context::from_protocol(Protocol::OpenPgp).unwrap();
+/// let key = ctx.get_key("[some key fingerprint]").unwrap();
+/// for finding in Linter::new().lint_with_context(&mut ctx, &key).unwrap() {
+///     if finding.severity() >= Severity::Warning {
+///         println!("{}", finding);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Linter {
+    min_rsa_bits: usize,
+    min_dsa_bits: usize,
+    expiry_horizon: Option<Duration>,
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Linter {
+            min_rsa_bits: 2048,
+            min_dsa_bits: 2048,
+            expiry_horizon: Some(Duration::from_secs(3 * 365 * 24 * 60 * 60)),
+        }
+    }
+}
+
+impl Linter {
+    /// Creates a linter requiring RSA and DSA keys of at least 2048 bits and expiration
+    /// within three years.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn min_rsa_bits(mut self, bits: usize) -> Self {
+        self.min_rsa_bits = bits;
+        self
+    }
+
+    pub fn min_dsa_bits(mut self, bits: usize) -> Self {
+        self.min_dsa_bits = bits;
+        self
+    }
+
+    /// Sets how far in the future keys may expire. If `None`, keys that never expire are
+    /// accepted.
+    pub fn expiry_horizon(mut self, horizon: Option<Duration>) -> Self {
+        self.expiry_horizon = horizon;
+        self
+    }
+
+    /// Lints the key using only the information contained in the key listing.
+    ///
+    /// User ids are only checked for self-signatures if the key was listed using
+    /// `KeyListMode::SIGS`. Self-signature hash algorithms are not available from a key
+    /// listing; use [`lint_with_context`] to check them.
+    ///
+    /// [`lint_with_context`]: #method.lint_with_context
+    pub fn lint(&self, key: &Key) -> Vec<Finding> {
+        let now = SystemTime::now();
+        let mut findings = Vec::new();
+        let mut can_encrypt = false;
+        for (i, subkey) in key.subkeys().enumerate() {
+            if subkey.is_revoked() || subkey.is_invalid() {
+                continue;
+            }
+            if subkey.can_encrypt() && !subkey.is_expired() && !subkey.is_disabled() {
+                can_encrypt = true;
+            }
+            self.lint_subkey(&subkey, i == 0, now, &mut findings);
+        }
+        if !can_encrypt {
+            findings.push(Finding {
+                severity: Severity::Warning,
+                issue: Issue::NoEncryptionSubkey,
+                subkey: None,
+                user_id: None,
+            });
+        }
+
+        if key.key_list_mode().contains(KeyListMode::SIGS) {
+            let primary_id = key.id().ok();
+            for uid in key.user_ids().filter(|u| !u.is_revoked()) {
+                let valid = uid.signatures().any(|sig| {
+                    (0x10..=0x13).contains(&sig.cert_class())
+                        && !sig.is_revocation()
+                        && !sig.is_invalid()
+                        && !sig.is_expired()
+                        && (sig.status().code() == 0)
+                        && primary_id.map_or(false, |id| {
+                            sig.signer_key_id()
+                                .map_or(false, |s| s.eq_ignore_ascii_case(id))
+                        })
+                });
+                if !valid {
+                    findings.push(Finding {
+                        severity: Severity::Error,
+                        issue: Issue::MissingSelfSignature,
+                        subkey: None,
+                        user_id: uid.id().ok().map(|s| s.to_owned()),
+                    });
+                }
+            }
+        }
+        findings.sort_by(|a, b| b.severity.cmp(&a.severity));
+        findings
+    }
+
+    /// Lints the key after listing its signatures and exporting it using `ctx` so that
+    /// user id self-signatures can be checked as well.
+    pub fn lint_with_context(&self, ctx: &mut Context, key: &Key) -> Result<Vec<Finding>> {
+        let mode = ctx.key_list_mode();
+        ctx.set_key_list_mode(mode | KeyListMode::SIGS)?;
+        let refreshed = ctx.refresh_key(key);
+        ctx.set_key_list_mode(mode)?;
+        let key = refreshed?;
+
+        let armor = ctx.armor();
+        ctx.set_armor(false);
+        let mut exported = Vec::new();
+        let result = ctx.export_keys(Some(&key), ExportMode::empty(), &mut exported);
+        ctx.set_armor(armor);
+        result?;
+
+        let mut findings = self.lint(&key);
+        let primary_id = key.id().unwrap_or("");
+        for (uid, hash) in self_signature_hashes(&exported, primary_id) {
+            if is_weak_hash(hash) {
+                findings.push(Finding {
+                    severity: Severity::Error,
+                    issue: Issue::WeakSelfSignature(hash),
+                    subkey: None,
+                    user_id: uid,
+                });
+            }
+        }
+        findings.sort_by(|a, b| b.severity.cmp(&a.severity));
+        Ok(findings)
+    }
+
+    fn lint_subkey(
+        &self, subkey: &Subkey<'_>, primary: bool, now: SystemTime, findings: &mut Vec<Finding>,
+    ) {
+        let fpr = subkey.fingerprint().ok().map(|s| s.to_owned());
+        let mut push = |severity, issue| {
+            findings.push(Finding {
+                severity,
+                issue,
+                subkey: fpr.clone(),
+                user_id: None,
+            })
+        };
+
+        let algorithm = subkey.algorithm();
+        let required = match algorithm {
+            KeyAlgorithm::Rsa | KeyAlgorithm::RsaEncrypt | KeyAlgorithm::RsaSign => {
+                Some(self.min_rsa_bits)
+            }
+            KeyAlgorithm::Dsa => Some(self.min_dsa_bits),
+            _ => None,
+        };
+        if let Some(required) = required {
+            if subkey.length() < required {
+                push(
+                    Severity::Error,
+                    Issue::ShortKey {
+                        algorithm,
+                        length: subkey.length(),
+                        required,
+                    },
+                );
+            }
+        }
+        match algorithm {
+            KeyAlgorithm::Dsa | KeyAlgorithm::Elgamal | KeyAlgorithm::ElgamalEncrypt => {
+                push(Severity::Warning, Issue::LegacyAlgorithm(algorithm));
+            }
+            _ => (),
+        }
+        if primary && subkey.can_encrypt() {
+            push(Severity::Warning, Issue::PrimaryKeyEncrypts);
+        }
+
+        match subkey.expiration_time() {
+            Some(expires) if expires <= now => {
+                let severity = if primary {
+                    Severity::Error
+                } else {
+                    Severity::Warning
+                };
+                push(severity, Issue::Expired(expires));
+            }
+            Some(expires) => {
+                // A horizon too large to represent does not limit the expiration time.
+                let limit = self.expiry_horizon.and_then(|h| now.checked_add(h));
+                if limit.map_or(false, |limit| expires > limit) {
+                    push(Severity::Warning, Issue::ExpiresTooLate(expires));
+                }
+            }
+            None => {
+                if self.expiry_horizon.is_some() {
+                    push(Severity::Info, Issue::NoExpiration);
+                }
+            }
+        }
+    }
+}
+
+fn is_weak_hash(hash: HashAlgorithm) -> bool {
+    match hash {
+        HashAlgorithm::Md2 | HashAlgorithm::Md4 | HashAlgorithm::Md5 | HashAlgorithm::Sha1 => true,
+        _ => false,
+    }
+}
+
+/// Returns the hash algorithm of every v4 user id certification issued by the key with the
+/// specified id, along with the user id it certifies.
+fn self_signature_hashes(mut data: &[u8], key_id: &str) -> Vec<(Option<String>, HashAlgorithm)> {
+    let mut result = Vec::new();
+    let mut uid = None;
+    while let Some((tag, body, rest)) = next_packet(data) {
+        data = rest;
+        match tag {
+            openpgp::TAG_USER_ID => uid = Some(String::from_utf8_lossy(body).into_owned()),
+            openpgp::TAG_PUBLIC_SUBKEY | openpgp::TAG_USER_ATTRIBUTE => uid = None,
+            openpgp::TAG_SIGNATURE if uid.is_some() && (body.len() > 5) && (body[0] == 4) => {
+                if !(0x10..=0x13).contains(&body[1]) {
+                    continue;
+                }
+                if signature_issuer(body).map_or(false, |id| id.eq_ignore_ascii_case(key_id)) {
+                    let hash = unsafe { HashAlgorithm::from_raw(body[3].into()) };
+                    result.push((uid.clone(), hash));
+                }
+            }
+            _ => (),
+        }
+    }
+    result
+}
+
+fn signature_issuer(body: &[u8]) -> Option<String> {
+    let hashed_len = be_len(body.get(4..6)?);
+    let hashed = body.get(6..(6 + hashed_len))?;
+    let rest = &body[(6 + hashed_len)..];
+    let unhashed_len = be_len(rest.get(..2)?);
+    let unhashed = rest.get(2..(2 + unhashed_len))?;
+
+    issuer_subpacket(hashed).or_else(|| issuer_subpacket(unhashed))
+}
+
+fn issuer_subpacket(mut data: &[u8]) -> Option<String> {
+    while let Some((typ, value, rest)) = next_subpacket(data) {
+        data = rest;
+        match typ & 0x7f {
+            // Issuer
+            16 if value.len() == 8 => return Some(hex(value)),
+            // Issuer fingerprint
+            33 if value.len() > 8 => return Some(hex(&value[(value.len() - 8)..])),
+            _ => (),
+        }
+    }
+    None
+}
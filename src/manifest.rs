@@ -0,0 +1,331 @@
+//! Clearsigned checksum manifests in the format of `sha256sum`, e.g. `SHA256SUMS.asc`.
+use std::{
+    fs::{self, File},
+    io::{self, prelude::*},
+    path::{Component, Path, PathBuf},
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{openpgp, Context, Error, Result, SigningResult, VerificationResult};
+
+/// A file listed in a `Manifest`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    path: String,
+    digest: [u8; 32],
+}
+
+impl Entry {
+    /// Returns the path of the file relative to the base directory, using `/` as separator.
+    #[inline]
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the SHA-256 digest of the file.
+    #[inline]
+    pub fn digest(&self) -> &[u8; 32] {
+        &self.digest
+    }
+}
+
+/// A list of files and their SHA-256 digests.
+///
+/// # Examples
+///
+/// ```no_run
+/// use gpgme::{manifest::Manifest, Context, Protocol};
+///
+/// let mut ctx = Context::from_protocol(Protocol::OpenPgp).unwrap();
+/// let manifest = Manifest::from_dir("dist").unwrap();
+/// let (signed, _) = manifest.sign(&mut ctx).unwrap();
+/// std::fs::write("SHA256SUMS.asc", signed).unwrap();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Manifest {
+    entries: Vec<Entry>,
+}
+
+fn hash(mut reader: impl Read) -> Result<[u8; 32]> {
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 8192];
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => hasher.input(&buf[..n]),
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+            Err(e) => return Err(Error::from(e)),
+        }
+    }
+    let mut digest = [0u8; 32];
+    digest.copy_from_slice(&hasher.result());
+    Ok(digest)
+}
+
+/// Converts a relative path to the form used in the manifest, rejecting paths that could
+/// refer to files outside of the base directory.
+fn normalize(path: &Path) -> Result<String> {
+    let mut result = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(c) => result.push(c.to_str().ok_or(Error::INV_VALUE)?),
+            Component::CurDir => (),
+            _ => return Err(Error::INV_VALUE),
+        }
+    }
+    if result.is_empty() {
+        return Err(Error::INV_VALUE);
+    }
+    Ok(result.join("/"))
+}
+
+fn parse_hex(s: &str) -> Option<[u8; 32]> {
+    if (s.len() != 64) || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let mut digest = [0u8; 32];
+    for (i, b) in digest.iter_mut().enumerate() {
+        *b = u8::from_str_radix(&s[(2 * i)..(2 * i + 2)], 16).ok()?;
+    }
+    Some(digest)
+}
+
+fn unescape(s: &str) -> Option<String> {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next()? {
+                'n' => result.push('\n'),
+                '\\' => result.push('\\'),
+                _ => return None,
+            }
+        } else {
+            result.push(c);
+        }
+    }
+    Some(result)
+}
+
+impl Manifest {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hashes the specified files, given relative to `base`.
+    pub fn from_files<I>(base: impl AsRef<Path>, files: I) -> Result<Self>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<Path>, {
+        let mut manifest = Manifest::new();
+        for file in files {
+            manifest.add_file(base.as_ref(), file)?;
+        }
+        Ok(manifest)
+    }
+
+    /// Hashes all regular files below `dir`, sorted by path. Symbolic links are not
+    /// followed.
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Self> {
+        fn walk(dir: &Path, prefix: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+            for entry in fs::read_dir(dir)? {
+                let entry = entry?;
+                let file_type = entry.file_type()?;
+                let path = prefix.join(entry.file_name());
+                if file_type.is_dir() {
+                    walk(&entry.path(), &path, files)?;
+                } else if file_type.is_file() {
+                    files.push(path);
+                }
+            }
+            Ok(())
+        }
+
+        let mut files = Vec::new();
+        walk(dir.as_ref(), Path::new(""), &mut files)?;
+        files.sort();
+        Self::from_files(dir, files)
+    }
+
+    /// Hashes the file at `path`, which is relative to `base`, and adds it to the manifest.
+    pub fn add_file(&mut self, base: impl AsRef<Path>, path: impl AsRef<Path>) -> Result<()> {
+        let name = normalize(path.as_ref())?;
+        let digest = hash(File::open(base.as_ref().join(path))?)?;
+        self.entries.push(Entry { path: name, digest });
+        Ok(())
+    }
+
+    /// Hashes the contents of `reader` and adds them to the manifest under `path`.
+    pub fn add_reader(&mut self, path: impl AsRef<Path>, reader: impl Read) -> Result<()> {
+        let name = normalize(path.as_ref())?;
+        let digest = hash(reader)?;
+        self.entries.push(Entry { path: name, digest });
+        Ok(())
+    }
+
+    #[inline]
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Parses a checksum list as produced by `sha256sum`.
+    ///
+    /// Returns an error if a line is malformed or if a path is absolute or contains `..`.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut entries = Vec::new();
+        for line in text.lines() {
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (escaped, line) = if line.starts_with('\\') {
+                (true, &line[1..])
+            } else {
+                (false, line)
+            };
+            if (line.len() < 66) || !line.is_char_boundary(64) || !line.is_char_boundary(66) {
+                return Err(Error::INV_VALUE);
+            }
+            let digest = parse_hex(&line[..64]).ok_or(Error::INV_VALUE)?;
+            if (&line[64..66] != "  ") && (&line[64..66] != " *") {
+                return Err(Error::INV_VALUE);
+            }
+            let path = if escaped {
+                unescape(&line[66..]).ok_or(Error::INV_VALUE)?
+            } else {
+                line[66..].to_owned()
+            };
+            entries.push(Entry {
+                path: normalize(Path::new(&path))?,
+                digest,
+            });
+        }
+        Ok(Manifest { entries })
+    }
+
+    /// Returns the checksum list in the format used by `sha256sum`.
+    pub fn to_text(&self) -> String {
+        let mut result = String::new();
+        for entry in &self.entries {
+            // Like `sha256sum`, escape names containing backslashes or line feeds and mark
+            // the line with a leading backslash.
+            let escaped = entry.path.contains(|c| (c == '\\') || (c == '\n'));
+            if escaped {
+                result.push('\\');
+            }
+            result.push_str(&openpgp::hex(&entry.digest).to_lowercase());
+            result.push_str("  ");
+            if escaped {
+                result.push_str(&entry.path.replace('\\', "\\\\").replace('\n', "\\n"));
+            } else {
+                result.push_str(&entry.path);
+            }
+            result.push('\n');
+        }
+        result
+    }
+
+    /// Clearsigns the checksum list using the signers configured in `ctx`.
+    pub fn sign(&self, ctx: &mut Context) -> Result<(Vec<u8>, SigningResult)> {
+        let mut signed = Vec::new();
+        let result = ctx.sign_clear(self.to_text(), &mut signed)?;
+        Ok((signed, result))
+    }
+}
+
+/// The state of a single file after verifying a manifest.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FileStatus {
+    /// The digest of the file matches.
+    Ok,
+    /// The digest of the file does not match.
+    Mismatch,
+    /// The file does not exist.
+    Missing,
+    /// The file could not be read.
+    Unreadable,
+}
+
+/// The result of checking a single file listed in a manifest.
+#[derive(Debug, Clone)]
+pub struct FileCheck {
+    entry: Entry,
+    status: FileStatus,
+}
+
+impl FileCheck {
+    #[inline]
+    pub fn entry(&self) -> &Entry {
+        &self.entry
+    }
+
+    #[inline]
+    pub fn path(&self) -> &str {
+        &self.entry.path
+    }
+
+    #[inline]
+    pub fn status(&self) -> FileStatus {
+        self.status
+    }
+}
+
+/// The result of verifying a signed manifest.
+#[derive(Debug, Clone)]
+pub struct Verified {
+    pub manifest: Manifest,
+    pub result: VerificationResult,
+    pub files: Vec<FileCheck>,
+}
+
+impl Verified {
+    /// Returns `true` if all signatures are good and all files match.
+    pub fn is_ok(&self) -> bool {
+        let mut signatures = self.result.signatures().peekable();
+        signatures.peek().is_some()
+            && signatures.all(|s| s.status().is_ok())
+            && self.files.iter().all(|f| f.status == FileStatus::Ok)
+    }
+
+    /// Returns the files whose status is not `FileStatus::Ok`.
+    pub fn failures(&self) -> impl Iterator<Item = &FileCheck> {
+        self.files.iter().filter(|f| f.status != FileStatus::Ok)
+    }
+}
+
+/// Verifies a clearsigned manifest and checks every listed file relative to `base`.
+///
+/// The files are checked regardless of the status of the signatures, which must be checked
+/// by the caller, e.g. using `Verified::is_ok`.
+pub fn verify(ctx: &mut Context, signed: &[u8], base: impl AsRef<Path>) -> Result<Verified> {
+    let mut text = Vec::new();
+    let result = ctx.verify_opaque(signed, &mut text)?;
+    let text = String::from_utf8(text).map_err(|_| Error::BAD_DATA)?;
+    let manifest = Manifest::parse(&text)?;
+    let files = manifest
+        .entries
+        .iter()
+        .map(|entry| {
+            let status = match File::open(base.as_ref().join(&entry.path)) {
+                Ok(file) => match hash(file) {
+                    Ok(ref digest) if digest == &entry.digest => FileStatus::Ok,
+                    Ok(_) => FileStatus::Mismatch,
+                    Err(_) => FileStatus::Unreadable,
+                },
+                Err(ref e) if e.kind() == io::ErrorKind::NotFound => FileStatus::Missing,
+                Err(_) => FileStatus::Unreadable,
+            };
+            FileCheck {
+                entry: entry.clone(),
+                status,
+            }
+        })
+        .collect();
+    Ok(Verified {
+        manifest,
+        result,
+        files,
+    })
+}
@@ -0,0 +1,401 @@
+//! OpenPGP/MIME ([RFC 3156]) message construction and parsing.
+//!
+//! All messages produced by this module use CRLF line endings. Input messages are converted
+//! to canonical CRLF form before they are parsed so that signatures over entities received
+//! with bare LF line endings can still be verified.
+//!
+//! [RFC 3156]: https://tools.ietf.org/html/rfc3156
+use crate::{
+    entity::{
+        boundary, find, header, parse_content_type, push_delimiter, split_entity,
+        split_multipart, CRLF,
+    },
+    utils::with_armor,
+    Context, DecryptionResult, EncryptFlags, EncryptionResult, Error, HashAlgorithm, Key, Result,
+    SigningResult, VerificationResult,
+};
+
+/// A top-level MIME entity produced by `sign`, `encrypt` or `sign_and_encrypt`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MimeMessage {
+    content_type: String,
+    headers: Vec<(&'static str, String)>,
+    body: Vec<u8>,
+}
+
+impl MimeMessage {
+    pub(crate) fn new(content_type: String, body: Vec<u8>) -> Self {
+        MimeMessage {
+            content_type,
+            headers: Vec::new(),
+            body,
+        }
+    }
+
+    pub(crate) fn with_header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    /// Returns the value of the `Content-Type` header of the message.
+    #[inline]
+    pub fn content_type(&self) -> &str {
+        &self.content_type
+    }
+
+    /// Returns any headers of the message other than `Content-Type`, e.g.
+    /// `Content-Transfer-Encoding`.
+    #[inline]
+    pub fn headers(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.headers.iter().map(|&(n, ref v)| (n, &**v))
+    }
+
+    /// Returns the body of the message, i.e. the entity without its headers.
+    #[inline]
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    #[inline]
+    pub fn into_body(self) -> Vec<u8> {
+        self.body
+    }
+
+    /// Returns the complete entity including the `MIME-Version`, `Content-Type` and any
+    /// other headers.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut result = Vec::with_capacity(self.body.len() + self.content_type.len() + 40);
+        result.extend_from_slice(b"MIME-Version: 1.0\r\nContent-Type: ");
+        result.extend_from_slice(self.content_type.as_bytes());
+        result.extend_from_slice(CRLF);
+        for &(name, ref value) in &self.headers {
+            result.extend_from_slice(name.as_bytes());
+            result.extend_from_slice(b": ");
+            result.extend_from_slice(value.as_bytes());
+            result.extend_from_slice(CRLF);
+        }
+        result.extend_from_slice(CRLF);
+        result.extend_from_slice(&self.body);
+        result
+    }
+}
+
+/// Returns the `micalg` parameter value for the specified hash algorithm.
+pub fn micalg(algo: HashAlgorithm) -> Option<&'static str> {
+    match algo {
+        HashAlgorithm::Md5 => Some("pgp-md5"),
+        HashAlgorithm::Sha1 => Some("pgp-sha1"),
+        HashAlgorithm::RipeMd160 => Some("pgp-ripemd160"),
+        HashAlgorithm::Sha224 => Some("pgp-sha224"),
+        HashAlgorithm::Sha256 => Some("pgp-sha256"),
+        HashAlgorithm::Sha384 => Some("pgp-sha384"),
+        HashAlgorithm::Sha512 => Some("pgp-sha512"),
+        _ => None,
+    }
+}
+
+/// Converts all line endings in `data` to CRLF.
+pub fn canonicalize(data: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(data.len() + data.len() / 32);
+    let mut prev = 0u8;
+    for &b in data {
+        if (b == b'\n') && (prev != b'\r') {
+            result.push(b'\r');
+        }
+        result.push(b);
+        prev = b;
+    }
+    result
+}
+
+/// Creates a `multipart/signed` message containing `entity` and a detached signature made
+/// using the signers configured in `ctx`.
+///
+/// `entity` must be a complete MIME entity including its headers. It is converted to
+/// canonical form before signing; callers should make sure that it only contains 7-bit data
+/// as required by RFC 3156.
+///
+/// # Examples
+///
+/// ```no_run
+/// use gpgme::{mime, Context, Protocol};
+///
+/// let mut ctx = Context::from_protocol(Protocol::OpenPgp).unwrap();
+/// let entity = "Content-Type: text/plain\r\n\r\nHello, World!\r\n";
+/// let (message, _) = mime::sign(&mut ctx, entity.as_bytes()).unwrap();
+/// println!("{}", String::from_utf8_lossy(&message.to_bytes()));
+/// ```
+pub fn sign(ctx: &mut Context, entity: &[u8]) -> Result<(MimeMessage, SigningResult)> {
+    let mut entity = canonicalize(entity);
+    if !entity.ends_with(CRLF) {
+        // Otherwise the line break preceding the delimiter would change the signed data.
+        entity.extend_from_slice(CRLF);
+    }
+    let mut signature = Vec::new();
+    let result = with_armor(ctx, true, |ctx| ctx.sign_detached(&entity[..], &mut signature))?;
+    let hash = result
+        .new_signatures()
+        .next()
+        .map_or(HashAlgorithm::None, |s| s.hash_algorithm());
+    let micalg = micalg(hash).ok_or(Error::DIGEST_ALGO)?;
+    let signature = canonicalize(&signature);
+
+    let boundary = boundary(&[&entity, &signature]);
+    let mut body = Vec::new();
+    push_delimiter(&mut body, &boundary, false);
+    body.extend_from_slice(&entity);
+    push_delimiter(&mut body, &boundary, false);
+    body.extend_from_slice(
+        b"Content-Type: application/pgp-signature; name=\"signature.asc\"\r\n\
+          Content-Description: OpenPGP digital signature\r\n\
+          Content-Disposition: attachment; filename=\"signature.asc\"\r\n\r\n",
+    );
+    body.extend_from_slice(&signature);
+    push_delimiter(&mut body, &boundary, true);
+
+    let content_type = format!(
+        "multipart/signed; boundary=\"{}\"; micalg=\"{}\"; \
+         protocol=\"application/pgp-signature\"",
+        boundary, micalg
+    );
+    Ok((MimeMessage::new(content_type, body), result))
+}
+
+/// Creates a `multipart/encrypted` message containing `entity` encrypted for `recipients`.
+///
+/// `entity` must be a complete MIME entity including its headers.
+#[inline]
+pub fn encrypt<'k, I>(
+    ctx: &mut Context, recipients: I, entity: &[u8],
+) -> Result<(MimeMessage, EncryptionResult)>
+where I: IntoIterator<Item = &'k Key> {
+    encrypt_with_flags(ctx, recipients, entity, EncryptFlags::empty())
+}
+
+pub fn encrypt_with_flags<'k, I>(
+    ctx: &mut Context, recipients: I, entity: &[u8], flags: EncryptFlags,
+) -> Result<(MimeMessage, EncryptionResult)>
+where I: IntoIterator<Item = &'k Key> {
+    let entity = canonicalize(entity);
+    let mut ciphertext = Vec::new();
+    let result = with_armor(ctx, true, |ctx| {
+        ctx.encrypt_with_flags(recipients, &entity[..], &mut ciphertext, flags)
+    })?;
+    Ok((encrypted_message(&ciphertext), result))
+}
+
+/// Creates a `multipart/encrypted` message containing `entity` signed using the signers
+/// configured in `ctx` and encrypted for `recipients`.
+///
+/// The signature is combined with the encryption as described in section 6.2 of RFC 3156.
+#[inline]
+pub fn sign_and_encrypt<'k, I>(
+    ctx: &mut Context, recipients: I, entity: &[u8],
+) -> Result<(MimeMessage, EncryptionResult, SigningResult)>
+where I: IntoIterator<Item = &'k Key> {
+    sign_and_encrypt_with_flags(ctx, recipients, entity, EncryptFlags::empty())
+}
+
+pub fn sign_and_encrypt_with_flags<'k, I>(
+    ctx: &mut Context, recipients: I, entity: &[u8], flags: EncryptFlags,
+) -> Result<(MimeMessage, EncryptionResult, SigningResult)>
+where I: IntoIterator<Item = &'k Key> {
+    let entity = canonicalize(entity);
+    let mut ciphertext = Vec::new();
+    let (enc, sig) = with_armor(ctx, true, |ctx| {
+        ctx.sign_and_encrypt_with_flags(recipients, &entity[..], &mut ciphertext, flags)
+    })?;
+    Ok((encrypted_message(&ciphertext), enc, sig))
+}
+
+fn encrypted_message(ciphertext: &[u8]) -> MimeMessage {
+    let ciphertext = canonicalize(ciphertext);
+    let boundary = boundary(&[&ciphertext]);
+    let mut body = Vec::new();
+    push_delimiter(&mut body, &boundary, false);
+    body.extend_from_slice(
+        b"Content-Type: application/pgp-encrypted\r\n\
+          Content-Description: PGP/MIME version identification\r\n\r\n\
+          Version: 1\r\n",
+    );
+    push_delimiter(&mut body, &boundary, false);
+    body.extend_from_slice(
+        b"Content-Type: application/octet-stream; name=\"encrypted.asc\"\r\n\
+          Content-Description: OpenPGP encrypted message\r\n\
+          Content-Disposition: inline; filename=\"encrypted.asc\"\r\n\r\n",
+    );
+    body.extend_from_slice(&ciphertext);
+    push_delimiter(&mut body, &boundary, true);
+
+    let content_type = format!(
+        "multipart/encrypted; boundary=\"{}\"; protocol=\"application/pgp-encrypted\"",
+        boundary
+    );
+    MimeMessage::new(content_type, body)
+}
+
+/// An incoming message recognized by `parse`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParsedMessage {
+    /// A `multipart/signed` message.
+    Signed {
+        /// The signed entity exactly as it was signed, in canonical form.
+        entity: Vec<u8>,
+        /// The detached signature.
+        signature: Vec<u8>,
+        /// The `micalg` parameter of the message, if any.
+        micalg: Option<String>,
+    },
+    /// A `multipart/encrypted` message.
+    Encrypted {
+        /// The encrypted entity.
+        ciphertext: Vec<u8>,
+    },
+    /// A message that is neither signed nor encrypted using OpenPGP/MIME.
+    Other {
+        /// The lower case MIME type of the message.
+        mime_type: String,
+    },
+}
+
+/// Parses an incoming message including its headers.
+///
+/// Returns an error if the message claims to be an OpenPGP/MIME message, but is malformed.
+pub fn parse(message: &[u8]) -> Result<ParsedMessage> {
+    let message = canonicalize(message);
+    let (headers, body) = split_entity(&message);
+    let (mime_type, params) = match header(headers, "content-type") {
+        Some(value) => parse_content_type(&value),
+        None => ("text/plain".to_owned(), Vec::new()),
+    };
+    let param = |name: &str| {
+        params
+            .iter()
+            .find(|&&(ref n, _)| n == name)
+            .map(|&(_, ref v)| v.clone())
+    };
+
+    match &*mime_type {
+        "multipart/signed" => {
+            let protocol = param("protocol").unwrap_or_default();
+            if !protocol.eq_ignore_ascii_case("application/pgp-signature") {
+                return Ok(ParsedMessage::Other { mime_type });
+            }
+            let boundary = param("boundary").ok_or(Error::INV_VALUE)?;
+            let parts = split_multipart(body, &boundary)?;
+            if parts.len() != 2 {
+                return Err(Error::INV_VALUE);
+            }
+            let (sig_headers, signature) = split_entity(parts[1]);
+            let sig_type = header(sig_headers, "content-type")
+                .map(|v| parse_content_type(&v).0)
+                .unwrap_or_default();
+            if sig_type != "application/pgp-signature" {
+                return Err(Error::INV_VALUE);
+            }
+            Ok(ParsedMessage::Signed {
+                entity: parts[0].to_vec(),
+                signature: signature.to_vec(),
+                micalg: param("micalg").map(|s| s.to_ascii_lowercase()),
+            })
+        }
+        "multipart/encrypted" => {
+            let protocol = param("protocol").unwrap_or_default();
+            if !protocol.eq_ignore_ascii_case("application/pgp-encrypted") {
+                return Ok(ParsedMessage::Other { mime_type });
+            }
+            let boundary = param("boundary").ok_or(Error::INV_VALUE)?;
+            let parts = split_multipart(body, &boundary)?;
+            if parts.len() != 2 {
+                return Err(Error::INV_VALUE);
+            }
+            let (control_headers, control) = split_entity(parts[0]);
+            let control_type = header(control_headers, "content-type")
+                .map(|v| parse_content_type(&v).0)
+                .unwrap_or_default();
+            if (control_type != "application/pgp-encrypted")
+                || find(control, b"Version: 1").is_none()
+            {
+                return Err(Error::INV_VALUE);
+            }
+            let (_, ciphertext) = split_entity(parts[1]);
+            Ok(ParsedMessage::Encrypted {
+                ciphertext: ciphertext.to_vec(),
+            })
+        }
+        _ => Ok(ParsedMessage::Other { mime_type }),
+    }
+}
+
+/// The result of verifying a `multipart/signed` message.
+#[derive(Debug, Clone)]
+pub struct Verified {
+    /// The signed entity, in canonical form.
+    pub entity: Vec<u8>,
+    pub result: VerificationResult,
+}
+
+/// The result of decrypting a `multipart/encrypted` message.
+#[derive(Debug, Clone)]
+pub struct Decrypted {
+    /// The decrypted entity.
+    pub entity: Vec<u8>,
+    pub decryption: DecryptionResult,
+    /// The result of verifying a signature combined with the encryption.
+    pub verification: VerificationResult,
+}
+
+/// The result of `process`.
+#[derive(Debug, Clone)]
+pub enum Processed {
+    Verified(Verified),
+    Decrypted(Decrypted),
+}
+
+/// Parses an incoming message and verifies or decrypts it as appropriate.
+///
+/// Returns `Error::NO_DATA` if the message is not an OpenPGP/MIME message.
+pub fn process(ctx: &mut Context, message: &[u8]) -> Result<Processed> {
+    match parse(message)? {
+        ParsedMessage::Signed {
+            entity, signature, ..
+        } => verify_(ctx, entity, &signature).map(Processed::Verified),
+        ParsedMessage::Encrypted { ciphertext } => {
+            decrypt_(ctx, &ciphertext).map(Processed::Decrypted)
+        }
+        ParsedMessage::Other { .. } => Err(Error::NO_DATA),
+    }
+}
+
+/// Verifies a `multipart/signed` message.
+pub fn verify(ctx: &mut Context, message: &[u8]) -> Result<Verified> {
+    match parse(message)? {
+        ParsedMessage::Signed {
+            entity, signature, ..
+        } => verify_(ctx, entity, &signature),
+        _ => Err(Error::NO_DATA),
+    }
+}
+
+/// Decrypts a `multipart/encrypted` message, verifying any combined signature.
+pub fn decrypt(ctx: &mut Context, message: &[u8]) -> Result<Decrypted> {
+    match parse(message)? {
+        ParsedMessage::Encrypted { ciphertext } => decrypt_(ctx, &ciphertext),
+        _ => Err(Error::NO_DATA),
+    }
+}
+
+fn verify_(ctx: &mut Context, entity: Vec<u8>, signature: &[u8]) -> Result<Verified> {
+    let result = ctx.verify_detached(signature, &entity[..])?;
+    Ok(Verified { entity, result })
+}
+
+fn decrypt_(ctx: &mut Context, ciphertext: &[u8]) -> Result<Decrypted> {
+    let mut entity = Vec::new();
+    let (decryption, verification) = ctx.decrypt_and_verify(ciphertext, &mut entity)?;
+    Ok(Decrypted {
+        entity,
+        decryption,
+        verification,
+    })
+}
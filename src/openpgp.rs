@@ -0,0 +1,111 @@
+//! Minimal helpers for working with OpenPGP packets (RFC 4880).
+
+pub const TAG_SIGNATURE: u8 = 2;
+pub const TAG_PUBLIC_KEY: u8 = 6;
+pub const TAG_USER_ID: u8 = 13;
+pub const TAG_PUBLIC_SUBKEY: u8 = 14;
+pub const TAG_USER_ATTRIBUTE: u8 = 17;
+
+/// Splits the first packet off `data`, returning its tag, its body and the remaining input.
+///
+/// Partial body lengths are not supported as they do not occur in transferable keys.
+pub fn next_packet(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&header, data) = data.split_first()?;
+    if (header & 0x80) == 0 {
+        return None;
+    }
+    let (tag, len, data) = if (header & 0x40) != 0 {
+        let (&first, data) = data.split_first()?;
+        match first {
+            0..=191 => (header & 0x3f, usize::from(first), data),
+            192..=223 => {
+                let (&second, data) = data.split_first()?;
+                let len = ((usize::from(first) - 192) << 8) + usize::from(second) + 192;
+                (header & 0x3f, len, data)
+            }
+            255 if data.len() >= 4 => (header & 0x3f, be_len(&data[..4]), &data[4..]),
+            _ => return None,
+        }
+    } else {
+        let tag = (header >> 2) & 0x0f;
+        match header & 0x03 {
+            0 if !data.is_empty() => (tag, usize::from(data[0]), &data[1..]),
+            1 if data.len() >= 2 => (tag, be_len(&data[..2]), &data[2..]),
+            2 if data.len() >= 4 => (tag, be_len(&data[..4]), &data[4..]),
+            3 => (tag, data.len(), data),
+            _ => return None,
+        }
+    };
+    if len > data.len() {
+        return None;
+    }
+    Some((tag, &data[..len], &data[len..]))
+}
+
+/// Splits the first signature subpacket off `data`, returning its type octet including the
+/// critical bit, its value and the remaining input.
+pub fn next_subpacket(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&first, rest) = data.split_first()?;
+    let (len, rest) = match first {
+        0..=191 => (usize::from(first), rest),
+        192..=254 => {
+            let (&second, rest) = rest.split_first()?;
+            (((usize::from(first) - 192) << 8) + usize::from(second) + 192, rest)
+        }
+        255 => (be_len(rest.get(..4)?), rest.get(4..)?),
+    };
+    let packet = rest.get(..len)?;
+    let (&typ, value) = packet.split_first()?;
+    Some((typ, value, &rest[len..]))
+}
+
+pub fn be_len(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .fold(0usize, |acc, &b| (acc << 8) | usize::from(b))
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+/// Copies the transferable public key in `data`, omitting user attributes, the user ids and
+/// subkeys rejected by the filters and the signatures following them. Subkeys are numbered
+/// starting at zero in the order they appear.
+///
+/// Returns `None` if no user id was kept.
+pub fn filter_key(
+    mut data: &[u8], mut keep_user_id: impl FnMut(&[u8]) -> bool,
+    mut keep_subkey: impl FnMut(usize) -> bool,
+) -> Option<Vec<u8>> {
+    let mut result = Vec::with_capacity(data.len());
+    let mut keep = false;
+    let mut has_user_id = false;
+    let mut subkey = 0;
+    while let Some((tag, body, rest)) = next_packet(data) {
+        let packet = &data[..(data.len() - rest.len())];
+        data = rest;
+        keep = match tag {
+            TAG_PUBLIC_KEY => true,
+            TAG_USER_ID => {
+                let matches = keep_user_id(body);
+                has_user_id |= matches;
+                matches
+            }
+            TAG_PUBLIC_SUBKEY => {
+                subkey += 1;
+                keep_subkey(subkey - 1)
+            }
+            TAG_SIGNATURE => keep,
+            _ => false,
+        };
+        if keep {
+            result.extend_from_slice(packet);
+        }
+    }
+    if has_user_id {
+        Some(result)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,1131 @@
+//! Inspection of OpenPGP packet streams (RFC 4880 and RFC 9580) without an engine, in the
+//! spirit of `gpg --list-packets`.
+//!
+//! Only the packet headers and the unencrypted parts of the packet bodies are parsed, so the
+//! recipients of a message, the issuers of signatures and similar metadata can be determined
+//! before handing the data to the engine. Compressed and encrypted data is not descended
+//! into.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::fs::File;
+//!
+//! use gpgme::packets::{self, Body};
+//!
+//! let packets = packets::parse(File::open("message.asc").unwrap()).unwrap();
+//! for packet in &packets {
+//!     if let Body::PublicKeyEncryptedSessionKey { recipient, .. } = packet.body() {
+//!         println!("encrypted to {}", recipient.as_ref().map_or("anonymous", |r| &**r));
+//!     }
+//! }
+//! ```
+use std::{
+    cmp, fmt,
+    io::{self, prelude::*},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    armor,
+    openpgp::{be_len, hex, next_subpacket},
+    Error, HashAlgorithm, KeyAlgorithm, Result,
+};
+
+/// The maximum number of bytes of a packet body that are parsed. The remainder is skipped.
+const MAX_BODY: u64 = 1 << 20;
+/// The number of bytes parsed from the body of a data packet, which is enough for the
+/// unencrypted header fields.
+const MAX_DATA_PREFIX: u64 = 512;
+
+ffi_enum_wrapper! {
+    /// The type of an OpenPGP packet.
+    pub enum Tag: u8 {
+        Reserved = 0,
+        PublicKeyEncryptedSessionKey = 1,
+        Signature = 2,
+        SymmetricKeyEncryptedSessionKey = 3,
+        OnePassSignature = 4,
+        SecretKey = 5,
+        PublicKey = 6,
+        SecretSubkey = 7,
+        CompressedData = 8,
+        SymmetricallyEncryptedData = 9,
+        Marker = 10,
+        LiteralData = 11,
+        Trust = 12,
+        UserId = 13,
+        PublicSubkey = 14,
+        UserAttribute = 17,
+        SymEncryptedIntegrityProtectedData = 18,
+        ModificationDetectionCode = 19,
+        AeadEncryptedData = 20,
+        Padding = 21,
+    }
+}
+
+impl Tag {
+    fn is_data(self) -> bool {
+        match self {
+            Tag::CompressedData
+            | Tag::SymmetricallyEncryptedData
+            | Tag::LiteralData
+            | Tag::SymEncryptedIntegrityProtectedData
+            | Tag::AeadEncryptedData => true,
+            _ => false,
+        }
+    }
+}
+
+ffi_enum_wrapper! {
+    /// A symmetric cipher algorithm as used in OpenPGP.
+    pub enum SymmetricAlgorithm: u8 {
+        Plaintext = 0,
+        Idea = 1,
+        TripleDes = 2,
+        Cast5 = 3,
+        Blowfish = 4,
+        Aes128 = 7,
+        Aes192 = 8,
+        Aes256 = 9,
+        Twofish = 10,
+        Camellia128 = 11,
+        Camellia192 = 12,
+        Camellia256 = 13,
+    }
+}
+
+ffi_enum_wrapper! {
+    /// An AEAD mode as used in OpenPGP.
+    pub enum AeadAlgorithm: u8 {
+        Eax = 1,
+        Ocb = 2,
+        Gcm = 3,
+    }
+}
+
+ffi_enum_wrapper! {
+    /// A compression algorithm as used in OpenPGP.
+    pub enum CompressionAlgorithm: u8 {
+        Uncompressed = 0,
+        Zip = 1,
+        Zlib = 2,
+        Bzip2 = 3,
+    }
+}
+
+/// Maps an OpenPGP public key algorithm id to a `KeyAlgorithm`.
+///
+/// The ids of the elliptic curve algorithms differ from the ones used by GPGME.
+fn key_algorithm(id: u8) -> KeyAlgorithm {
+    match id {
+        18 => KeyAlgorithm::Ecdh,
+        19 => KeyAlgorithm::Ecdsa,
+        22 => KeyAlgorithm::Eddsa,
+        _ => unsafe { KeyAlgorithm::from_raw(id.into()) },
+    }
+}
+
+/// The inverse of `key_algorithm`.
+fn key_algorithm_id(algorithm: KeyAlgorithm) -> u32 {
+    match algorithm {
+        KeyAlgorithm::Ecdh => 18,
+        KeyAlgorithm::Ecdsa => 19,
+        KeyAlgorithm::Eddsa => 22,
+        _ => algorithm.raw(),
+    }
+}
+
+fn time(secs: u32) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs.into())
+}
+
+fn secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+/// A string-to-key specifier.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct S2k {
+    /// The type of the specifier, e.g. 3 for iterated and salted or 4 for Argon2.
+    pub kind: u8,
+    /// The hash algorithm, if the specifier uses one.
+    pub hash: Option<HashAlgorithm>,
+    /// The number of octets hashed for the iterated and salted specifier.
+    pub count: Option<u32>,
+}
+
+/// A signature subpacket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subpacket {
+    /// The type of the subpacket, without the critical bit.
+    pub kind: u8,
+    pub critical: bool,
+    /// Whether the subpacket is part of the hashed area.
+    pub hashed: bool,
+    pub value: Vec<u8>,
+}
+
+/// The parsed body of a packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Body {
+    PublicKeyEncryptedSessionKey {
+        version: u8,
+        /// The key id (version 3) or fingerprint (version 6) of the recipient's key, or
+        /// `None` for anonymous recipients.
+        recipient: Option<String>,
+        algorithm: KeyAlgorithm,
+    },
+    SymmetricKeyEncryptedSessionKey {
+        version: u8,
+        cipher: SymmetricAlgorithm,
+        aead: Option<AeadAlgorithm>,
+        s2k: S2k,
+    },
+    Signature {
+        version: u8,
+        signature_type: u8,
+        algorithm: KeyAlgorithm,
+        hash: HashAlgorithm,
+        created: Option<SystemTime>,
+        /// The fingerprint or key id of the issuer, if known.
+        issuer: Option<String>,
+        subpackets: Vec<Subpacket>,
+    },
+    OnePassSignature {
+        version: u8,
+        signature_type: u8,
+        hash: HashAlgorithm,
+        algorithm: KeyAlgorithm,
+        /// The key id (version 3) or fingerprint (version 6) of the issuer.
+        issuer: String,
+        /// Whether this is the last one-pass signature before the signed data.
+        last: bool,
+    },
+    Key {
+        version: u8,
+        /// Whether this is a primary key rather than a subkey.
+        primary: bool,
+        secret: bool,
+        created: SystemTime,
+        algorithm: KeyAlgorithm,
+        /// The size of the key in bits, for RSA, DSA and Elgamal keys.
+        bits: Option<usize>,
+        /// The name or OID of the curve, for ECC keys.
+        curve: Option<String>,
+        /// The fingerprint, except for version 3 keys.
+        fingerprint: Option<String>,
+        key_id: Option<String>,
+    },
+    CompressedData {
+        algorithm: CompressionAlgorithm,
+    },
+    SymmetricallyEncryptedData,
+    Marker,
+    LiteralData {
+        /// The format octet, e.g. `b'b'` for binary or `b'u'` for UTF-8 text.
+        format: u8,
+        filename: Vec<u8>,
+        date: Option<SystemTime>,
+    },
+    Trust,
+    UserId(String),
+    UserAttribute,
+    SymEncryptedIntegrityProtectedData {
+        version: u8,
+        /// The cipher, for version 2.
+        cipher: Option<SymmetricAlgorithm>,
+        /// The AEAD mode, for version 2.
+        aead: Option<AeadAlgorithm>,
+        /// The chunk size in bytes, for version 2.
+        chunk_size: Option<u64>,
+    },
+    ModificationDetectionCode,
+    AeadEncryptedData {
+        version: u8,
+        cipher: SymmetricAlgorithm,
+        aead: AeadAlgorithm,
+        chunk_size: u64,
+    },
+    Padding,
+    /// A packet with an unknown tag or version, or a body that could not be parsed.
+    Unknown,
+}
+
+/// A packet in an OpenPGP stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Packet {
+    offset: u64,
+    tag: Tag,
+    new_format: bool,
+    partial: bool,
+    length: u64,
+    body: Body,
+}
+
+impl Packet {
+    /// Returns the offset of the packet header in the (dearmored) input.
+    #[inline]
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    #[inline]
+    pub fn tag(&self) -> Tag {
+        self.tag
+    }
+
+    /// Returns `true` if the packet uses the new header format.
+    #[inline]
+    pub fn is_new_format(&self) -> bool {
+        self.new_format
+    }
+
+    /// Returns `true` if the body is encoded using partial body lengths, or extends to the
+    /// end of the input.
+    #[inline]
+    pub fn is_partial(&self) -> bool {
+        self.partial
+    }
+
+    /// Returns the length of the body in bytes.
+    #[inline]
+    pub fn length(&self) -> u64 {
+        self.length
+    }
+
+    #[inline]
+    pub fn body(&self) -> &Body {
+        &self.body
+    }
+}
+
+impl fmt::Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "# off={} ctb={} tag={} len={}",
+            self.offset,
+            if self.new_format { "new" } else { "old" },
+            self.tag.raw(),
+            self.length
+        )?;
+        if self.partial {
+            write!(f, " (partial)")?;
+        }
+        writeln!(f)?;
+        match self.body {
+            Body::PublicKeyEncryptedSessionKey {
+                version,
+                ref recipient,
+                algorithm,
+            } => writeln!(
+                f,
+                ":pubkey enc packet: version {}, algo {}, keyid {}",
+                version,
+                key_algorithm_id(algorithm),
+                recipient.as_ref().map_or("0000000000000000", |r| &**r)
+            ),
+            Body::SymmetricKeyEncryptedSessionKey {
+                version,
+                cipher,
+                aead,
+                s2k,
+            } => {
+                write!(f, ":symkey enc packet: version {}, cipher {}", version, cipher.raw())?;
+                if let Some(aead) = aead {
+                    write!(f, ", aead {}", aead.raw())?;
+                }
+                write!(f, ", s2k {}", s2k.kind)?;
+                if let Some(hash) = s2k.hash {
+                    write!(f, ", hash {}", hash.raw())?;
+                }
+                if let Some(count) = s2k.count {
+                    write!(f, ", count {}", count)?;
+                }
+                writeln!(f)
+            }
+            Body::Signature {
+                version,
+                signature_type,
+                algorithm,
+                hash,
+                created,
+                ref issuer,
+                ref subpackets,
+            } => {
+                writeln!(
+                    f,
+                    ":signature packet: algo {}, keyid {}",
+                    key_algorithm_id(algorithm),
+                    issuer.as_ref().map_or("0000000000000000", |i| &**i)
+                )?;
+                writeln!(
+                    f,
+                    "\tversion {}, created {}, sigclass 0x{:02x}, digest algo {}",
+                    version,
+                    created.map_or(0, secs),
+                    signature_type,
+                    hash.raw()
+                )?;
+                for subpacket in subpackets {
+                    writeln!(
+                        f,
+                        "\t{}{}subpkt {} len {}",
+                        if subpacket.hashed { "hashed " } else { "" },
+                        if subpacket.critical { "critical " } else { "" },
+                        subpacket.kind,
+                        subpacket.value.len()
+                    )?;
+                }
+                Ok(())
+            }
+            Body::OnePassSignature {
+                version,
+                signature_type,
+                hash,
+                algorithm,
+                ref issuer,
+                last,
+            } => writeln!(
+                f,
+                ":onepass_sig packet: keyid {}\n\tversion {}, sigclass 0x{:02x}, digest {}, \
+                 pubkey {}, last={}",
+                issuer,
+                version,
+                signature_type,
+                hash.raw(),
+                key_algorithm_id(algorithm),
+                last as u8
+            ),
+            Body::Key {
+                version,
+                primary,
+                secret,
+                created,
+                algorithm,
+                bits,
+                ref curve,
+                ref key_id,
+                ..
+            } => {
+                writeln!(
+                    f,
+                    ":{} {}key packet:",
+                    if secret { "secret" } else { "public" },
+                    if primary { "" } else { "sub" }
+                )?;
+                writeln!(
+                    f,
+                    "\tversion {}, algo {}, created {}",
+                    version,
+                    key_algorithm_id(algorithm),
+                    secs(created)
+                )?;
+                if let Some(bits) = bits {
+                    writeln!(f, "\tpkey[0]: [{} bits]", bits)?;
+                }
+                if let Some(ref curve) = *curve {
+                    writeln!(f, "\tcurve: {}", curve)?;
+                }
+                if let Some(ref key_id) = *key_id {
+                    writeln!(f, "\tkeyid: {}", key_id)?;
+                }
+                Ok(())
+            }
+            Body::CompressedData { algorithm } => {
+                writeln!(f, ":compressed packet: algo={}", algorithm.raw())
+            }
+            Body::SymmetricallyEncryptedData => writeln!(f, ":encrypted data packet:"),
+            Body::Marker => writeln!(f, ":marker packet:"),
+            Body::LiteralData {
+                format,
+                ref filename,
+                date,
+            } => writeln!(
+                f,
+                ":literal data packet:\n\tmode {} ({:X}), created {}, name=\"{}\"",
+                format as char,
+                format,
+                date.map_or(0, secs),
+                String::from_utf8_lossy(filename)
+            ),
+            Body::Trust => writeln!(f, ":trust packet:"),
+            Body::UserId(ref uid) => writeln!(f, ":user ID packet: {:?}", uid),
+            Body::UserAttribute => writeln!(f, ":attribute packet:"),
+            Body::SymEncryptedIntegrityProtectedData {
+                version,
+                cipher,
+                aead,
+                chunk_size,
+            } => {
+                write!(f, ":encrypted data packet:\n\tversion {}", version)?;
+                if let (Some(cipher), Some(aead), Some(chunk_size)) = (cipher, aead, chunk_size) {
+                    write!(
+                        f,
+                        ", cipher {}, aead {}, chunk size {}",
+                        cipher.raw(),
+                        aead.raw(),
+                        chunk_size
+                    )?;
+                }
+                writeln!(f)
+            }
+            Body::ModificationDetectionCode => writeln!(f, ":mdc packet:"),
+            Body::AeadEncryptedData {
+                version,
+                cipher,
+                aead,
+                chunk_size,
+            } => writeln!(
+                f,
+                ":aead encrypted packet: version {}, cipher {}, aead {}, chunk size {}",
+                version,
+                cipher.raw(),
+                aead.raw(),
+                chunk_size
+            ),
+            Body::Padding => writeln!(f, ":padding packet:"),
+            Body::Unknown => writeln!(f, ":unknown packet:"),
+        }
+    }
+}
+
+/// A cursor over a packet body.
+struct Input<'a>(&'a [u8]);
+
+impl<'a> Input<'a> {
+    fn u8(&mut self) -> Option<u8> {
+        let (&b, rest) = self.0.split_first()?;
+        self.0 = rest;
+        Some(b)
+    }
+
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let bytes = self.0.get(..n)?;
+        self.0 = &self.0[n..];
+        Some(bytes)
+    }
+
+    fn be(&mut self, n: usize) -> Option<usize> {
+        self.take(n).map(be_len)
+    }
+
+    fn time(&mut self) -> Option<SystemTime> {
+        self.be(4).map(|s| time(s as u32))
+    }
+
+    /// Reads a multiprecision integer, returning its size in bits.
+    fn mpi(&mut self) -> Option<usize> {
+        let bits = self.be(2)?;
+        self.take((bits + 7) / 8)?;
+        Some(bits)
+    }
+
+    fn oid(&mut self) -> Option<&'a [u8]> {
+        let len = self.u8()?;
+        self.take(len.into())
+    }
+}
+
+fn curve_name(oid: &[u8]) -> String {
+    const CURVES: &[(&[u8], &str)] = &[
+        (b"\x2B\x06\x01\x04\x01\xDA\x47\x0F\x01", "ed25519"),
+        (b"\x2B\x06\x01\x04\x01\x97\x55\x01\x05\x01", "cv25519"),
+        (b"\x2A\x86\x48\xCE\x3D\x03\x01\x07", "nistp256"),
+        (b"\x2B\x81\x04\x00\x22", "nistp384"),
+        (b"\x2B\x81\x04\x00\x23", "nistp521"),
+        (b"\x2B\x24\x03\x03\x02\x08\x01\x01\x07", "brainpoolP256r1"),
+        (b"\x2B\x24\x03\x03\x02\x08\x01\x01\x0B", "brainpoolP384r1"),
+        (b"\x2B\x24\x03\x03\x02\x08\x01\x01\x0D", "brainpoolP512r1"),
+        (b"\x2B\x81\x04\x00\x0A", "secp256k1"),
+    ];
+    if let Some(&(_, name)) = CURVES.iter().find(|&&(c, _)| c == oid) {
+        return name.to_owned();
+    }
+
+    let mut arcs = Vec::new();
+    if let Some(&first) = oid.first() {
+        arcs.push(u64::from(first / 40));
+        arcs.push(u64::from(first % 40));
+    }
+    let mut arc = 0u64;
+    for &b in oid.iter().skip(1) {
+        arc = (arc << 7) | u64::from(b & 0x7f);
+        if (b & 0x80) == 0 {
+            arcs.push(arc);
+            arc = 0;
+        }
+    }
+    arcs.iter()
+        .map(|a| a.to_string())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn parse_s2k(input: &mut Input<'_>) -> Option<S2k> {
+    let kind = input.u8()?;
+    let mut s2k = S2k {
+        kind,
+        hash: None,
+        count: None,
+    };
+    if (kind <= 3) && (kind != 2) {
+        s2k.hash = Some(unsafe { HashAlgorithm::from_raw(input.u8()?.into()) });
+    }
+    if kind == 3 {
+        input.take(8)?;
+        let c = u32::from(input.u8()?);
+        s2k.count = Some((16 + (c & 15)) << ((c >> 4) + 6));
+    }
+    Some(s2k)
+}
+
+fn parse_pkesk(mut input: Input<'_>) -> Option<Body> {
+    let version = input.u8()?;
+    let recipient = match version {
+        3 => Some(input.take(8)?),
+        6 => match input.u8()? {
+            0 => None,
+            len => Some(input.take(len.into())?.get(1..)?),
+        },
+        _ => return None,
+    };
+    Some(Body::PublicKeyEncryptedSessionKey {
+        version,
+        recipient: recipient
+            .filter(|r| r.iter().any(|&b| b != 0))
+            .map(hex),
+        algorithm: key_algorithm(input.u8()?),
+    })
+}
+
+fn parse_skesk(mut input: Input<'_>) -> Option<Body> {
+    let version = input.u8()?;
+    let (cipher, aead) = match version {
+        4 => (input.u8()?, None),
+        5 => (input.u8()?, Some(input.u8()?)),
+        6 => {
+            input.u8()?;
+            let cipher = input.u8()?;
+            let aead = input.u8()?;
+            input.u8()?;
+            (cipher, Some(aead))
+        }
+        _ => return None,
+    };
+    Some(Body::SymmetricKeyEncryptedSessionKey {
+        version,
+        cipher: unsafe { SymmetricAlgorithm::from_raw(cipher) },
+        aead: aead.map(|a| unsafe { AeadAlgorithm::from_raw(a) }),
+        s2k: parse_s2k(&mut input)?,
+    })
+}
+
+fn parse_signature(mut input: Input<'_>) -> Option<Body> {
+    let version = input.u8()?;
+    if version == 3 {
+        if input.u8()? != 5 {
+            return None;
+        }
+        let signature_type = input.u8()?;
+        let created = input.time()?;
+        let issuer = hex(input.take(8)?);
+        let algorithm = key_algorithm(input.u8()?);
+        let hash = unsafe { HashAlgorithm::from_raw(input.u8()?.into()) };
+        return Some(Body::Signature {
+            version,
+            signature_type,
+            algorithm,
+            hash,
+            created: Some(created),
+            issuer: Some(issuer),
+            subpackets: Vec::new(),
+        });
+    }
+
+    let len_size = match version {
+        4 | 5 => 2,
+        6 => 4,
+        _ => return None,
+    };
+    let signature_type = input.u8()?;
+    let algorithm = key_algorithm(input.u8()?);
+    let hash = unsafe { HashAlgorithm::from_raw(input.u8()?.into()) };
+    let mut subpackets = Vec::new();
+    for &hashed in &[true, false] {
+        let len = input.be(len_size)?;
+        let mut area = input.take(len)?;
+        while !area.is_empty() {
+            let (kind, value, rest) = next_subpacket(area)?;
+            area = rest;
+            subpackets.push(Subpacket {
+                kind: kind & 0x7f,
+                critical: (kind & 0x80) != 0,
+                hashed,
+                value: value.to_vec(),
+            });
+        }
+    }
+
+    let created = subpackets
+        .iter()
+        .find(|s| s.hashed && (s.kind == 2) && (s.value.len() == 4))
+        .map(|s| time(be_len(&s.value) as u32));
+    // Prefer the issuer fingerprint, dropping its version octet.
+    let issuer = subpackets
+        .iter()
+        .find(|s| (s.kind == 33) && (s.value.len() > 1))
+        .map(|s| hex(&s.value[1..]))
+        .or_else(|| {
+            subpackets
+                .iter()
+                .find(|s| (s.kind == 16) && (s.value.len() == 8))
+                .map(|s| hex(&s.value))
+        });
+    Some(Body::Signature {
+        version,
+        signature_type,
+        algorithm,
+        hash,
+        created,
+        issuer,
+        subpackets,
+    })
+}
+
+fn parse_one_pass_signature(mut input: Input<'_>) -> Option<Body> {
+    let version = input.u8()?;
+    let signature_type = input.u8()?;
+    let hash = unsafe { HashAlgorithm::from_raw(input.u8()?.into()) };
+    let algorithm = key_algorithm(input.u8()?);
+    let issuer = match version {
+        3 => hex(input.take(8)?),
+        6 => {
+            let salt_len = input.u8()?;
+            input.take(salt_len.into())?;
+            hex(input.take(32)?)
+        }
+        _ => return None,
+    };
+    Some(Body::OnePassSignature {
+        version,
+        signature_type,
+        hash,
+        algorithm,
+        issuer,
+        last: input.u8()? != 0,
+    })
+}
+
+/// Parses the public key material of a key packet, returning the size in bits or the name of
+/// the curve.
+fn parse_key_material(
+    algorithm: u8, input: &mut Input<'_>,
+) -> Option<(Option<usize>, Option<String>)> {
+    let mut bits = None;
+    let mut curve = None;
+    match algorithm {
+        // RSA
+        1..=3 => {
+            bits = Some(input.mpi()?);
+            input.mpi()?;
+        }
+        // Elgamal
+        16 | 20 => {
+            bits = Some(input.mpi()?);
+            input.mpi()?;
+            input.mpi()?;
+        }
+        // DSA
+        17 => {
+            bits = Some(input.mpi()?);
+            for _ in 0..3 {
+                input.mpi()?;
+            }
+        }
+        // ECDSA, EdDSA
+        19 | 22 => {
+            curve = Some(curve_name(input.oid()?));
+            input.mpi()?;
+        }
+        // ECDH
+        18 => {
+            curve = Some(curve_name(input.oid()?));
+            input.mpi()?;
+            input.oid()?;
+        }
+        // X25519, X448, Ed25519, Ed448
+        25 => drop(input.take(32)?),
+        26 => drop(input.take(56)?),
+        27 => drop(input.take(32)?),
+        28 => drop(input.take(57)?),
+        _ => return None,
+    }
+    Some((bits, curve))
+}
+
+fn parse_key(tag: Tag, body: &[u8]) -> Option<Body> {
+    let secret = (tag == Tag::SecretKey) || (tag == Tag::SecretSubkey);
+    let mut input = Input(body);
+    let version = input.u8()?;
+    let created = input.time()?;
+    if version == 3 {
+        input.take(2)?;
+    }
+    let algorithm = input.u8()?;
+    // The public part of the key packet, which is hashed to compute the fingerprint.
+    let (public, bits, curve) = match version {
+        3 | 4 => match parse_key_material(algorithm, &mut input) {
+            Some((bits, curve)) => (Some(&body[..(body.len() - input.0.len())]), bits, curve),
+            None if !secret => (Some(body), None, None),
+            None => (None, None, None),
+        },
+        5 | 6 => {
+            let len = input.be(4)?;
+            let mut material = Input(input.take(len)?);
+            let (bits, curve) =
+                parse_key_material(algorithm, &mut material).unwrap_or((None, None));
+            (Some(&body[..(body.len() - input.0.len())]), bits, curve)
+        }
+        _ => return None,
+    };
+
+    let fingerprint = match (version, public) {
+        (4, Some(public)) => {
+            let mut hasher = Sha1::new();
+            hasher.input([0x99]);
+            hasher.input((public.len() as u16).to_be_bytes());
+            hasher.input(public);
+            Some(hex(&hasher.result()))
+        }
+        (5, Some(public)) | (6, Some(public)) => {
+            let mut hasher = Sha256::new();
+            hasher.input([if version == 5 { 0x9a } else { 0x9b }]);
+            hasher.input((public.len() as u32).to_be_bytes());
+            hasher.input(public);
+            Some(hex(&hasher.result()))
+        }
+        _ => None,
+    };
+    // Version 4 key ids are the low 64 bits of the fingerprint, later ones the high 64 bits.
+    let key_id = fingerprint.as_ref().map(|fpr| {
+        if version == 4 {
+            fpr[(fpr.len() - 16)..].to_owned()
+        } else {
+            fpr[..16].to_owned()
+        }
+    });
+    Some(Body::Key {
+        version,
+        primary: (tag == Tag::PublicKey) || (tag == Tag::SecretKey),
+        secret,
+        created,
+        algorithm: key_algorithm(algorithm),
+        bits,
+        curve,
+        fingerprint,
+        key_id,
+    })
+}
+
+fn parse_literal(mut input: Input<'_>) -> Option<Body> {
+    let format = input.u8()?;
+    let len = input.u8()?;
+    let filename = input.take(len.into())?.to_vec();
+    let date = input.be(4)? as u32;
+    Some(Body::LiteralData {
+        format,
+        filename,
+        date: if date != 0 { Some(time(date)) } else { None },
+    })
+}
+
+/// Decodes a chunk size octet, rejecting values above the maximum of 16 allowed by RFC 9580.
+fn chunk_size(octet: u8) -> Option<u64> {
+    if octet > 16 {
+        return None;
+    }
+    Some(1 << (u32::from(octet) + 6))
+}
+
+fn parse_seipd(mut input: Input<'_>) -> Option<Body> {
+    let version = input.u8()?;
+    match version {
+        1 => Some(Body::SymEncryptedIntegrityProtectedData {
+            version,
+            cipher: None,
+            aead: None,
+            chunk_size: None,
+        }),
+        2 => Some(Body::SymEncryptedIntegrityProtectedData {
+            version,
+            cipher: Some(unsafe { SymmetricAlgorithm::from_raw(input.u8()?) }),
+            aead: Some(unsafe { AeadAlgorithm::from_raw(input.u8()?) }),
+            chunk_size: Some(chunk_size(input.u8()?)?),
+        }),
+        _ => None,
+    }
+}
+
+fn parse_aead(mut input: Input<'_>) -> Option<Body> {
+    let version = input.u8()?;
+    if version != 1 {
+        return None;
+    }
+    Some(Body::AeadEncryptedData {
+        version,
+        cipher: unsafe { SymmetricAlgorithm::from_raw(input.u8()?) },
+        aead: unsafe { AeadAlgorithm::from_raw(input.u8()?) },
+        chunk_size: chunk_size(input.u8()?)?,
+    })
+}
+
+fn parse_body(tag: Tag, body: &[u8]) -> Body {
+    let input = Input(body);
+    let result = match tag {
+        Tag::PublicKeyEncryptedSessionKey => parse_pkesk(input),
+        Tag::Signature => parse_signature(input),
+        Tag::SymmetricKeyEncryptedSessionKey => parse_skesk(input),
+        Tag::OnePassSignature => parse_one_pass_signature(input),
+        Tag::SecretKey | Tag::PublicKey | Tag::SecretSubkey | Tag::PublicSubkey => {
+            parse_key(tag, body)
+        }
+        Tag::CompressedData => body.first().map(|&a| Body::CompressedData {
+            algorithm: unsafe { CompressionAlgorithm::from_raw(a) },
+        }),
+        Tag::SymmetricallyEncryptedData => Some(Body::SymmetricallyEncryptedData),
+        Tag::Marker => Some(Body::Marker),
+        Tag::LiteralData => parse_literal(input),
+        Tag::Trust => Some(Body::Trust),
+        Tag::UserId => Some(Body::UserId(String::from_utf8_lossy(body).into_owned())),
+        Tag::UserAttribute => Some(Body::UserAttribute),
+        Tag::SymEncryptedIntegrityProtectedData => parse_seipd(input),
+        Tag::ModificationDetectionCode => Some(Body::ModificationDetectionCode),
+        Tag::AeadEncryptedData => parse_aead(input),
+        Tag::Padding => Some(Body::Padding),
+        _ => None,
+    };
+    result.unwrap_or(Body::Unknown)
+}
+
+/// Reads the body of a packet, following partial body lengths.
+struct BodyReader<'a, R> {
+    reader: &'a mut R,
+    remaining: u64,
+    partial: bool,
+    /// Whether the body extends to the end of the input.
+    indeterminate: bool,
+    count: u64,
+}
+
+impl<R: Read> BodyReader<'_, R> {
+    fn next_chunk(&mut self) -> io::Result<()> {
+        let len = match read_length(self.reader)? {
+            Length::Partial(len) => len,
+            Length::Full(len) => {
+                self.partial = false;
+                len
+            }
+        };
+        self.count += length_size(len, self.partial);
+        self.remaining = len;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for BodyReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.indeterminate {
+            return self.reader.read(buf);
+        }
+        while self.remaining == 0 {
+            if !self.partial {
+                return Ok(0);
+            }
+            self.next_chunk()?;
+        }
+        let max = cmp::min(buf.len() as u64, self.remaining) as usize;
+        let n = self.reader.read(&mut buf[..max])?;
+        if n == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        self.remaining -= n as u64;
+        Ok(n)
+    }
+}
+
+enum Length {
+    Full(u64),
+    Partial(u64),
+}
+
+fn read_u8(reader: &mut impl Read) -> io::Result<u8> {
+    let mut buf = [0u8; 1];
+    reader.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_be(reader: &mut impl Read, n: usize) -> io::Result<u64> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf[..n])?;
+    Ok(buf[..n]
+        .iter()
+        .fold(0, |acc, &b| (acc << 8) | u64::from(b)))
+}
+
+/// Reads a new format length.
+fn read_length(reader: &mut impl Read) -> io::Result<Length> {
+    let first = read_u8(reader)?;
+    Ok(match first {
+        0..=191 => Length::Full(first.into()),
+        192..=223 => {
+            let second = read_u8(reader)?;
+            Length::Full(((u64::from(first) - 192) << 8) + u64::from(second) + 192)
+        }
+        224..=254 => Length::Partial(1 << (first & 0x1f)),
+        255 => Length::Full(read_be(reader, 4)?),
+    })
+}
+
+/// Returns the number of bytes used to encode a new format length.
+fn length_size(len: u64, partial: bool) -> u64 {
+    if partial || (len < 192) {
+        1
+    } else if len < 8384 {
+        2
+    } else {
+        5
+    }
+}
+
+fn io_error(err: io::Error) -> Error {
+    if err.kind() == io::ErrorKind::UnexpectedEof {
+        Error::TRUNCATED
+    } else {
+        Error::from(err)
+    }
+}
+
+/// A streaming parser for binary OpenPGP data.
+///
+/// Packet bodies are read incrementally and only their beginning is kept, so arbitrarily large
+/// messages can be inspected. Use `parse` for armored input.
+#[derive(Debug)]
+pub struct Parser<R> {
+    reader: R,
+    offset: u64,
+    done: bool,
+}
+
+impl<R: Read> Parser<R> {
+    #[inline]
+    pub fn new(reader: R) -> Self {
+        Parser {
+            reader,
+            offset: 0,
+            done: false,
+        }
+    }
+
+    fn next_packet(&mut self) -> Result<Option<Packet>> {
+        let mut header = [0u8; 1];
+        loop {
+            match self.reader.read(&mut header) {
+                Ok(0) => return Ok(None),
+                Ok(_) => break,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(Error::from(e)),
+            }
+        }
+        let header = header[0];
+        if (header & 0x80) == 0 {
+            return Err(Error::BAD_DATA);
+        }
+
+        let new_format = (header & 0x40) != 0;
+        let (tag, length, header_len, partial, indeterminate) = if new_format {
+            let tag = header & 0x3f;
+            match read_length(&mut self.reader).map_err(io_error)? {
+                Length::Full(len) => (tag, len, 1 + length_size(len, false), false, false),
+                Length::Partial(len) => (tag, len, 2, true, false),
+            }
+        } else {
+            let tag = (header >> 2) & 0x0f;
+            match header & 0x03 {
+                0 => (tag, read_be(&mut self.reader, 1).map_err(io_error)?, 2, false, false),
+                1 => (tag, read_be(&mut self.reader, 2).map_err(io_error)?, 3, false, false),
+                2 => (tag, read_be(&mut self.reader, 4).map_err(io_error)?, 5, false, false),
+                _ => (tag, 0, 1, false, true),
+            }
+        };
+        let tag = unsafe { Tag::from_raw(tag) };
+
+        let mut body = BodyReader {
+            reader: &mut self.reader,
+            remaining: length,
+            partial,
+            indeterminate,
+            count: 0,
+        };
+        let limit = if tag.is_data() { MAX_DATA_PREFIX } else { MAX_BODY };
+        let mut prefix = Vec::new();
+        (&mut body)
+            .take(limit)
+            .read_to_end(&mut prefix)
+            .map_err(io_error)?;
+        let skipped = io::copy(&mut body, &mut io::sink()).map_err(io_error)?;
+        let length = prefix.len() as u64 + skipped;
+
+        let packet = Packet {
+            offset: self.offset,
+            tag,
+            new_format,
+            partial: partial || indeterminate,
+            length,
+            body: parse_body(tag, &prefix),
+        };
+        self.offset += header_len + body.count + length;
+        Ok(Some(packet))
+    }
+}
+
+impl<R: Read> Iterator for Parser<R> {
+    type Item = Result<Packet>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.next_packet() {
+            Ok(Some(packet)) => Some(Ok(packet)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Parses all packets in `reader`, which may contain binary or ASCII armored data.
+///
+/// For armored input, the first armored block containing binary data is parsed.
+pub fn parse(mut reader: impl Read) -> Result<Vec<Packet>> {
+    let mut first = [0u8; 1];
+    if reader.read(&mut first)? == 0 {
+        return Ok(Vec::new());
+    }
+    if (first[0] & 0x80) != 0 {
+        return Parser::new(io::Cursor::new(first).chain(reader)).collect();
+    }
+
+    let reader = armor::Reader::new(io::Cursor::new(first).chain(reader))?;
+    Parser::new(reader).collect()
+}
@@ -0,0 +1,478 @@
+use std::{
+    fmt,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    Context, Error, HashAlgorithm, Key, KeyAlgorithm, Signature, SignatureSummary,
+    VerificationResult,
+};
+
+/// How far in the future a signature's creation time may lie before `max_age` rejects it.
+const MAX_CLOCK_SKEW: Duration = Duration::from_secs(5 * 60);
+
+/// A declarative set of requirements that signatures in a `VerificationResult` must meet
+/// to be accepted.
+///
+/// # Examples
+///
+/// ```no_run
+/// use gpgme::{policy::VerifyPolicy, Context, Protocol};
+///
+/// let mut ctx = Context::from_protocol(Protocol::OpenPgp).unwrap();
+/// let result = ctx.verify_opaque("[some signed message]", &mut Vec::new()).unwrap();
+/// let policy = VerifyPolicy::new()
+///     .allow_signer("0123456789ABCDEF0123456789ABCDEF01234567")
+///     .min_key_size(gpgme::KeyAlgorithm::Rsa, 3072);
+/// let report = policy.evaluate_with_context(&mut ctx, &result);
+/// assert!(report.is_accepted(), "{}", report);
+/// ```
+#[derive(Debug, Clone)]
+pub struct VerifyPolicy {
+    allowed_signers: Vec<String>,
+    min_signatures: usize,
+    banned_hashes: Vec<HashAlgorithm>,
+    min_key_sizes: Vec<(KeyAlgorithm, usize)>,
+    required_summary: SignatureSummary,
+    max_age: Option<Duration>,
+    required_notations: Vec<(String, Option<String>)>,
+}
+
+impl Default for VerifyPolicy {
+    fn default() -> Self {
+        VerifyPolicy {
+            allowed_signers: Vec::new(),
+            min_signatures: 1,
+            banned_hashes: vec![
+                HashAlgorithm::Md2,
+                HashAlgorithm::Md4,
+                HashAlgorithm::Md5,
+                HashAlgorithm::Sha1,
+            ],
+            min_key_sizes: Vec::new(),
+            required_summary: SignatureSummary::VALID,
+            max_age: None,
+            required_notations: Vec::new(),
+        }
+    }
+}
+
+impl VerifyPolicy {
+    /// Creates a policy requiring a single signature with the `VALID` summary that was not
+    /// made using MD2, MD4, MD5 or SHA-1.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restricts accepted signatures to those made by the key with the specified
+    /// fingerprint.
+    ///
+    /// The fingerprint may belong to a primary key, in which case signatures made by any of
+    /// its subkeys are accepted, or to a single subkey. May be called more than once.
+    pub fn allow_signer(mut self, fpr: impl AsRef<str>) -> Self {
+        self.allowed_signers.push(normalize_fpr(fpr.as_ref()));
+        self
+    }
+
+    /// Sets the number of signatures that must be accepted for the result as a whole to be
+    /// accepted.
+    pub fn min_signatures(mut self, count: usize) -> Self {
+        self.min_signatures = count;
+        self
+    }
+
+    /// Rejects signatures made using the specified hash algorithm.
+    pub fn ban_hash(mut self, algo: HashAlgorithm) -> Self {
+        if !self.banned_hashes.contains(&algo) {
+            self.banned_hashes.push(algo);
+        }
+        self
+    }
+
+    /// Removes the specified hash algorithm from the list of banned algorithms.
+    pub fn allow_hash(mut self, algo: HashAlgorithm) -> Self {
+        self.banned_hashes.retain(|&x| x != algo);
+        self
+    }
+
+    /// Rejects signatures made by keys of the specified algorithm that are shorter than
+    /// `bits`.
+    ///
+    /// Algorithm variants that only differ in usage (e.g. `RsaSign` and `Rsa`) are treated
+    /// as the same algorithm.
+    pub fn min_key_size(mut self, algo: KeyAlgorithm, bits: usize) -> Self {
+        let algo = algorithm_family(algo);
+        self.min_key_sizes.retain(|&(x, _)| x != algo);
+        self.min_key_sizes.push((algo, bits));
+        self
+    }
+
+    /// Sets the summary flags that every accepted signature must have.
+    pub fn require_summary(mut self, summary: SignatureSummary) -> Self {
+        self.required_summary = summary;
+        self
+    }
+
+    /// Rejects signatures that were created longer ago than `age`.
+    ///
+    /// Signatures whose creation time lies more than five minutes in the future are
+    /// rejected as well, since their age cannot be determined.
+    pub fn max_age(mut self, age: Duration) -> Self {
+        self.max_age = Some(age);
+        self
+    }
+
+    /// Requires accepted signatures to carry a notation with the specified name and, if
+    /// provided, value.
+    pub fn require_notation(mut self, name: impl Into<String>, value: Option<&str>) -> Self {
+        self.required_notations
+            .push((name.into(), value.map(|s| s.to_owned())));
+        self
+    }
+
+    /// Evaluates the policy using the keys embedded in the signatures of the result.
+    ///
+    /// Key size requirements and matching of signatures made by subkeys against primary
+    /// key fingerprints require the key that made the signature. If it is not available,
+    /// use [`evaluate_with_context`] instead.
+    ///
+    /// [`evaluate_with_context`]: #method.evaluate_with_context
+    pub fn evaluate(&self, result: &VerificationResult) -> PolicyReport {
+        self.evaluate_at(result, SystemTime::now())
+    }
+
+    /// Evaluates the policy like [`evaluate`], but as of the time `now` instead of the
+    /// current time.
+    ///
+    /// [`evaluate`]: #method.evaluate
+    pub fn evaluate_at(&self, result: &VerificationResult, now: SystemTime) -> PolicyReport {
+        self.evaluate_(result, now, |sig| sig.key())
+    }
+
+    /// Evaluates the policy, looking up the keys that made each signature using `ctx` if
+    /// they are not embedded in the result.
+    pub fn evaluate_with_context(
+        &self, ctx: &mut Context, result: &VerificationResult,
+    ) -> PolicyReport {
+        self.evaluate_(result, SystemTime::now(), |sig| {
+            sig.key()
+                .or_else(|| sig.fingerprint_raw().and_then(|fpr| ctx.get_key(fpr).ok()))
+        })
+    }
+
+    fn evaluate_(
+        &self, result: &VerificationResult, now: SystemTime,
+        mut lookup: impl FnMut(&Signature<'_>) -> Option<Key>,
+    ) -> PolicyReport {
+        let signatures: Vec<_> = result
+            .signatures()
+            .enumerate()
+            .map(|(index, sig)| {
+                let key = lookup(&sig);
+                SignatureReport {
+                    index,
+                    fingerprint: sig.fingerprint().ok().map(|s| s.to_owned()),
+                    violations: self.check(&sig, key.as_ref(), now),
+                }
+            })
+            .collect();
+        PolicyReport {
+            signatures,
+            required: self.min_signatures,
+        }
+    }
+
+    fn check(&self, sig: &Signature<'_>, key: Option<&Key>, now: SystemTime) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        if let Err(e) = sig.status() {
+            violations.push(Violation::BadStatus(e));
+        }
+
+        let missing = self.required_summary - sig.summary();
+        if !missing.is_empty() {
+            violations.push(Violation::MissingSummary(missing));
+        }
+
+        let fpr = sig.fingerprint().ok().map(normalize_fpr);
+        if !self.allowed_signers.is_empty() && !self.is_allowed(fpr.as_ref(), key) {
+            violations.push(Violation::UnknownSigner);
+        }
+
+        let hash = sig.hash_algorithm();
+        if self.banned_hashes.contains(&hash) {
+            violations.push(Violation::BannedHash(hash));
+        }
+
+        let algo = algorithm_family(sig.key_algorithm());
+        if let Some(&(_, required)) = self.min_key_sizes.iter().find(|&&(x, _)| x == algo) {
+            match key.and_then(|k| signing_subkey_length(k, fpr.as_ref())) {
+                Some(length) if length < required => {
+                    violations.push(Violation::WeakKey {
+                        algorithm: algo,
+                        length,
+                        required,
+                    });
+                }
+                Some(_) => (),
+                None => violations.push(Violation::UnknownKeySize),
+            }
+        }
+
+        if let Some(max_age) = self.max_age {
+            match sig.creation_time() {
+                Some(created) => match now.duration_since(created) {
+                    Ok(age) => {
+                        if age > max_age {
+                            violations.push(Violation::TooOld(age));
+                        }
+                    }
+                    Err(e) => {
+                        if e.duration() > MAX_CLOCK_SKEW {
+                            violations.push(Violation::CreatedInFuture(e.duration()));
+                        }
+                    }
+                },
+                None => violations.push(Violation::NoCreationTime),
+            }
+        }
+
+        if let Some(expires) = sig.expiration_time() {
+            if expires <= now {
+                violations.push(Violation::Expired);
+            }
+        }
+
+        for (name, value) in &self.required_notations {
+            let found = sig.notations().any(|n| {
+                (n.name() == Ok(name))
+                    && value
+                        .as_ref()
+                        .map_or(true, |v| n.value().map_or(false, |x| x == v))
+            });
+            if !found {
+                violations.push(Violation::MissingNotation(name.clone()));
+            }
+        }
+        violations
+    }
+
+    fn is_allowed(&self, fpr: Option<&String>, key: Option<&Key>) -> bool {
+        let matches = |x: &str| self.allowed_signers.contains(&normalize_fpr(x));
+        if fpr.map_or(false, |f| self.allowed_signers.contains(f)) {
+            return true;
+        }
+        key.map_or(false, |k| {
+            k.fingerprint().map_or(false, matches)
+                && fpr.map_or(false, |f| {
+                    k.subkeys()
+                        .any(|s| s.fingerprint().map_or(false, |x| normalize_fpr(x) == *f))
+                })
+        })
+    }
+}
+
+/// The outcome of evaluating a `VerifyPolicy` against a `VerificationResult`.
+#[derive(Debug, Clone)]
+pub struct PolicyReport {
+    signatures: Vec<SignatureReport>,
+    required: usize,
+}
+
+impl PolicyReport {
+    /// Returns `true` if enough signatures satisfied the policy.
+    #[inline]
+    pub fn is_accepted(&self) -> bool {
+        self.accepted_count() >= self.required
+    }
+
+    /// Returns the number of signatures that satisfied the policy.
+    #[inline]
+    pub fn accepted_count(&self) -> usize {
+        self.signatures.iter().filter(|s| s.is_accepted()).count()
+    }
+
+    /// Returns the number of accepted signatures the policy requires.
+    #[inline]
+    pub fn required_count(&self) -> usize {
+        self.required
+    }
+
+    /// Returns the per-signature results in the same order as the signatures in the
+    /// `VerificationResult`.
+    #[inline]
+    pub fn signatures(&self) -> &[SignatureReport] {
+        &self.signatures
+    }
+
+    /// Converts the report into a `Result`, returning `Error::BAD_SIGNATURE` if it was not
+    /// accepted.
+    #[inline]
+    pub fn into_result(self) -> crate::Result<PolicyReport> {
+        if self.is_accepted() {
+            Ok(self)
+        } else {
+            Err(Error::BAD_SIGNATURE)
+        }
+    }
+}
+
+impl fmt::Display for PolicyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({} of {} required signatures accepted)",
+            if self.is_accepted() {
+                "accepted"
+            } else {
+                "rejected"
+            },
+            self.accepted_count(),
+            self.required
+        )?;
+        for sig in &self.signatures {
+            write!(f, "\n  {}", sig)?;
+        }
+        Ok(())
+    }
+}
+
+/// The outcome of evaluating a `VerifyPolicy` against a single signature.
+#[derive(Debug, Clone)]
+pub struct SignatureReport {
+    index: usize,
+    fingerprint: Option<String>,
+    violations: Vec<Violation>,
+}
+
+impl SignatureReport {
+    /// Returns the position of the signature in the `VerificationResult`.
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    #[inline]
+    pub fn fingerprint(&self) -> Option<&str> {
+        self.fingerprint.as_ref().map(|s| &**s)
+    }
+
+    #[inline]
+    pub fn is_accepted(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Returns the requirements of the policy that the signature did not meet.
+    #[inline]
+    pub fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
+}
+
+impl fmt::Display for SignatureReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "signature {} ({}): ",
+            self.index,
+            self.fingerprint().unwrap_or("[none]")
+        )?;
+        if self.violations.is_empty() {
+            return f.write_str("ok");
+        }
+        for (i, v) in self.violations.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", v)?;
+        }
+        Ok(())
+    }
+}
+
+/// A requirement of a `VerifyPolicy` that a signature did not meet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// The signature status was not successful.
+    BadStatus(Error),
+    /// The signature summary lacked the contained required flags.
+    MissingSummary(SignatureSummary),
+    /// The signature was not made by one of the allowed signers.
+    UnknownSigner,
+    /// The signature was made using a banned hash algorithm.
+    BannedHash(HashAlgorithm),
+    /// The key that made the signature is too short.
+    WeakKey {
+        algorithm: KeyAlgorithm,
+        length: usize,
+        required: usize,
+    },
+    /// The key that made the signature was not available to check its size.
+    UnknownKeySize,
+    /// The signature was created longer ago than allowed.
+    TooOld(Duration),
+    /// The signature was created further in the future than the allowed clock skew.
+    CreatedInFuture(Duration),
+    /// The signature has no creation time, so its age could not be checked.
+    NoCreationTime,
+    /// The signature has expired.
+    Expired,
+    /// The signature lacks the named notation.
+    MissingNotation(String),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Violation::BadStatus(e) => write!(f, "bad status: {}", e),
+            Violation::MissingSummary(s) => write!(f, "missing summary flags: {:?}", s),
+            Violation::UnknownSigner => f.write_str("signer not allowed"),
+            Violation::BannedHash(h) => write!(f, "banned hash algorithm: {}", h),
+            Violation::WeakKey {
+                algorithm,
+                length,
+                required,
+            } => write!(
+                f,
+                "{} key too short: {} < {} bits",
+                algorithm, length, required
+            ),
+            Violation::UnknownKeySize => f.write_str("key size unknown"),
+            Violation::TooOld(age) => write!(f, "signature too old: {}s", age.as_secs()),
+            Violation::CreatedInFuture(ahead) => {
+                write!(f, "signature created in the future: {}s", ahead.as_secs())
+            }
+            Violation::NoCreationTime => f.write_str("signature has no creation time"),
+            Violation::Expired => f.write_str("signature expired"),
+            Violation::MissingNotation(ref name) => write!(f, "missing notation: {}", name),
+        }
+    }
+}
+
+fn normalize_fpr(fpr: &str) -> String {
+    fpr.chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+fn algorithm_family(algo: KeyAlgorithm) -> KeyAlgorithm {
+    match algo {
+        KeyAlgorithm::RsaEncrypt | KeyAlgorithm::RsaSign => KeyAlgorithm::Rsa,
+        KeyAlgorithm::ElgamalEncrypt => KeyAlgorithm::Elgamal,
+        other => other,
+    }
+}
+
+/// Returns the length of the subkey that made the signature, or `None` if it is not part of
+/// `key`. The length of another subkey must never be checked in its place.
+fn signing_subkey_length(key: &Key, fpr: Option<&String>) -> Option<usize> {
+    fpr.and_then(|f| {
+        key.subkeys()
+            .find(|s| {
+                s.fingerprint().map_or(false, |x| normalize_fpr(x) == *f)
+                    || s.id().map_or(false, |x| f.ends_with(&normalize_fpr(x)))
+            })
+            .map(|s| s.length())
+    })
+}
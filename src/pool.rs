@@ -0,0 +1,260 @@
+//! A pool of preconfigured contexts for multi-threaded programs.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use gpgme::{pool::ContextPool, Context, Protocol};
+//! use std::{sync::Arc, thread};
+//!
+//! let pool = Arc::new(ContextPool::new(4, || {
+//!     let mut ctx = Context::from_protocol(Protocol::OpenPgp)?;
+//!     ctx.set_armor(true);
+//!     Ok(ctx)
+//! }));
+//! let workers: Vec<_> = (0..16)
+//!     .map(|_| {
+//!         let pool = pool.clone();
+//!         thread::spawn(move || {
+//!             let mut ctx = pool.get().unwrap();
+//!             let mut ciphertext = Vec::new();
+//!             ctx.encrypt_symmetric("Hello, World!", &mut ciphertext).unwrap();
+//!         })
+//!     })
+//!     .collect();
+//! for worker in workers {
+//!     worker.join().unwrap();
+//! }
+//! ```
+use std::{
+    fmt,
+    ops::{Deref, DerefMut},
+    ptr,
+    sync::{Condvar, Mutex, MutexGuard},
+    thread,
+    time::{Duration, Instant},
+};
+
+use ffi;
+
+use crate::{config::ContextConfig, Context, Result};
+
+type Init = dyn Fn() -> Result<Context> + Send + Sync;
+
+struct State {
+    idle: Vec<Context>,
+    /// Number of contexts that are idle or handed out.
+    open: usize,
+}
+
+/// A bounded pool of contexts created from a template.
+///
+/// At most `max_size` contexts exist at any time, which also bounds the number of engine
+/// processes started by the pool's users. When a [`PooledContext`] is dropped its context is
+/// reset and made available again; the signers, signature notations and sender are cleared
+/// and any passphrase provider, progress handler or status handler is removed. Other
+/// settings changed on a pooled context are kept, so callers that change them should restore
+/// them or [`discard`](PooledContext::discard) the context.
+pub struct ContextPool {
+    init: Box<Init>,
+    max_size: usize,
+    state: Mutex<State>,
+    available: Condvar,
+}
+
+impl fmt::Debug for ContextPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.lock();
+        f.debug_struct("ContextPool")
+            .field("max_size", &self.max_size)
+            .field("open", &state.open)
+            .field("idle", &state.idle.len())
+            .finish()
+    }
+}
+
+impl ContextPool {
+    /// Creates a pool of at most `max_size` contexts that are created on demand by `init`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_size` is zero.
+    pub fn new<F>(max_size: usize, init: F) -> Self
+    where F: Fn() -> Result<Context> + Send + Sync + 'static {
+        assert!(max_size > 0, "pool size must be positive");
+        ContextPool {
+            init: Box::new(init),
+            max_size,
+            state: Mutex::new(State {
+                idle: Vec::new(),
+                open: 0,
+            }),
+            available: Condvar::new(),
+        }
+    }
+
+    /// Creates a pool of at most `max_size` contexts configured by `config`.
+    pub fn from_config(max_size: usize, config: ContextConfig) -> Self {
+        ContextPool::new(max_size, move || config.build())
+    }
+
+    #[inline]
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// Returns the number of contexts currently handed out.
+    pub fn in_use(&self) -> usize {
+        let state = self.lock();
+        state.open - state.idle.len()
+    }
+
+    /// Returns a context, blocking until one is available.
+    pub fn get(&self) -> Result<PooledContext<'_>> {
+        self.get_until(None).map(|ctx| ctx.unwrap())
+    }
+
+    /// Returns a context if one is available without blocking.
+    pub fn try_get(&self) -> Result<Option<PooledContext<'_>>> {
+        self.get_until(Some(Instant::now()))
+    }
+
+    /// Returns a context, blocking for at most `timeout` until one is available.
+    ///
+    /// A timeout too large to represent blocks like [`get`](ContextPool::get).
+    pub fn get_timeout(&self, timeout: Duration) -> Result<Option<PooledContext<'_>>> {
+        self.get_until(Instant::now().checked_add(timeout))
+    }
+
+    fn get_until(&self, deadline: Option<Instant>) -> Result<Option<PooledContext<'_>>> {
+        let mut state = self.lock();
+        loop {
+            if let Some(ctx) = state.idle.pop() {
+                return Ok(Some(self.wrap(ctx)));
+            }
+
+            if state.open < self.max_size {
+                state.open += 1;
+                drop(state);
+                return match (self.init)() {
+                    Ok(ctx) => Ok(Some(self.wrap(ctx))),
+                    Err(err) => {
+                        self.release(None);
+                        Err(err)
+                    }
+                };
+            }
+
+            state = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Ok(None);
+                    }
+                    self.available
+                        .wait_timeout(state, deadline - now)
+                        .unwrap_or_else(|e| e.into_inner())
+                        .0
+                }
+                None => self
+                    .available
+                    .wait(state)
+                    .unwrap_or_else(|e| e.into_inner()),
+            };
+        }
+    }
+
+    fn wrap(&self, ctx: Context) -> PooledContext<'_> {
+        PooledContext {
+            ctx: Some(ctx),
+            pool: self,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Returns a context to the pool, or frees its slot if `ctx` is `None`.
+    fn release(&self, ctx: Option<Context>) {
+        let mut state = self.lock();
+        match ctx {
+            Some(ctx) => state.idle.push(ctx),
+            None => state.open -= 1,
+        }
+        self.available.notify_one();
+    }
+}
+
+/// Clears the per-operation state of a context before it is reused.
+fn reset(ctx: &mut Context) -> Result<()> {
+    ctx.clear_signers();
+    ctx.clear_signature_notations();
+    ctx.clear_sender()?;
+    unsafe {
+        ffi::gpgme_set_passphrase_cb(ctx.as_raw(), None, ptr::null_mut());
+        ffi::gpgme_set_progress_cb(ctx.as_raw(), None, ptr::null_mut());
+        ffi::gpgme_set_status_cb(ctx.as_raw(), None, ptr::null_mut());
+    }
+    Ok(())
+}
+
+/// A context borrowed from a [`ContextPool`].
+///
+/// The context is returned to the pool when this value is dropped. Contexts are not returned
+/// while the thread is panicking, as they may have been left in an inconsistent state.
+pub struct PooledContext<'a> {
+    ctx: Option<Context>,
+    pool: &'a ContextPool,
+}
+
+impl fmt::Debug for PooledContext<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PooledContext").field(&self.ctx).finish()
+    }
+}
+
+impl PooledContext<'_> {
+    /// Removes the context from the pool, e.g. because its settings were changed.
+    ///
+    /// The pool creates a new context in its place when needed.
+    pub fn discard(mut self) {
+        drop(self.ctx.take());
+        self.pool.release(None);
+    }
+
+    /// Removes the context from the pool and returns it.
+    pub fn detach(mut self) -> Context {
+        let ctx = self.ctx.take().unwrap();
+        self.pool.release(None);
+        ctx
+    }
+}
+
+impl Deref for PooledContext<'_> {
+    type Target = Context;
+
+    #[inline]
+    fn deref(&self) -> &Context {
+        self.ctx.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledContext<'_> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Context {
+        self.ctx.as_mut().unwrap()
+    }
+}
+
+impl Drop for PooledContext<'_> {
+    fn drop(&mut self) {
+        if let Some(mut ctx) = self.ctx.take() {
+            if thread::panicking() || reset(&mut ctx).is_err() {
+                drop(ctx);
+                self.pool.release(None);
+            } else {
+                self.pool.release(Some(ctx));
+            }
+        }
+    }
+}
@@ -0,0 +1,399 @@
+use std::{ffi::CStr, fmt, time::UNIX_EPOCH};
+
+use ffi;
+
+use crate::{
+    utils::CStrArgument, Context, Key, KeyListMode, Result, TofuPolicy, UserId, Validity,
+};
+
+/// Maps email addresses to the preferred key to encrypt to for each address.
+///
+/// A key is only considered usable for an address if it is not revoked, expired, disabled or
+/// invalid, it can encrypt, the user id matching the address has at least the configured
+/// validity and the TOFU policy for that user id is not `TofuPolicy::Bad`. If more than one
+/// usable key exists, the one whose matching user id has the highest validity is chosen;
+/// keys that tie are reported as ambiguous.
+///
+/// # Examples
+///
+/// ```no_run
+/// use gpgme::{resolver::KeyResolver, Context, Protocol};
+///
+/// let mut ctx = Context::from_protocol(Protocol::OpenPgp).unwrap();
+/// ctx.set_sender("alice@example.org").unwrap();
+/// let resolution = KeyResolver::new()
+///     .locate(true)
+///     .resolve(&mut ctx, &["bob@example.org", "carol@example.org"])
+///     .unwrap();
+/// if resolution.is_complete() {
+///     let keys: Vec<_> = resolution.keys().cloned().collect();
+///     ctx.encrypt(&keys, "Hello, World!", &mut Vec::new()).unwrap();
+/// }
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct KeyResolver {
+    min_validity: Validity,
+    locate: bool,
+    encrypt_to_self: bool,
+}
+
+impl Default for KeyResolver {
+    fn default() -> Self {
+        KeyResolver {
+            min_validity: Validity::Marginal,
+            locate: false,
+            encrypt_to_self: true,
+        }
+    }
+}
+
+impl KeyResolver {
+    /// Creates a resolver that requires at least marginal validity and resolves the
+    /// context's sender for encrypt-to-self.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the minimum validity a matching user id must have.
+    pub fn min_validity(mut self, validity: Validity) -> Self {
+        self.min_validity = validity;
+        self
+    }
+
+    /// Controls whether addresses without a usable local key are looked up again using
+    /// `KeyListMode::LOCATE`.
+    pub fn locate(mut self, enabled: bool) -> Self {
+        self.locate = enabled;
+        self
+    }
+
+    /// Controls whether the address set with `Context::set_sender` is resolved as well.
+    pub fn encrypt_to_self(mut self, enabled: bool) -> Self {
+        self.encrypt_to_self = enabled;
+        self
+    }
+
+    /// Resolves each address to a key.
+    ///
+    /// Addresses may be plain addresses or full user ids such as
+    /// `"Alice <alice@example.org>"`.
+    pub fn resolve<I>(&self, ctx: &mut Context, addresses: I) -> Result<Resolution>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>, {
+        let recipients = addresses
+            .into_iter()
+            .map(|addr| self.resolve_address(ctx, addr.as_ref(), false))
+            .collect::<Result<_>>()?;
+        let sender = if self.encrypt_to_self {
+            match ctx.sender().ok().map(|s| s.to_owned()) {
+                Some(sender) => Some(self.resolve_address(ctx, &sender, true)?),
+                None => None,
+            }
+        } else {
+            None
+        };
+        Ok(Resolution { recipients, sender })
+    }
+
+    fn resolve_address(
+        &self, ctx: &mut Context, address: &str, secret: bool,
+    ) -> Result<AddressResolution> {
+        let address = addr_spec(address).unwrap_or_else(|| address.trim().to_owned());
+        let status = match self.resolve_with(ctx, &address, secret)? {
+            ResolutionStatus::Unresolved(rejected) if self.locate && !secret => {
+                let mode = ctx.key_list_mode();
+                ctx.set_key_list_mode(mode | KeyListMode::LOCATE)?;
+                let located = self.resolve_with(ctx, &address, secret);
+                ctx.set_key_list_mode(mode)?;
+                match located? {
+                    ResolutionStatus::Unresolved(mut more) => {
+                        for r in rejected {
+                            if !more.contains(&r) {
+                                more.push(r);
+                            }
+                        }
+                        ResolutionStatus::Unresolved(more)
+                    }
+                    other => other,
+                }
+            }
+            status => status,
+        };
+        Ok(AddressResolution { address, status })
+    }
+
+    fn resolve_with(
+        &self, ctx: &mut Context, address: &str, secret: bool,
+    ) -> Result<ResolutionStatus> {
+        let candidates: Vec<Key> = if secret {
+            let secret_keys = ctx
+                .find_secret_keys(Some(address))?
+                .collect::<Result<Vec<_>>>()?;
+            secret_keys
+                .iter()
+                .filter_map(|k| k.fingerprint_raw().and_then(|fpr| ctx.get_key(fpr).ok()))
+                .collect()
+        } else {
+            ctx.find_keys(Some(address))?.collect::<Result<_>>()?
+        };
+
+        let mut usable = Vec::new();
+        let mut rejected = Vec::new();
+        for key in candidates {
+            let uid = match key.user_ids().find(|u| matches_address(u, address)) {
+                Some(uid) => uid,
+                None => continue,
+            };
+            match self.check(&key, &uid) {
+                Ok(()) => usable.push((validity_rank(uid.validity()), key.clone())),
+                Err(reason) => rejected.push(Rejection {
+                    fingerprint: key.fingerprint().ok().map(|s| s.to_owned()),
+                    reason,
+                }),
+            }
+        }
+
+        let best = match usable.iter().map(|&(rank, _)| rank).max() {
+            Some(best) => best,
+            None => return Ok(ResolutionStatus::Unresolved(rejected)),
+        };
+        let mut preferred: Vec<Key> = usable
+            .into_iter()
+            .filter(|&(rank, _)| rank == best)
+            .map(|(_, key)| key)
+            .collect();
+        if preferred.len() == 1 {
+            Ok(ResolutionStatus::Resolved(preferred.remove(0)))
+        } else {
+            preferred.sort_by_key(|k| std::cmp::Reverse(newest_encryption_subkey(k)));
+            Ok(ResolutionStatus::Ambiguous(preferred))
+        }
+    }
+
+    fn check(&self, key: &Key, uid: &UserId<'_>) -> ::std::result::Result<(), RejectionReason> {
+        if key.is_revoked() || uid.is_revoked() {
+            return Err(RejectionReason::Revoked);
+        }
+        if key.is_expired() {
+            return Err(RejectionReason::Expired);
+        }
+        if key.is_disabled() {
+            return Err(RejectionReason::Disabled);
+        }
+        if key.is_invalid() || uid.is_invalid() {
+            return Err(RejectionReason::Invalid);
+        }
+        if !key.can_encrypt() {
+            return Err(RejectionReason::CannotEncrypt);
+        }
+        if uid
+            .tofu_info()
+            .map_or(false, |info| info.policy() == TofuPolicy::Bad)
+        {
+            return Err(RejectionReason::TofuBad);
+        }
+        let validity = uid.validity();
+        if (validity == Validity::Never)
+            || (validity_rank(validity) < validity_rank(self.min_validity))
+        {
+            return Err(RejectionReason::InsufficientValidity(validity));
+        }
+        Ok(())
+    }
+}
+
+/// The result of resolving a set of addresses with a `KeyResolver`.
+#[derive(Debug, Clone)]
+pub struct Resolution {
+    recipients: Vec<AddressResolution>,
+    sender: Option<AddressResolution>,
+}
+
+impl Resolution {
+    /// Returns `true` if every address, including the sender if requested, was resolved to
+    /// a single key.
+    #[inline]
+    pub fn is_complete(&self) -> bool {
+        self.recipients
+            .iter()
+            .chain(self.sender.as_ref())
+            .all(|r| r.status.is_resolved())
+    }
+
+    /// Returns the resolution for each address in the order they were provided.
+    #[inline]
+    pub fn recipients(&self) -> &[AddressResolution] {
+        &self.recipients
+    }
+
+    /// Returns the resolution for the sender, if encrypt-to-self was requested and a sender
+    /// was set.
+    #[inline]
+    pub fn sender(&self) -> Option<&AddressResolution> {
+        self.sender.as_ref()
+    }
+
+    /// Returns the resolved keys, including the sender's, without duplicates.
+    pub fn keys(&self) -> impl Iterator<Item = &Key> {
+        let mut seen = Vec::new();
+        self.recipients
+            .iter()
+            .chain(self.sender.as_ref())
+            .filter_map(|r| r.key())
+            .filter(move |k| {
+                let fpr = k.fingerprint_raw();
+                if seen.contains(&fpr) {
+                    false
+                } else {
+                    seen.push(fpr);
+                    true
+                }
+            })
+    }
+
+    /// Returns the addresses that are either ambiguous or could not be resolved.
+    pub fn problems(&self) -> impl Iterator<Item = &AddressResolution> {
+        self.recipients
+            .iter()
+            .chain(self.sender.as_ref())
+            .filter(|r| !r.status.is_resolved())
+    }
+}
+
+/// The result of resolving a single address.
+#[derive(Debug, Clone)]
+pub struct AddressResolution {
+    address: String,
+    status: ResolutionStatus,
+}
+
+impl AddressResolution {
+    /// Returns the normalized address that was resolved.
+    #[inline]
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    #[inline]
+    pub fn status(&self) -> &ResolutionStatus {
+        &self.status
+    }
+
+    /// Returns the chosen key, if the address was resolved.
+    #[inline]
+    pub fn key(&self) -> Option<&Key> {
+        match self.status {
+            ResolutionStatus::Resolved(ref key) => Some(key),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ResolutionStatus {
+    /// The address was resolved to a single preferred key.
+    Resolved(Key),
+    /// More than one usable key is equally preferred. The keys are ordered by the creation
+    /// time of their newest encryption subkey, newest first.
+    Ambiguous(Vec<Key>),
+    /// No usable key was found. Contains the reasons the keys that were found were rejected.
+    Unresolved(Vec<Rejection>),
+}
+
+impl ResolutionStatus {
+    #[inline]
+    pub fn is_resolved(&self) -> bool {
+        match *self {
+            ResolutionStatus::Resolved(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// A key that matched an address but was not usable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rejection {
+    fingerprint: Option<String>,
+    reason: RejectionReason,
+}
+
+impl Rejection {
+    #[inline]
+    pub fn fingerprint(&self) -> Option<&str> {
+        self.fingerprint.as_ref().map(|s| &**s)
+    }
+
+    #[inline]
+    pub fn reason(&self) -> RejectionReason {
+        self.reason
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum RejectionReason {
+    Revoked,
+    Expired,
+    Disabled,
+    Invalid,
+    CannotEncrypt,
+    TofuBad,
+    InsufficientValidity(Validity),
+}
+
+impl fmt::Display for RejectionReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            RejectionReason::Revoked => f.write_str("revoked"),
+            RejectionReason::Expired => f.write_str("expired"),
+            RejectionReason::Disabled => f.write_str("disabled"),
+            RejectionReason::Invalid => f.write_str("invalid"),
+            RejectionReason::CannotEncrypt => f.write_str("not usable for encryption"),
+            RejectionReason::TofuBad => f.write_str("TOFU policy is bad"),
+            RejectionReason::InsufficientValidity(v) => {
+                write!(f, "insufficient user id validity ({})", v)
+            }
+        }
+    }
+}
+
+fn validity_rank(validity: Validity) -> u8 {
+    match validity {
+        Validity::Ultimate => 4,
+        Validity::Full => 3,
+        Validity::Marginal => 2,
+        Validity::Undefined | Validity::Unknown => 1,
+        Validity::Never => 0,
+    }
+}
+
+fn matches_address(uid: &UserId<'_>, address: &str) -> bool {
+    uid.email()
+        .ok()
+        .map(|e| e.trim_start_matches('<').trim_end_matches('>'))
+        .map_or(false, |e| e.eq_ignore_ascii_case(address))
+}
+
+fn newest_encryption_subkey(key: &Key) -> u64 {
+    key.subkeys()
+        .filter(|s| s.can_encrypt() && !s.is_revoked() && !s.is_expired())
+        .filter_map(|s| s.creation_time())
+        .filter_map(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .max()
+        .unwrap_or(0)
+}
+
+fn addr_spec(uid: &str) -> Option<String> {
+    let uid = uid.try_into_cstr().ok()?;
+    unsafe {
+        ffi::gpgme_addrspec_from_uid(uid.as_ref().as_ptr())
+            .as_mut()
+            .map(|raw| {
+                let result = CStr::from_ptr(raw).to_string_lossy().into_owned();
+                ffi::gpgme_free(raw as *mut _ as *mut _);
+                result
+            })
+    }
+}
@@ -282,6 +282,12 @@ impl<'a> Import<'a> {
         unsafe { (*self.as_raw()).fpr.as_ref().map(|s| CStr::from_ptr(s)) }
     }
 
+    /// Returns the fingerprint as a validated `Fingerprint`.
+    #[inline]
+    pub fn to_fingerprint(&self) -> Option<crate::Fingerprint> {
+        self.fingerprint().ok().and_then(|s| s.parse().ok())
+    }
+
     #[inline]
     pub fn result(&self) -> Result<()> {
         unsafe {
@@ -629,6 +635,12 @@ impl<'a> Signature<'a> {
         unsafe { (*self.as_raw()).fpr.as_ref().map(|s| CStr::from_ptr(s)) }
     }
 
+    /// Returns the fingerprint as a validated `Fingerprint`.
+    #[inline]
+    pub fn to_fingerprint(&self) -> Option<crate::Fingerprint> {
+        self.fingerprint().ok().and_then(|s| s.parse().ok())
+    }
+
     #[inline]
     pub fn status(&self) -> Result<()> {
         unsafe {
@@ -0,0 +1,331 @@
+//! Memory buffers for sensitive data such as plaintext and passphrases.
+//!
+//! A [`SecureBuffer`] keeps its contents in memory that is locked into RAM (where the
+//! platform and the `RLIMIT_MEMLOCK` resource limit allow it) and overwritten with zeros
+//! before it is released, including when the buffer has to grow.
+//!
+//! Note that the engine and GPGME may still hold their own copies of the data while an
+//! operation is in progress.
+use std::{
+    alloc::{self, Layout},
+    cmp, fmt,
+    io::{self, Read, Seek, SeekFrom, Write},
+    ptr, slice,
+    str::{self, Utf8Error},
+    sync::atomic::{self, Ordering},
+};
+
+use libc;
+
+use crate::{Data, IntoData, Result};
+
+/// A growable byte buffer that is locked into memory and wiped on drop.
+///
+/// The buffer behaves like an `io::Cursor<Vec<u8>>`: reads and writes start at the current
+/// position, which can be changed with `Seek`. A mutable reference to a buffer can be used
+/// anywhere an [`IntoData`] is accepted, e.g. as the plaintext sink for
+/// [`Context::decrypt`](crate::Context::decrypt) or the source for
+/// [`Context::encrypt`](crate::Context::encrypt).
+///
+/// # Examples
+///
+/// ```no_run
+/// use gpgme::{secure::SecureBuffer, Context, Protocol};
+///
+/// let mut ctx = Context::from_protocol(Protocol::OpenPgp).unwrap();
+/// let mut plaintext = SecureBuffer::new();
+/// let ciphertext = std::fs::read("secret.gpg").unwrap();
+/// ctx.decrypt(&ciphertext, &mut plaintext).unwrap();
+/// ```
+pub struct SecureBuffer {
+    ptr: *mut u8,
+    cap: usize,
+    len: usize,
+    pos: usize,
+    locked: bool,
+}
+
+unsafe impl Send for SecureBuffer {}
+unsafe impl Sync for SecureBuffer {}
+
+impl SecureBuffer {
+    /// Creates an empty buffer. No memory is allocated until data is written.
+    #[inline]
+    pub fn new() -> Self {
+        SecureBuffer {
+            ptr: ptr::NonNull::dangling().as_ptr(),
+            cap: 0,
+            len: 0,
+            pos: 0,
+            locked: false,
+        }
+    }
+
+    /// Creates an empty buffer able to hold at least `capacity` bytes without reallocating.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut buffer = SecureBuffer::new();
+        buffer.reserve(capacity);
+        buffer
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    /// Returns `true` if the memory backing the buffer could be locked into RAM.
+    ///
+    /// Locking is best effort; an empty buffer is never locked.
+    #[inline]
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.ptr, self.len) }
+    }
+
+    #[inline]
+    pub fn as_mut_bytes(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> ::std::result::Result<&str, Utf8Error> {
+        str::from_utf8(self.as_bytes())
+    }
+
+    #[inline]
+    pub fn position(&self) -> u64 {
+        self.pos as u64
+    }
+
+    #[inline]
+    pub fn set_position(&mut self, pos: u64) {
+        self.pos = pos as usize;
+    }
+
+    /// Shortens the buffer to `len` bytes, wiping the removed contents.
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len {
+            unsafe {
+                wipe(self.ptr.add(len), self.len - len);
+            }
+            self.len = len;
+        }
+    }
+
+    /// Wipes the contents of the buffer and resets the position to the start.
+    pub fn clear(&mut self) {
+        self.truncate(0);
+        self.pos = 0;
+    }
+
+    /// Ensures the buffer can hold at least `additional` more bytes past its current length.
+    pub fn reserve(&mut self, additional: usize) {
+        let needed = self.len.checked_add(additional).expect("capacity overflow");
+        self.grow(needed);
+    }
+
+    fn grow(&mut self, needed: usize) {
+        if needed <= self.cap {
+            return;
+        }
+
+        // Allocations are whole pages so that locking a buffer never affects, and unlocking
+        // it never releases, the memory of another allocation.
+        let page = page_size();
+        let cap = cmp::max(self.cap.saturating_mul(2), needed)
+            .checked_add(page - 1)
+            .expect("capacity overflow")
+            / page
+            * page;
+        let layout = page_layout(cap);
+        let ptr = unsafe { alloc::alloc_zeroed(layout) };
+        if ptr.is_null() {
+            alloc::handle_alloc_error(layout);
+        }
+        let locked = unsafe { lock(ptr, cap) };
+        unsafe {
+            ptr::copy_nonoverlapping(self.ptr, ptr, self.len);
+            self.release();
+        }
+        self.ptr = ptr;
+        self.cap = cap;
+        self.locked = locked;
+    }
+
+    /// Wipes, unlocks and frees the current allocation.
+    unsafe fn release(&mut self) {
+        if self.cap == 0 {
+            return;
+        }
+        wipe(self.ptr, self.cap);
+        if self.locked {
+            unlock(self.ptr, self.cap);
+        }
+        alloc::dealloc(self.ptr, page_layout(self.cap));
+    }
+}
+
+impl Drop for SecureBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            self.release();
+        }
+    }
+}
+
+impl Default for SecureBuffer {
+    #[inline]
+    fn default() -> Self {
+        SecureBuffer::new()
+    }
+}
+
+impl fmt::Debug for SecureBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecureBuffer")
+            .field("len", &self.len)
+            .field("capacity", &self.cap)
+            .field("locked", &self.locked)
+            .finish()
+    }
+}
+
+impl AsRef<[u8]> for SecureBuffer {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl<'a> From<&'a [u8]> for SecureBuffer {
+    fn from(bytes: &'a [u8]) -> Self {
+        let mut buffer = SecureBuffer::with_capacity(bytes.len());
+        buffer.write_all(bytes).unwrap();
+        buffer.pos = 0;
+        buffer
+    }
+}
+
+impl Read for SecureBuffer {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let start = cmp::min(self.pos, self.len);
+        let n = cmp::min(buf.len(), self.len - start);
+        buf[..n].copy_from_slice(&self.as_bytes()[start..(start + n)]);
+        self.pos = start + n;
+        Ok(n)
+    }
+}
+
+impl Write for SecureBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let end = self
+            .pos
+            .checked_add(buf.len())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "buffer too large"))?;
+        self.grow(end);
+        unsafe {
+            ptr::copy_nonoverlapping(buf.as_ptr(), self.ptr.add(self.pos), buf.len());
+        }
+        self.pos = end;
+        self.len = cmp::max(self.len, end);
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for SecureBuffer {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(n) => {
+                self.pos = n as usize;
+                return Ok(n);
+            }
+            SeekFrom::End(n) => (self.len as u64, n),
+            SeekFrom::Current(n) => (self.pos as u64, n),
+        };
+        let new = if offset >= 0 {
+            base.checked_add(offset as u64)
+        } else {
+            base.checked_sub(offset.wrapping_neg() as u64)
+        };
+        match new {
+            Some(n) => {
+                self.pos = n as usize;
+                Ok(n)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )),
+        }
+    }
+}
+
+impl<'a> IntoData<'a> for &'a mut SecureBuffer {
+    type Output = Data<'a>;
+
+    fn into_data(self) -> Result<Data<'a>> {
+        Data::from_seekable_stream(self).map_err(|e| e.error())
+    }
+}
+
+#[cfg(unix)]
+fn page_size() -> usize {
+    match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+        size if size > 0 => size as usize,
+        _ => 4096,
+    }
+}
+
+#[cfg(not(unix))]
+fn page_size() -> usize {
+    4096
+}
+
+fn page_layout(size: usize) -> Layout {
+    Layout::from_size_align(size, page_size()).expect("capacity overflow")
+}
+
+/// Overwrites `len` bytes at `ptr` with zeros in a way the compiler will not elide.
+unsafe fn wipe(ptr: *mut u8, len: usize) {
+    for i in 0..len {
+        ptr::write_volatile(ptr.add(i), 0);
+    }
+    atomic::compiler_fence(Ordering::SeqCst);
+}
+
+#[cfg(unix)]
+unsafe fn lock(ptr: *mut u8, len: usize) -> bool {
+    libc::mlock(ptr as *const libc::c_void, len) == 0
+}
+
+#[cfg(not(unix))]
+unsafe fn lock(_ptr: *mut u8, _len: usize) -> bool {
+    false
+}
+
+#[cfg(unix)]
+unsafe fn unlock(ptr: *mut u8, len: usize) {
+    libc::munlock(ptr as *const libc::c_void, len);
+}
+
+#[cfg(not(unix))]
+unsafe fn unlock(_ptr: *mut u8, _len: usize) {}
@@ -0,0 +1,504 @@
+//! S/MIME ([RFC 5751]) message construction and parsing for `Protocol::Cms`.
+//!
+//! CMS objects are always transferred using the base64 content transfer encoding. Signed
+//! entities are converted to canonical CRLF form before signing.
+//!
+//! [RFC 5751]: https://tools.ietf.org/html/rfc5751
+use std::fmt;
+
+use crate::{
+    base64, entity,
+    mime::{self, MimeMessage},
+    utils,
+    Context, DecryptionResult, EncryptionResult, Error, ExportMode, HashAlgorithm, ImportResult,
+    Key, Protocol, Result, SignMode, SigningResult, VerificationResult,
+};
+
+const OID_DATA: &[u8] = b"\x2a\x86\x48\x86\xf7\x0d\x01\x07\x01";
+const OID_SIGNED_DATA: &[u8] = b"\x2a\x86\x48\x86\xf7\x0d\x01\x07\x02";
+const OID_ENVELOPED_DATA: &[u8] = b"\x2a\x86\x48\x86\xf7\x0d\x01\x07\x03";
+const OID_COMPRESSED_DATA: &[u8] = b"\x2a\x86\x48\x86\xf7\x0d\x01\x09\x10\x01\x09";
+const OID_AUTH_ENVELOPED_DATA: &[u8] = b"\x2a\x86\x48\x86\xf7\x0d\x01\x09\x10\x01\x17";
+
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+const TAG_OID: u8 = 0x06;
+const TAG_INTEGER: u8 = 0x02;
+const TAG_CONTEXT_0: u8 = 0xa0;
+
+/// The `smime-type` parameter of an `application/pkcs7-mime` entity.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SmimeType {
+    EnvelopedData,
+    AuthEnvelopedData,
+    SignedData,
+    CertsOnly,
+    CompressedData,
+}
+
+impl SmimeType {
+    /// Returns the value of the `smime-type` parameter.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            SmimeType::EnvelopedData => "enveloped-data",
+            SmimeType::AuthEnvelopedData => "authEnveloped-data",
+            SmimeType::SignedData => "signed-data",
+            SmimeType::CertsOnly => "certs-only",
+            SmimeType::CompressedData => "compressed-data",
+        }
+    }
+
+    /// Parses the value of an `smime-type` parameter.
+    pub fn from_param(s: &str) -> Option<Self> {
+        [
+            SmimeType::EnvelopedData,
+            SmimeType::AuthEnvelopedData,
+            SmimeType::SignedData,
+            SmimeType::CertsOnly,
+            SmimeType::CompressedData,
+        ]
+        .iter()
+        .cloned()
+        .find(|t| t.as_str().eq_ignore_ascii_case(s))
+    }
+
+    /// Determines the type of a DER or BER encoded CMS `ContentInfo` structure.
+    ///
+    /// This is useful for messages lacking the `smime-type` parameter.
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        let (tag, info, _) = read_tlv(data)?;
+        if tag != TAG_SEQUENCE {
+            return None;
+        }
+        let (tag, oid, rest) = read_tlv(info)?;
+        if tag != TAG_OID {
+            return None;
+        }
+        match oid {
+            OID_ENVELOPED_DATA => Some(SmimeType::EnvelopedData),
+            OID_AUTH_ENVELOPED_DATA => Some(SmimeType::AuthEnvelopedData),
+            OID_COMPRESSED_DATA => Some(SmimeType::CompressedData),
+            OID_SIGNED_DATA => {
+                let (tag, content, _) = read_tlv(rest)?;
+                let (seq_tag, signed_data, _) = read_tlv(content)?;
+                if (tag != TAG_CONTEXT_0) || (seq_tag != TAG_SEQUENCE) {
+                    return None;
+                }
+                let mut last = None;
+                let mut data = signed_data;
+                while !data.is_empty() {
+                    let (tag, content, rest) = read_tlv(data)?;
+                    last = Some((tag, content));
+                    data = rest;
+                }
+                match last {
+                    Some((TAG_SET, signers)) if signers.is_empty() => Some(SmimeType::CertsOnly),
+                    _ => Some(SmimeType::SignedData),
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SmimeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A certificate a message was encrypted for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecipientCert {
+    fingerprint: Option<String>,
+    subject: Option<String>,
+    issuer_name: Option<String>,
+    issuer_serial: Option<String>,
+}
+
+impl RecipientCert {
+    fn new(key: &Key) -> Self {
+        RecipientCert {
+            fingerprint: key.fingerprint().ok().map(|s| s.to_owned()),
+            subject: key
+                .user_ids()
+                .next()
+                .and_then(|u| u.id().ok().map(|s| s.to_owned())),
+            issuer_name: key.issuer_name().ok().map(|s| s.to_owned()),
+            issuer_serial: key.issuer_serial().ok().map(|s| s.to_owned()),
+        }
+    }
+
+    #[inline]
+    pub fn fingerprint(&self) -> Option<&str> {
+        self.fingerprint.as_ref().map(|s| &**s)
+    }
+
+    /// Returns the subject of the certificate.
+    #[inline]
+    pub fn subject(&self) -> Option<&str> {
+        self.subject.as_ref().map(|s| &**s)
+    }
+
+    #[inline]
+    pub fn issuer_name(&self) -> Option<&str> {
+        self.issuer_name.as_ref().map(|s| &**s)
+    }
+
+    #[inline]
+    pub fn issuer_serial(&self) -> Option<&str> {
+        self.issuer_serial.as_ref().map(|s| &**s)
+    }
+}
+
+/// Returns the S/MIME `micalg` parameter value for the specified hash algorithm.
+pub fn micalg(algo: HashAlgorithm) -> Option<&'static str> {
+    match algo {
+        HashAlgorithm::Md5 => Some("md5"),
+        HashAlgorithm::Sha1 => Some("sha-1"),
+        HashAlgorithm::Sha224 => Some("sha-224"),
+        HashAlgorithm::Sha256 => Some("sha-256"),
+        HashAlgorithm::Sha384 => Some("sha-384"),
+        HashAlgorithm::Sha512 => Some("sha-512"),
+        _ => None,
+    }
+}
+
+fn check_protocol(ctx: &Context) -> Result<()> {
+    if ctx.protocol() != Protocol::Cms {
+        return Err(Error::UNSUPPORTED_PROTOCOL);
+    }
+    Ok(())
+}
+
+fn pkcs7_mime(smime_type: SmimeType, data: &[u8]) -> MimeMessage {
+    let mut body = base64::encode(data, 76).into_bytes();
+    body.extend_from_slice(b"\r\n");
+    let name = if smime_type == SmimeType::CertsOnly {
+        "smime.p7c"
+    } else {
+        "smime.p7m"
+    };
+    MimeMessage::new(
+        format!(
+            "application/pkcs7-mime; smime-type={}; name=\"{}\"",
+            smime_type, name
+        ),
+        body,
+    )
+    .with_header("Content-Transfer-Encoding", "base64")
+    .with_header(
+        "Content-Disposition",
+        format!("attachment; filename=\"{}\"", name),
+    )
+}
+
+/// Creates an `application/pkcs7-mime` enveloped-data message containing `entity` encrypted
+/// for `recipients`.
+///
+/// Returns the certificates the message was successfully encrypted for along with the
+/// result of the operation.
+pub fn encrypt<'k, I>(
+    ctx: &mut Context, recipients: I, entity: &[u8],
+) -> Result<(MimeMessage, EncryptionResult, Vec<RecipientCert>)>
+where I: IntoIterator<Item = &'k Key> {
+    check_protocol(ctx)?;
+    let recipients = recipients.into_iter().collect::<Vec<_>>();
+    let entity = mime::canonicalize(entity);
+    let mut ciphertext = Vec::new();
+    let result = utils::with_armor(ctx, false, |ctx| {
+        ctx.encrypt(recipients.iter().cloned(), &entity[..], &mut ciphertext)
+    })?;
+    let invalid = result
+        .invalid_recipients()
+        .filter_map(|i| i.fingerprint_raw())
+        .collect::<Vec<_>>();
+    let reported = recipients
+        .into_iter()
+        .filter(|k| k.fingerprint_raw().map_or(true, |f| !invalid.contains(&f)))
+        .map(RecipientCert::new)
+        .collect();
+    Ok((
+        pkcs7_mime(SmimeType::EnvelopedData, &ciphertext),
+        result,
+        reported,
+    ))
+}
+
+/// Creates a `multipart/signed` message containing `entity` and a detached
+/// `application/pkcs7-signature` made using the signers configured in `ctx`.
+///
+/// The certificates included in the signature are controlled by
+/// `Context::set_include_certs`.
+pub fn sign(ctx: &mut Context, entity: &[u8]) -> Result<(MimeMessage, SigningResult)> {
+    check_protocol(ctx)?;
+    let mut entity = mime::canonicalize(entity);
+    if !entity.ends_with(b"\r\n") {
+        entity.extend_from_slice(b"\r\n");
+    }
+    let mut signature = Vec::new();
+    let result = utils::with_armor(ctx, false, |ctx| {
+        ctx.sign_detached(&entity[..], &mut signature)
+    })?;
+    let hash = result
+        .new_signatures()
+        .next()
+        .map_or(HashAlgorithm::None, |s| s.hash_algorithm());
+    let micalg = micalg(hash).ok_or(Error::DIGEST_ALGO)?;
+    let signature = base64::encode(&signature, 76);
+
+    let boundary = entity::boundary(&[&entity]);
+    let mut body = Vec::new();
+    entity::push_delimiter(&mut body, &boundary, false);
+    body.extend_from_slice(&entity);
+    entity::push_delimiter(&mut body, &boundary, false);
+    body.extend_from_slice(
+        b"Content-Type: application/pkcs7-signature; name=\"smime.p7s\"\r\n\
+          Content-Transfer-Encoding: base64\r\n\
+          Content-Disposition: attachment; filename=\"smime.p7s\"\r\n\r\n",
+    );
+    body.extend_from_slice(signature.as_bytes());
+    entity::push_delimiter(&mut body, &boundary, true);
+
+    let content_type = format!(
+        "multipart/signed; boundary=\"{}\"; micalg=\"{}\"; \
+         protocol=\"application/pkcs7-signature\"",
+        boundary, micalg
+    );
+    Ok((MimeMessage::new(content_type, body), result))
+}
+
+/// Creates an `application/pkcs7-mime` signed-data message embedding `entity`.
+///
+/// Unlike `sign`, the content of the message can only be read by S/MIME capable clients.
+pub fn sign_opaque(ctx: &mut Context, entity: &[u8]) -> Result<(MimeMessage, SigningResult)> {
+    check_protocol(ctx)?;
+    let entity = mime::canonicalize(entity);
+    let mut signed = Vec::new();
+    let result = utils::with_armor(ctx, false, |ctx| {
+        ctx.sign(SignMode::Normal, &entity[..], &mut signed)
+    })?;
+    Ok((pkcs7_mime(SmimeType::SignedData, &signed), result))
+}
+
+/// Creates an `application/pkcs7-mime` certs-only message containing the specified
+/// certificates.
+pub fn certs_only<'k, I>(ctx: &mut Context, certs: I) -> Result<MimeMessage>
+where I: IntoIterator<Item = &'k Key> {
+    check_protocol(ctx)?;
+    let mut exported = Vec::new();
+    utils::with_armor(ctx, false, |ctx| {
+        ctx.export_keys(certs, ExportMode::empty(), &mut exported)
+    })?;
+    if exported.is_empty() {
+        return Err(Error::NO_DATA);
+    }
+
+    // A degenerate SignedData structure without content or signers, see RFC 5751
+    // section 3.6.
+    let mut signed_data = der(TAG_INTEGER, &[1]);
+    signed_data.extend(der(TAG_SET, &[]));
+    signed_data.extend(der(TAG_SEQUENCE, &der(TAG_OID, OID_DATA)));
+    signed_data.extend(der(TAG_CONTEXT_0, &exported));
+    signed_data.extend(der(TAG_SET, &[]));
+    let mut info = der(TAG_OID, OID_SIGNED_DATA);
+    info.extend(der(TAG_CONTEXT_0, &der(TAG_SEQUENCE, &signed_data)));
+    Ok(pkcs7_mime(SmimeType::CertsOnly, &der(TAG_SEQUENCE, &info)))
+}
+
+/// An incoming message recognized by `parse`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParsedMessage {
+    /// A `multipart/signed` message with a detached signature.
+    Signed {
+        /// The signed entity exactly as it was signed, in canonical form.
+        entity: Vec<u8>,
+        /// The decoded detached signature.
+        signature: Vec<u8>,
+        /// The `micalg` parameter of the message, if any.
+        micalg: Option<String>,
+    },
+    /// An `application/pkcs7-mime` message.
+    Opaque {
+        /// The type of the message, detected from its content if the `smime-type` parameter
+        /// is missing.
+        smime_type: SmimeType,
+        /// The decoded CMS object.
+        data: Vec<u8>,
+    },
+    /// A message that is not an S/MIME message.
+    Other {
+        /// The lower case MIME type of the message.
+        mime_type: String,
+    },
+}
+
+fn decode_body(headers: &[u8], body: &[u8]) -> Result<Vec<u8>> {
+    let encoding = entity::header(headers, "content-transfer-encoding").unwrap_or_default();
+    if encoding.eq_ignore_ascii_case("base64") {
+        base64::decode(body).ok_or(Error::INV_VALUE)
+    } else {
+        Ok(body.to_vec())
+    }
+}
+
+/// Parses an incoming message including its headers.
+///
+/// Returns an error if the message claims to be an S/MIME message, but is malformed.
+pub fn parse(message: &[u8]) -> Result<ParsedMessage> {
+    let message = mime::canonicalize(message);
+    let (headers, body) = entity::split_entity(&message);
+    let (mime_type, params) = match entity::header(headers, "content-type") {
+        Some(value) => entity::parse_content_type(&value),
+        None => ("text/plain".to_owned(), Vec::new()),
+    };
+    let param = |name: &str| {
+        params
+            .iter()
+            .find(|&&(ref n, _)| n == name)
+            .map(|&(_, ref v)| v.clone())
+    };
+
+    match &*mime_type {
+        "application/pkcs7-mime" | "application/x-pkcs7-mime" => {
+            let data = decode_body(headers, body)?;
+            let smime_type = param("smime-type")
+                .and_then(|t| SmimeType::from_param(&t))
+                .or_else(|| SmimeType::detect(&data))
+                .ok_or(Error::INV_VALUE)?;
+            Ok(ParsedMessage::Opaque { smime_type, data })
+        }
+        "multipart/signed" => {
+            let protocol = param("protocol").unwrap_or_default().to_ascii_lowercase();
+            if (protocol != "application/pkcs7-signature")
+                && (protocol != "application/x-pkcs7-signature")
+            {
+                return Ok(ParsedMessage::Other { mime_type });
+            }
+            let boundary = param("boundary").ok_or(Error::INV_VALUE)?;
+            let parts = entity::split_multipart(body, &boundary)?;
+            if parts.len() != 2 {
+                return Err(Error::INV_VALUE);
+            }
+            let (sig_headers, signature) = entity::split_entity(parts[1]);
+            Ok(ParsedMessage::Signed {
+                entity: parts[0].to_vec(),
+                signature: decode_body(sig_headers, signature)?,
+                micalg: param("micalg").map(|s| s.to_ascii_lowercase()),
+            })
+        }
+        _ => Ok(ParsedMessage::Other { mime_type }),
+    }
+}
+
+/// The result of `process`.
+#[derive(Debug, Clone)]
+pub enum Processed {
+    /// A signed message. For opaque signed-data messages the entity is the embedded content.
+    Verified {
+        entity: Vec<u8>,
+        result: VerificationResult,
+    },
+    /// An enveloped-data message. The decrypted entity may itself be an S/MIME message,
+    /// e.g. if it was signed before encryption.
+    Decrypted {
+        entity: Vec<u8>,
+        result: DecryptionResult,
+    },
+    /// A certs-only message whose certificates were imported.
+    Imported(ImportResult),
+}
+
+/// Parses an incoming message and verifies, decrypts or imports it as appropriate.
+///
+/// Returns `Error::NO_DATA` if the message is not an S/MIME message and
+/// `Error::NOT_SUPPORTED` for compressed-data messages.
+pub fn process(ctx: &mut Context, message: &[u8]) -> Result<Processed> {
+    check_protocol(ctx)?;
+    match parse(message)? {
+        ParsedMessage::Signed {
+            entity, signature, ..
+        } => {
+            let result = ctx.verify_detached(&signature[..], &entity[..])?;
+            Ok(Processed::Verified { entity, result })
+        }
+        ParsedMessage::Opaque { smime_type, data } => match smime_type {
+            SmimeType::SignedData => {
+                let mut entity = Vec::new();
+                let result = ctx.verify_opaque(&data[..], &mut entity)?;
+                Ok(Processed::Verified { entity, result })
+            }
+            SmimeType::EnvelopedData | SmimeType::AuthEnvelopedData => {
+                let mut entity = Vec::new();
+                let result = ctx.decrypt(&data[..], &mut entity)?;
+                Ok(Processed::Decrypted { entity, result })
+            }
+            SmimeType::CertsOnly => ctx.import(&data[..]).map(Processed::Imported),
+            SmimeType::CompressedData => Err(Error::NOT_SUPPORTED),
+        },
+        ParsedMessage::Other { .. } => Err(Error::NO_DATA),
+    }
+}
+
+fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(content.len() + 6);
+    result.push(tag);
+    let len = content.len();
+    if len < 0x80 {
+        result.push(len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let skip = bytes.iter().take_while(|&&b| b == 0).count();
+        result.push(0x80 | (bytes.len() - skip) as u8);
+        result.extend_from_slice(&bytes[skip..]);
+    }
+    result.extend_from_slice(content);
+    result
+}
+
+/// Maximum nesting depth of indefinite length values accepted by `read_tlv`.
+const MAX_DEPTH: usize = 32;
+
+/// Reads a single BER encoded value, returning its tag, its contents and the remaining
+/// input. Indefinite length encodings are supported up to `MAX_DEPTH` levels of nesting.
+fn read_tlv(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    read_tlv_nested(data, 0)
+}
+
+fn read_tlv_nested(data: &[u8], depth: usize) -> Option<(u8, &[u8], &[u8])> {
+    let tag = *data.get(0)?;
+    if (tag & 0x1f) == 0x1f {
+        return None;
+    }
+    let first = *data.get(1)?;
+    if first == 0x80 {
+        if ((tag & 0x20) == 0) || (depth >= MAX_DEPTH) {
+            return None;
+        }
+        let content = &data[2..];
+        let mut rest = content;
+        loop {
+            if rest.starts_with(&[0, 0]) {
+                let len = content.len() - rest.len();
+                return Some((tag, &content[..len], &rest[2..]));
+            }
+            rest = read_tlv_nested(rest, depth + 1)?.2;
+        }
+    }
+    let (len, header) = if first < 0x80 {
+        (usize::from(first), 2)
+    } else {
+        let n = usize::from(first & 0x7f);
+        if (n == 0) || (n > 4) {
+            return None;
+        }
+        let bytes = data.get(2..(2 + n))?;
+        (
+            bytes.iter().fold(0usize, |acc, &b| (acc << 8) | usize::from(b)),
+            2 + n,
+        )
+    };
+    let end = header.checked_add(len)?;
+    let content = data.get(header..end)?;
+    Some((tag, content, &data[end..]))
+}
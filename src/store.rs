@@ -0,0 +1,369 @@
+//! A password store compatible with [pass](https://www.passwordstore.org/).
+//!
+//! Each entry is stored in its own file ending in `.gpg` below the root of the store. The
+//! recipients of an entry are listed in the `.gpg-id` file of the closest enclosing
+//! directory, one key id, fingerprint or email address per line.
+use std::{
+    collections::HashMap,
+    env, fs,
+    fs::File,
+    io,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{utils, Context, DecryptionResult, EncryptFlags, Error, Key, Result};
+
+const GPG_ID: &str = ".gpg-id";
+const EXTENSION: &str = ".gpg";
+
+/// A `pass` password store.
+///
+/// # Examples
+///
+/// ```no_run
+/// use gpgme::{store::PasswordStore, Context, Protocol};
+///
+/// let mut ctx = Context::from_protocol(Protocol::OpenPgp).unwrap();
+/// let store = PasswordStore::from_env().unwrap();
+/// for name in store.find("example.org").unwrap() {
+///     println!("{}: {}", name, store.get_password(&mut ctx, &name).unwrap());
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct PasswordStore {
+    root: PathBuf,
+    flags: EncryptFlags,
+}
+
+/// Converts an entry or directory name to its '/' separated form, rejecting names that could
+/// refer to files outside of the store.
+fn normalize(name: &str) -> Result<String> {
+    let mut result = Vec::new();
+    for component in Path::new(name).components() {
+        match component {
+            Component::Normal(c) => result.push(c.to_str().ok_or(Error::INV_VALUE)?),
+            Component::CurDir => (),
+            _ => return Err(Error::INV_VALUE),
+        }
+    }
+    Ok(result.join("/"))
+}
+
+fn parse_gpg_ids(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(|l| l.split('#').next().unwrap_or("").trim())
+        .filter(|l| !l.is_empty())
+        .map(|l| l.to_owned())
+        .collect()
+}
+
+/// Returns `true` if `id` is the fingerprint or (short) key id of one of the subkeys of `key`,
+/// or the email address or complete text of one of its user ids.
+fn is_named_by(key: &Key, id: &str) -> bool {
+    let hex = id.trim_start_matches("0x").trim_start_matches("0X");
+    if !hex.is_empty() && hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        let named = key.subkeys().any(|s| {
+            s.fingerprint().map_or(false, |f| f.eq_ignore_ascii_case(hex))
+                || s.id().map_or(false, |i| {
+                    i.eq_ignore_ascii_case(hex)
+                        || ((i.len() == 16) && i[8..].eq_ignore_ascii_case(hex))
+                })
+        });
+        if named {
+            return true;
+        }
+    }
+    let addr = id.trim_start_matches('<').trim_end_matches('>');
+    key.user_ids().any(|u| {
+        u.id().map_or(false, |u| u == id)
+            || u.email().map_or(false, |e| e.eq_ignore_ascii_case(addr))
+    })
+}
+
+fn is_encrypted_to(result: &DecryptionResult, keys: &[Key]) -> bool {
+    let ids = result
+        .recipients()
+        .filter_map(|r| r.key_id().ok())
+        .collect::<Vec<_>>();
+    let has_id = |key: &Key, id: &str| key.subkeys().any(|s| s.id().ok() == Some(id));
+    keys.iter()
+        .all(|k| ids.iter().any(|id| has_id(k, id)))
+        && ids.iter().all(|id| keys.iter().any(|k| has_id(k, id)))
+}
+
+impl PasswordStore {
+    /// Opens the store located at `root`.
+    ///
+    /// Entries are encrypted using `EncryptFlags::NO_ENCRYPT_TO` and
+    /// `EncryptFlags::NO_COMPRESS`, matching the options used by `pass`.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        PasswordStore {
+            root: root.into(),
+            flags: EncryptFlags::NO_ENCRYPT_TO | EncryptFlags::NO_COMPRESS,
+        }
+    }
+
+    /// Opens the store specified by the `PASSWORD_STORE_DIR` environment variable, or
+    /// `~/.password-store` if it is not set.
+    pub fn from_env() -> Result<Self> {
+        if let Some(dir) = env::var_os("PASSWORD_STORE_DIR") {
+            return Ok(Self::new(dir));
+        }
+        let home = env::var_os("HOME").ok_or(Error::NOT_FOUND)?;
+        Ok(Self::new(Path::new(&home).join(".password-store")))
+    }
+
+    /// Sets the flags used when encrypting entries.
+    #[inline]
+    pub fn with_flags(mut self, flags: EncryptFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    #[inline]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns the path of the file storing the entry `name`.
+    pub fn entry_path(&self, name: &str) -> Result<PathBuf> {
+        let name = normalize(name)?;
+        if name.is_empty() {
+            return Err(Error::INV_VALUE);
+        }
+        Ok(self.root.join(name + EXTENSION))
+    }
+
+    fn dir_path(&self, dir: &str) -> Result<PathBuf> {
+        Ok(self.root.join(normalize(dir)?))
+    }
+
+    /// Returns the path of the `.gpg-id` file that applies to the directory `dir`.
+    fn gpg_id_path(&self, mut dir: PathBuf) -> Result<PathBuf> {
+        loop {
+            let path = dir.join(GPG_ID);
+            if path.is_file() {
+                return Ok(path);
+            }
+            if (dir == self.root) || !dir.pop() || !dir.starts_with(&self.root) {
+                return Err(Error::NOT_FOUND);
+            }
+        }
+    }
+
+    /// Returns the key ids, fingerprints or addresses listed in the `.gpg-id` file that
+    /// applies to the directory `dir`.
+    pub fn gpg_ids(&self, dir: &str) -> Result<Vec<String>> {
+        let path = self.gpg_id_path(self.dir_path(dir)?)?;
+        Ok(parse_gpg_ids(&fs::read_to_string(path)?))
+    }
+
+    fn resolve(&self, ctx: &mut Context, gpg_id: &Path) -> Result<Vec<Key>> {
+        let mut keys: Vec<Key> = Vec::new();
+        for id in parse_gpg_ids(&fs::read_to_string(gpg_id)?) {
+            let mut found: Option<Key> = None;
+            for key in ctx.find_keys(Some(&*id))? {
+                let key = key?;
+                if !key.can_encrypt() || key.is_revoked() || key.is_expired() {
+                    continue;
+                }
+                if key.is_disabled() || key.is_invalid() || !is_named_by(&key, &id) {
+                    continue;
+                }
+                if found
+                    .as_ref()
+                    .map_or(false, |k| k.fingerprint_raw() != key.fingerprint_raw())
+                {
+                    return Err(Error::AMBIGUOUS_NAME);
+                }
+                found = Some(key);
+            }
+            let key = found.ok_or(Error::NO_PUBKEY)?;
+            if !keys.iter().any(|k| k.fingerprint_raw() == key.fingerprint_raw()) {
+                keys.push(key);
+            }
+        }
+        if keys.is_empty() {
+            return Err(Error::NO_PUBKEY);
+        }
+        Ok(keys)
+    }
+
+    /// Resolves the recipients for entries in the directory `dir` using `Context::find_keys`.
+    ///
+    /// Each listed id must be the fingerprint or key id of a key, or the email address or
+    /// complete user id of one of its user ids; keys that merely contain the id are ignored.
+    /// Returns `Error::NO_PUBKEY` if no usable key is found for one of the listed ids and
+    /// `Error::AMBIGUOUS_NAME` if more than one is found.
+    pub fn recipients(&self, ctx: &mut Context, dir: &str) -> Result<Vec<Key>> {
+        let path = self.gpg_id_path(self.dir_path(dir)?)?;
+        self.resolve(ctx, &path)
+    }
+
+    /// Decrypts the entry `name`.
+    pub fn get(&self, ctx: &mut Context, name: &str) -> Result<Vec<u8>> {
+        let mut plaintext = Vec::new();
+        ctx.decrypt(File::open(self.entry_path(name)?)?, &mut plaintext)?;
+        Ok(plaintext)
+    }
+
+    /// Decrypts the entry `name` and returns its first line, which by convention holds the
+    /// password.
+    pub fn get_password(&self, ctx: &mut Context, name: &str) -> Result<String> {
+        let contents = self.get(ctx, name)?;
+        let line = contents.split(|&b| b == b'\n').next().unwrap_or(&[]);
+        let line = String::from_utf8(line.to_vec()).map_err(|_| Error::BAD_DATA)?;
+        Ok(line.trim_end_matches('\r').to_owned())
+    }
+
+    fn write_entry(
+        &self, ctx: &mut Context, path: &Path, recipients: &[Key], contents: &[u8],
+    ) -> Result<()> {
+        let mut ciphertext = Vec::new();
+        utils::with_armor(ctx, false, |ctx| {
+            ctx.encrypt_with_flags(recipients, contents, &mut ciphertext, self.flags)
+        })?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        fs::write(&tmp, &ciphertext)?;
+        fs::rename(&tmp, path).map_err(|e| {
+            let _ = fs::remove_file(&tmp);
+            Error::from(e)
+        })
+    }
+
+    /// Encrypts `contents` for the recipients of the entry's directory and stores them as
+    /// the entry `name`, replacing any existing entry.
+    pub fn insert(&self, ctx: &mut Context, name: &str, contents: &[u8]) -> Result<()> {
+        let path = self.entry_path(name)?;
+        let dir = path.parent().map_or_else(|| self.root.clone(), |p| p.to_owned());
+        let recipients = self.resolve(ctx, &self.gpg_id_path(dir)?)?;
+        self.write_entry(ctx, &path, &recipients, contents)
+    }
+
+    /// Decrypts the entry `name`, passes its contents to `f` and stores the modified
+    /// contents. A new entry is created if `name` does not exist.
+    pub fn edit<F>(&self, ctx: &mut Context, name: &str, f: F) -> Result<()>
+    where F: FnOnce(&mut Vec<u8>) {
+        let mut contents = if self.entry_path(name)?.exists() {
+            self.get(ctx, name)?
+        } else {
+            Vec::new()
+        };
+        f(&mut contents);
+        self.insert(ctx, name, &contents)
+    }
+
+    /// Removes the entry `name`.
+    pub fn remove(&self, name: &str) -> Result<()> {
+        fs::remove_file(self.entry_path(name)?)?;
+        Ok(())
+    }
+
+    fn entries_below(&self, dir: &str) -> Result<Vec<String>> {
+        fn walk(dir: &Path, prefix: &str, names: &mut Vec<String>) -> io::Result<()> {
+            let read_dir = match fs::read_dir(dir) {
+                Ok(read_dir) => read_dir,
+                Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+                Err(e) => return Err(e),
+            };
+            for entry in read_dir {
+                let entry = entry?;
+                let name = match entry.file_name().into_string() {
+                    Ok(ref name) if name.starts_with('.') => continue,
+                    Ok(name) => name,
+                    Err(_) => continue,
+                };
+                let file_type = entry.file_type()?;
+                if file_type.is_dir() {
+                    walk(&entry.path(), &format!("{}{}/", prefix, name), names)?;
+                } else if file_type.is_file() && name.ends_with(EXTENSION) {
+                    let name = &name[..(name.len() - EXTENSION.len())];
+                    names.push(format!("{}{}", prefix, name));
+                }
+            }
+            Ok(())
+        }
+
+        let dir = normalize(dir)?;
+        let prefix = if dir.is_empty() { dir.clone() } else { format!("{}/", dir) };
+        let mut names = Vec::new();
+        walk(&self.root.join(&dir), &prefix, &mut names)?;
+        names.sort();
+        Ok(names)
+    }
+
+    /// Returns the names of all entries, sorted. Hidden files and directories, such as
+    /// `.git`, are skipped.
+    pub fn list(&self) -> Result<Vec<String>> {
+        self.entries_below("")
+    }
+
+    /// Returns the names of all entries containing `pattern`, ignoring case.
+    pub fn find(&self, pattern: &str) -> Result<Vec<String>> {
+        let pattern = pattern.to_lowercase();
+        let mut names = self.list()?;
+        names.retain(|n| n.to_lowercase().contains(&pattern));
+        Ok(names)
+    }
+
+    /// Writes the `.gpg-id` file of the directory `dir` and re-encrypts the entries below it.
+    ///
+    /// If `ids` is empty, the `.gpg-id` file of `dir` is removed so that the recipients of
+    /// the parent directory apply again. This is not allowed for the root of the store.
+    /// Returns the names of the re-encrypted entries.
+    pub fn init<I>(&self, ctx: &mut Context, dir: &str, ids: I) -> Result<Vec<String>>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>, {
+        let path = self.dir_path(dir)?;
+        let ids = ids
+            .into_iter()
+            .map(|id| id.as_ref().trim().to_owned())
+            .collect::<Vec<_>>();
+        if ids.iter().any(|id| id.is_empty() || id.contains('\n')) {
+            return Err(Error::INV_VALUE);
+        }
+        if ids.is_empty() {
+            if path == self.root {
+                return Err(Error::INV_VALUE);
+            }
+            fs::remove_file(path.join(GPG_ID))?;
+        } else {
+            fs::create_dir_all(&path)?;
+            fs::write(path.join(GPG_ID), ids.join("\n") + "\n")?;
+        }
+        self.reencrypt(ctx, dir)
+    }
+
+    /// Re-encrypts all entries below the directory `dir` whose recipients differ from the
+    /// ones currently listed in the applicable `.gpg-id` files.
+    ///
+    /// Returns the names of the re-encrypted entries.
+    pub fn reencrypt(&self, ctx: &mut Context, dir: &str) -> Result<Vec<String>> {
+        let mut recipients: HashMap<PathBuf, Vec<Key>> = HashMap::new();
+        let mut changed = Vec::new();
+        for name in self.entries_below(dir)? {
+            let path = self.entry_path(&name)?;
+            let parent = path.parent().map_or_else(|| self.root.clone(), |p| p.to_owned());
+            let gpg_id = self.gpg_id_path(parent)?;
+            if !recipients.contains_key(&gpg_id) {
+                let keys = self.resolve(ctx, &gpg_id)?;
+                recipients.insert(gpg_id.clone(), keys);
+            }
+            let keys = &recipients[&gpg_id];
+
+            let mut plaintext = Vec::new();
+            let result = ctx.decrypt(File::open(&path)?, &mut plaintext)?;
+            if !is_encrypted_to(&result, keys) {
+                self.write_entry(ctx, &path, keys, &plaintext)?;
+                changed.push(name);
+            }
+        }
+        Ok(changed)
+    }
+}
@@ -0,0 +1,270 @@
+//! Streaming encryption and decryption.
+//!
+//! [`DecryptReader`] and [`EncryptWriter`] run a decryption or encryption operation on a
+//! helper thread and exchange the plaintext with the caller through a bounded channel, so
+//! they can be combined with `std::io::copy`, compressors or network streams without
+//! buffering the whole message.
+//!
+//! See [`Context::decrypt_reader`] and [`Context::encrypt_writer`].
+use std::{
+    fmt,
+    io::{self, prelude::*},
+    panic,
+    sync::mpsc::{self, Receiver, SyncSender},
+    thread::{self, JoinHandle},
+};
+
+use crate::{
+    Context, Data, DecryptionResult, EncryptFlags, EncryptionResult, Error, IntoData, Key,
+    Result,
+};
+
+/// Number of chunks that may be queued between the caller and the helper thread.
+const QUEUE_LEN: usize = 4;
+
+struct Worker<T> {
+    handle: Option<JoinHandle<T>>,
+    value: Option<T>,
+}
+
+impl<T: Send + 'static> Worker<T> {
+    fn spawn<F: FnOnce() -> T + Send + 'static>(f: F) -> Self {
+        Worker {
+            handle: Some(thread::spawn(f)),
+            value: None,
+        }
+    }
+}
+
+impl<T> Worker<T> {
+    fn is_done(&self) -> bool {
+        self.value.is_some()
+    }
+
+    fn join(&mut self) -> &mut T {
+        if let Some(handle) = self.handle.take() {
+            match handle.join() {
+                Ok(value) => self.value = Some(value),
+                Err(err) => panic::resume_unwind(err),
+            }
+        }
+        self.value.as_mut().unwrap()
+    }
+}
+
+fn broken_pipe() -> io::Error {
+    io::Error::new(io::ErrorKind::BrokenPipe, "stream closed")
+}
+
+/// A chunk of plaintext, or the error that aborts the operation.
+type Chunk = io::Result<Vec<u8>>;
+
+struct ChannelReader {
+    chunks: Receiver<Chunk>,
+    chunk: Vec<u8>,
+    pos: usize,
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.chunk.len() {
+            match self.chunks.recv() {
+                Ok(chunk) => {
+                    self.chunk = chunk?;
+                    self.pos = 0;
+                }
+                Err(_) => return Ok(0),
+            }
+        }
+        let n = (&self.chunk[self.pos..]).read(buf)?;
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+struct ChannelWriter(SyncSender<Chunk>);
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !buf.is_empty() {
+            self.0.send(Ok(buf.to_vec())).map_err(|_| broken_pipe())?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A reader over the plaintext of a message that is decrypted on a helper thread.
+///
+/// The result of the operation becomes available once the plaintext has been read
+/// completely, and a failed operation is reported as an error by the final `read`. Note that
+/// the integrity of the message is only known at that point, so the plaintext must not be
+/// trusted before the reader has returned end of file or [`finish`](DecryptReader::finish)
+/// has returned successfully.
+pub struct DecryptReader {
+    plain: Option<ChannelReader>,
+    worker: Worker<(Context, Result<DecryptionResult>)>,
+}
+
+impl fmt::Debug for DecryptReader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DecryptReader")
+            .field("done", &self.worker.is_done())
+            .finish()
+    }
+}
+
+impl DecryptReader {
+    pub(crate) fn new<C>(mut ctx: Context, ciphertext: C) -> Self
+    where C: IntoData<'static> + Send + 'static {
+        let (tx, rx) = mpsc::sync_channel(QUEUE_LEN);
+        let worker = Worker::spawn(move || {
+            let result = Data::from_writer(ChannelWriter(tx))
+                .map_err(|e| e.error())
+                .and_then(|plain| ctx.decrypt(ciphertext, plain));
+            (ctx, result)
+        });
+        DecryptReader {
+            plain: Some(ChannelReader {
+                chunks: rx,
+                chunk: Vec::new(),
+                pos: 0,
+            }),
+            worker,
+        }
+    }
+
+    /// Waits for the operation to complete and returns its result.
+    ///
+    /// If the plaintext has not been read completely, the operation is canceled.
+    pub fn finish(&mut self) -> Result<DecryptionResult> {
+        self.plain = None;
+        self.worker.join().1.clone()
+    }
+
+    /// Waits for the operation to complete and returns the context it was run on.
+    pub fn into_context(mut self) -> Context {
+        self.plain = None;
+        self.worker.join();
+        self.worker.value.take().unwrap().0
+    }
+}
+
+impl Read for DecryptReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = match self.plain {
+            Some(ref mut plain) => plain.read(buf)?,
+            None => 0,
+        };
+        if n > 0 || buf.is_empty() {
+            return Ok(n);
+        }
+        match self.worker.join().1 {
+            Ok(_) => Ok(0),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// A writer that encrypts the data written to it on a helper thread.
+///
+/// The ciphertext is written to the sink passed to [`Context::encrypt_writer`]. Call
+/// [`finish`](EncryptWriter::finish) or [`into_inner`](EncryptWriter::into_inner) once all
+/// plaintext has been written. Dropping the writer without doing so aborts the operation
+/// instead of encrypting the plaintext written so far, and waits for the helper thread to
+/// exit; any output already written to the sink must be discarded.
+pub struct EncryptWriter<W> {
+    plain: Option<SyncSender<Chunk>>,
+    worker: Worker<(Context, W, Result<EncryptionResult>)>,
+}
+
+impl<W> fmt::Debug for EncryptWriter<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncryptWriter")
+            .field("done", &self.worker.is_done())
+            .finish()
+    }
+}
+
+impl<W: Write + Send + 'static> EncryptWriter<W> {
+    pub(crate) fn new(
+        mut ctx: Context, recp: Vec<Key>, mut sink: W, flags: EncryptFlags,
+    ) -> Self {
+        let (tx, rx) = mpsc::sync_channel(QUEUE_LEN);
+        let worker = Worker::spawn(move || {
+            let plain = ChannelReader {
+                chunks: rx,
+                chunk: Vec::new(),
+                pos: 0,
+            };
+            let result = Data::from_reader(plain)
+                .map_err(|e| e.error())
+                .and_then(|plain| {
+                    let cipher = Data::from_writer(&mut sink).map_err(|e| e.error())?;
+                    ctx.encrypt_with_flags(&recp, plain, cipher, flags)
+                });
+            (ctx, sink, result)
+        });
+        EncryptWriter {
+            plain: Some(tx),
+            worker,
+        }
+    }
+
+    /// Signals the end of the plaintext, waits for the operation to complete and returns
+    /// its result.
+    pub fn finish(&mut self) -> Result<EncryptionResult> {
+        self.plain = None;
+        self.worker.join().2.clone()
+    }
+
+    /// Waits for the operation to complete and returns the context and the sink.
+    pub fn into_inner(mut self) -> (Context, W) {
+        self.plain = None;
+        self.worker.join();
+        let (ctx, sink, _) = self.worker.value.take().unwrap();
+        (ctx, sink)
+    }
+}
+
+impl<W: Write + Send + 'static> Write for EncryptWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let sent = match self.plain {
+            Some(ref plain) => plain.send(Ok(buf.to_vec())).is_ok(),
+            None => false,
+        };
+        if sent {
+            return Ok(buf.len());
+        }
+
+        // The operation ended before all plaintext was written.
+        self.plain = None;
+        match self.worker.join().2 {
+            Err(err) => Err(err.into()),
+            Ok(_) => Err(Error::EPIPE.into()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<W> Drop for EncryptWriter<W> {
+    fn drop(&mut self) {
+        if let Some(plain) = self.plain.take() {
+            let err = io::Error::new(io::ErrorKind::Other, "encryption aborted");
+            let _ = plain.send(Err(err));
+        }
+        // Wait for the helper thread so that nothing is written to the sink afterwards.
+        if let Some(handle) = self.worker.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
@@ -3,7 +3,7 @@ use std::io::{self, prelude::*};
 use ffi;
 use libc;
 
-use crate::Error;
+use crate::{Context, Error, Result};
 
 pub use cstr_argument::CStrArgument;
 pub type SmallVec<T> = ::smallvec::SmallVec<[T; 4]>;
@@ -202,3 +202,14 @@ impl<T> Ptr for *mut T {
 impl<T> Ptr for *const T {
     type Inner = T;
 }
+
+/// Runs `f` with ASCII armor set to `enabled`, restoring the previous setting afterwards.
+pub(crate) fn with_armor<R>(
+    ctx: &mut Context, enabled: bool, f: impl FnOnce(&mut Context) -> Result<R>,
+) -> Result<R> {
+    let armor = ctx.armor();
+    ctx.set_armor(enabled);
+    let result = f(ctx);
+    ctx.set_armor(armor);
+    result
+}
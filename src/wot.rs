@@ -0,0 +1,383 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    time::SystemTime,
+};
+
+use crate::{Context, Error, Fingerprint, Key, KeyId, KeyListMode, Result};
+
+/// A key in a `TrustGraph`.
+#[derive(Debug, Clone)]
+pub struct Node {
+    fingerprint: Fingerprint,
+    key_id: KeyId,
+    user_ids: Vec<String>,
+}
+
+impl Node {
+    #[inline]
+    pub fn fingerprint(&self) -> &Fingerprint {
+        &self.fingerprint
+    }
+
+    #[inline]
+    pub fn key_id(&self) -> &KeyId {
+        &self.key_id
+    }
+
+    #[inline]
+    pub fn user_ids(&self) -> &[String] {
+        &self.user_ids
+    }
+}
+
+/// A certification of a user id by another key.
+#[derive(Debug, Clone)]
+pub struct Certification {
+    signer: KeyId,
+    target: usize,
+    user_id: String,
+    cert_class: u64,
+    creation_time: Option<SystemTime>,
+    expiration_time: Option<SystemTime>,
+    is_expired: bool,
+    is_invalid: bool,
+    is_revoked: bool,
+    status: Error,
+}
+
+impl Certification {
+    /// Returns the key id of the signer. The signer is not necessarily part of the graph.
+    #[inline]
+    pub fn signer(&self) -> &KeyId {
+        &self.signer
+    }
+
+    #[inline]
+    pub fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    #[inline]
+    pub fn cert_class(&self) -> u64 {
+        self.cert_class
+    }
+
+    #[inline]
+    pub fn creation_time(&self) -> Option<SystemTime> {
+        self.creation_time
+    }
+
+    #[inline]
+    pub fn expiration_time(&self) -> Option<SystemTime> {
+        self.expiration_time
+    }
+
+    #[inline]
+    pub fn is_expired(&self) -> bool {
+        self.is_expired
+    }
+
+    #[inline]
+    pub fn is_invalid(&self) -> bool {
+        self.is_invalid
+    }
+
+    /// Returns `true` if the signer later revoked the certification.
+    #[inline]
+    pub fn is_revoked(&self) -> bool {
+        self.is_revoked
+    }
+
+    #[inline]
+    pub fn status(&self) -> Error {
+        self.status
+    }
+
+    /// Returns `true` if the certification is neither expired, invalid nor revoked and the
+    /// signature was successfully checked.
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        !self.is_expired && !self.is_invalid && !self.is_revoked && (self.status.code() == 0)
+    }
+}
+
+/// A certification graph built from the signatures of a key listing.
+///
+/// Edges point from the signer to the certified key. Self-signatures are not included.
+///
+/// # Examples
+///
+/// ```no_run
+/// use gpgme::{wot::TrustGraph, Context, Protocol};
+///
+/// let mut ctx = Context::from_protocol(Protocol::OpenPgp).unwrap();
+/// let graph = TrustGraph::load(&mut ctx).unwrap();
+/// let me = "[my fingerprint]".parse().unwrap();
+/// let them = "[their fingerprint]".parse().unwrap();
+/// if let Some(path) = graph.shortest_path(&me, &them) {
+///     for cert in path {
+///         println!("{} certified {:?}", cert.signer(), cert.user_id());
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TrustGraph {
+    nodes: Vec<Node>,
+    certifications: Vec<Certification>,
+    by_fpr: HashMap<Fingerprint, usize>,
+    by_id: HashMap<KeyId, usize>,
+}
+
+impl TrustGraph {
+    /// Lists all public keys including their signatures using `ctx` and builds a graph from
+    /// them.
+    pub fn load(ctx: &mut Context) -> Result<Self> {
+        let mode = ctx.key_list_mode();
+        ctx.set_key_list_mode(mode | KeyListMode::SIGS)?;
+        let keys = ctx.keys().and_then(|k| k.collect::<Result<Vec<_>>>());
+        ctx.set_key_list_mode(mode)?;
+        Ok(Self::from_keys(keys?))
+    }
+
+    /// Builds a graph from keys listed using `KeyListMode::SIGS`.
+    ///
+    /// Keys listed without signatures are added as nodes without any edges.
+    pub fn from_keys(keys: impl IntoIterator<Item = Key>) -> Self {
+        let mut graph = TrustGraph::default();
+        let keys = keys.into_iter().collect::<Vec<_>>();
+        for key in &keys {
+            let (fpr, id) = match (key.to_fingerprint(), key.to_key_id()) {
+                (Some(fpr), Some(id)) => (fpr, id),
+                _ => continue,
+            };
+            if graph.by_fpr.contains_key(&fpr) {
+                continue;
+            }
+            let index = graph.nodes.len();
+            graph.by_fpr.insert(fpr.clone(), index);
+            graph.by_id.insert(id.clone(), index);
+            graph.nodes.push(Node {
+                fingerprint: fpr,
+                key_id: id,
+                user_ids: key
+                    .user_ids()
+                    .filter_map(|u| u.id().ok().map(|s| s.to_owned()))
+                    .collect(),
+            });
+        }
+
+        for key in &keys {
+            let target = match key.to_fingerprint().and_then(|f| graph.by_fpr.get(&f)) {
+                Some(&target) => target,
+                None => continue,
+            };
+            let own_id = graph.nodes[target].key_id.clone();
+            for uid in key.user_ids() {
+                let user_id = match uid.id() {
+                    Ok(id) => id,
+                    Err(_) => continue,
+                };
+                let mut certs: Vec<Certification> = Vec::new();
+                let mut revocations: Vec<(KeyId, Option<SystemTime>)> = Vec::new();
+                for sig in uid.signatures() {
+                    let signer = match sig.signer_key_id().ok().and_then(|s| s.parse().ok()) {
+                        Some(signer) => signer,
+                        None => continue,
+                    };
+                    if signer == own_id {
+                        continue;
+                    }
+                    if sig.is_revocation() {
+                        revocations.push((signer, sig.creation_time()));
+                        continue;
+                    }
+                    certs.push(Certification {
+                        signer,
+                        target,
+                        user_id: user_id.to_owned(),
+                        cert_class: sig.cert_class(),
+                        creation_time: sig.creation_time(),
+                        expiration_time: sig.expiration_time(),
+                        is_expired: sig.is_expired(),
+                        is_invalid: sig.is_invalid(),
+                        is_revoked: false,
+                        status: sig.status(),
+                    });
+                }
+                for cert in &mut certs {
+                    cert.is_revoked = revocations.iter().any(|&(ref signer, revoked)| {
+                        (*signer == cert.signer) && (revoked >= cert.creation_time)
+                    });
+                }
+                graph.certifications.extend(certs);
+            }
+        }
+        graph
+    }
+
+    #[inline]
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    #[inline]
+    pub fn certifications(&self) -> &[Certification] {
+        &self.certifications
+    }
+
+    pub fn node(&self, fpr: &Fingerprint) -> Option<&Node> {
+        self.by_fpr.get(fpr).map(|&i| &self.nodes[i])
+    }
+
+    /// Returns the node whose primary key has the specified key id.
+    pub fn node_by_id(&self, id: &KeyId) -> Option<&Node> {
+        self.by_id.get(id).map(|&i| &self.nodes[i])
+    }
+
+    /// Returns the key a certification was made on.
+    #[inline]
+    pub fn target(&self, cert: &Certification) -> &Node {
+        &self.nodes[cert.target]
+    }
+
+    /// Returns the key that made a certification, if it is part of the graph.
+    #[inline]
+    pub fn signer(&self, cert: &Certification) -> Option<&Node> {
+        self.node_by_id(&cert.signer)
+    }
+
+    /// Returns all certifications of the specified key, or only those of the specified user
+    /// id if `user_id` is not `None`.
+    pub fn certifiers<'a>(
+        &'a self, fpr: &Fingerprint, user_id: Option<&'a str>,
+    ) -> impl Iterator<Item = &'a Certification> + 'a {
+        let target = self.by_fpr.get(fpr).cloned();
+        self.certifications.iter().filter(move |c| {
+            (Some(c.target) == target) && user_id.map_or(true, |u| c.user_id == u)
+        })
+    }
+
+    /// Returns all certifications made by the specified key.
+    pub fn certifications_by<'a>(
+        &'a self, fpr: &Fingerprint,
+    ) -> impl Iterator<Item = &'a Certification> + 'a {
+        let signer = fpr.key_id();
+        self.certifications
+            .iter()
+            .filter(move |c| c.signer == signer)
+    }
+
+    /// Returns the certifications made by the specified key that have expired or have been
+    /// revoked.
+    pub fn stale_certifications_by<'a>(
+        &'a self, fpr: &Fingerprint,
+    ) -> impl Iterator<Item = &'a Certification> + 'a {
+        self.certifications_by(fpr)
+            .filter(|c| c.is_expired || c.is_revoked)
+    }
+
+    /// Returns the shortest chain of valid certifications leading from `from` to `to`.
+    ///
+    /// Returns an empty path if both keys are the same and `None` if no such chain exists.
+    pub fn shortest_path(
+        &self, from: &Fingerprint, to: &Fingerprint,
+    ) -> Option<Vec<&Certification>> {
+        let start = *self.by_fpr.get(from)?;
+        let end = *self.by_fpr.get(to)?;
+
+        let mut outgoing: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (i, cert) in self.certifications.iter().enumerate() {
+            if !cert.is_valid() {
+                continue;
+            }
+            if let Some(&signer) = self.by_id.get(&cert.signer) {
+                outgoing.entry(signer).or_default().push(i);
+            }
+        }
+
+        let mut via: HashMap<usize, Option<usize>> = HashMap::new();
+        via.insert(start, None);
+        let mut queue = VecDeque::new();
+        queue.push_back(start);
+        while let Some(node) = queue.pop_front() {
+            if node == end {
+                let mut path = Vec::new();
+                let mut current = node;
+                while let Some(Some(cert)) = via.get(&current) {
+                    let cert = &self.certifications[*cert];
+                    path.push(cert);
+                    current = self.by_id[&cert.signer];
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for &cert in outgoing.get(&node).map_or(&[][..], |v| &v[..]) {
+                let next = self.certifications[cert].target;
+                if !via.contains_key(&next) {
+                    via.insert(next, Some(cert));
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    /// Writes the graph in Graphviz DOT format.
+    ///
+    /// Invalid certifications are drawn dashed. Signers that are not part of the graph are
+    /// included as nodes labelled with their key id.
+    pub fn write_dot(&self, out: &mut impl fmt::Write) -> fmt::Result {
+        writeln!(out, "digraph wot {{")?;
+        for node in &self.nodes {
+            let label = node.user_ids.first().map_or_else(
+                || node.key_id.to_string(),
+                |u| format!("{}\\n{}", escape(u), node.key_id),
+            );
+            writeln!(
+                out,
+                "  \"{}\" [label=\"{}\"];",
+                node.fingerprint.as_str(),
+                label
+            )?;
+        }
+        for cert in &self.certifications {
+            let signer = match self.by_id.get(&cert.signer) {
+                Some(&i) => self.nodes[i].fingerprint.as_str().to_owned(),
+                None => cert.signer.as_str().to_owned(),
+            };
+            write!(
+                out,
+                "  \"{}\" -> \"{}\" [label=\"{}\"",
+                signer,
+                self.nodes[cert.target].fingerprint.as_str(),
+                escape(&cert.user_id)
+            )?;
+            if !cert.is_valid() {
+                out.write_str(", style=dashed")?;
+            }
+            writeln!(out, "];")?;
+        }
+        writeln!(out, "}}")
+    }
+
+    /// Returns the graph in Graphviz DOT format.
+    pub fn to_dot(&self) -> String {
+        let mut result = String::new();
+        let _ = self.write_dot(&mut result);
+        result
+    }
+}
+
+fn escape(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            c if c.is_control() => (),
+            c => result.push(c),
+        }
+    }
+    result
+}
@@ -0,0 +1,100 @@
+use std::{
+    cell::Cell,
+    fs,
+    io::{self, prelude::*, Cursor, SeekFrom},
+};
+
+use gpgme::{archive, EncryptFlags, Error};
+use tempdir::TempDir;
+
+use self::support::passphrase_cb;
+
+#[macro_use]
+mod support;
+
+test_case! {
+    test_encrypt_restore(test) {
+        let src = TempDir::new("archive-src").unwrap();
+        fs::create_dir(src.path().join("sub")).unwrap();
+        fs::write(src.path().join("a.txt"), b"hello\n").unwrap();
+        fs::write(src.path().join("sub/b.bin"), vec![0xa5; 70000]).unwrap();
+
+        let mut ctx = test.create_context();
+        let key = fail_if_err!(ctx.find_keys(Some("alfa@example.net"))).nth(0).unwrap().unwrap();
+        let mut ciphertext = Vec::new();
+        fail_if_err!(archive::encrypt_with_flags(
+            &mut ctx,
+            Some(&key),
+            src.path(),
+            &mut ciphertext,
+            EncryptFlags::ALWAYS_TRUST
+        ));
+        drop(ctx);
+
+        let root = src.path().file_name().unwrap();
+        test.create_context().with_passphrase_provider(passphrase_cb, |ctx| {
+            let dest = TempDir::new("archive-dest").unwrap();
+            let result = archive::restore(ctx, Cursor::new(&ciphertext), dest.path(), |_, _| false);
+            assert_eq!(result.err(), Some(Error::CANCELED));
+            assert_eq!(fs::read_dir(dest.path()).unwrap().count(), 0);
+
+            // Results of the extracting pass are checked too, and rejecting them removes
+            // everything that was extracted.
+            let passes = Cell::new(0);
+            let result = archive::restore(ctx, Cursor::new(&ciphertext), dest.path(), |_, _| {
+                passes.set(passes.get() + 1);
+                passes.get() == 1
+            });
+            assert_eq!(result.err(), Some(Error::CANCELED));
+            assert_eq!(passes.get(), 2);
+            assert_eq!(fs::read_dir(dest.path()).unwrap().count(), 0);
+
+            let changing = Changing {
+                inner: Cursor::new(ciphertext.clone()),
+                passes: 0,
+            };
+            assert!(archive::restore(ctx, changing, dest.path(), |_, _| true).is_err());
+            assert_eq!(fs::read_dir(dest.path()).unwrap().count(), 0);
+
+            let restored = fail_if_err!(archive::restore(
+                ctx,
+                Cursor::new(&ciphertext),
+                dest.path(),
+                |_, _| true
+            ));
+            assert_eq!(restored.paths.len(), 4);
+            let restored = dest.path().join(root);
+            assert_eq!(fs::read(restored.join("a.txt")).unwrap(), b"hello\n");
+            assert_eq!(fs::read(restored.join("sub/b.bin")).unwrap(), vec![0xa5; 70000]);
+        });
+    },
+}
+
+/// Corrupts the end of the ciphertext once it is read a second time.
+struct Changing {
+    inner: Cursor<Vec<u8>>,
+    passes: usize,
+}
+
+impl Read for Changing {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if (self.passes > 1) && (n > 0) && (self.inner.position() as usize == self.len()) {
+            buf[n - 1] ^= 0xff;
+        }
+        Ok(n)
+    }
+}
+
+impl Seek for Changing {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.passes += 1;
+        self.inner.seek(pos)
+    }
+}
+
+impl Changing {
+    fn len(&self) -> usize {
+        self.inner.get_ref().len()
+    }
+}
@@ -0,0 +1,68 @@
+use std::io::prelude::*;
+
+use gpgme::{
+    armor::{self, Block, Kind, Reader, Writer},
+    Error,
+};
+
+#[test]
+fn test_round_trip() {
+    let data = (0..200u8).collect::<Vec<_>>();
+    let block = Block::new(Kind::Signature, data.clone()).with_header("Comment", "test");
+    let text = block.to_string();
+    assert!(text.starts_with("-----BEGIN PGP SIGNATURE-----\nComment: test\n\n"));
+    assert!(text.ends_with("-----END PGP SIGNATURE-----\n"));
+
+    let decoded = armor::decode(&text).unwrap();
+    assert_eq!(decoded, block);
+    assert_eq!(decoded.header("comment"), Some("test"));
+
+    let mut writer = Writer::new(Vec::new(), Kind::Signature);
+    writer.write_all(&data[..10]).unwrap();
+    writer.write_all(&data[10..]).unwrap();
+    assert_eq!(writer.finish().unwrap(), armor::encode(Kind::Signature, data.clone()).as_bytes());
+
+    let mut reader = Reader::new(text.as_bytes()).unwrap();
+    assert_eq!(*reader.kind(), Kind::Signature);
+    let mut read = Vec::new();
+    reader.read_to_end(&mut read).unwrap();
+    assert_eq!(read, data);
+}
+
+#[test]
+fn test_checksum() {
+    let text = armor::encode(Kind::Message, &b"Hello World"[..]);
+    let tampered = text.replace("SGVsbG8g", "SGVsbG9g");
+    assert_eq!(armor::decode(&tampered).err(), Some(Error::CHECKSUM));
+
+    let mut reader = Reader::new(tampered.as_bytes()).unwrap();
+    let err = reader.read_to_end(&mut Vec::new()).unwrap_err();
+    assert_eq!(Error::from(err), Error::CHECKSUM);
+}
+
+#[test]
+fn test_multiple_blocks() {
+    let text = format!(
+        "Some mail text.\n\n-----BEGIN PGP SIGNED MESSAGE-----\nHash: SHA256\n\n\
+         Hello\n- -- dashes\n-----BEGIN PGP SIGNATURE-----\n\nAQID\n\
+         -----END PGP SIGNATURE-----\n\nMore text.\n{}",
+        armor::encode(Kind::PublicKey, &[4u8, 5, 6][..])
+    );
+    let blocks = armor::blocks(text.as_bytes())
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(blocks.len(), 3);
+    assert_eq!(*blocks[0].kind(), Kind::SignedMessage);
+    assert_eq!(blocks[0].header("Hash"), Some("SHA256"));
+    assert_eq!(blocks[0].data(), b"Hello\n-- dashes");
+    assert_eq!(*blocks[1].kind(), Kind::Signature);
+    assert_eq!(blocks[1].data(), [1, 2, 3]);
+    assert_eq!(*blocks[2].kind(), Kind::PublicKey);
+    assert_eq!(blocks[2].data(), [4, 5, 6]);
+
+    let mut reader = Reader::new(text.as_bytes()).unwrap();
+    assert_eq!(*reader.kind(), Kind::Signature);
+    let mut read = Vec::new();
+    reader.read_to_end(&mut read).unwrap();
+    assert_eq!(read, [1, 2, 3]);
+}
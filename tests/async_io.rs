@@ -0,0 +1,183 @@
+use std::{
+    future::Future,
+    io::{self, prelude::*},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Wake},
+    thread::{self, Thread},
+};
+
+use futures_io::{AsyncRead, AsyncWrite};
+use gpgme::async_io;
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+fn block_on<F: Future + Unpin>(mut future: F) -> F::Output {
+    let waker = Arc::new(ThreadWaker(thread::current())).into();
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(value) = Pin::new(&mut future).poll(&mut cx) {
+            return value;
+        }
+        thread::park();
+    }
+}
+
+fn test_data() -> Vec<u8> {
+    (0..100_000u32).map(|i| (i % 251) as u8).collect()
+}
+
+/// A stream that returns `Pending` before every read, and fails after `fail_at` bytes.
+struct Source {
+    data: Vec<u8>,
+    pos: usize,
+    fail_at: Option<usize>,
+    ready: bool,
+}
+
+impl Source {
+    fn new(data: Vec<u8>, fail_at: Option<usize>) -> Self {
+        Source {
+            data,
+            pos: 0,
+            fail_at,
+            ready: false,
+        }
+    }
+}
+
+impl AsyncRead for Source {
+    fn poll_read(
+        mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.ready = !self.ready;
+        if !self.ready {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        if self.fail_at.map_or(false, |n| self.pos >= n) {
+            return Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, "read failed")));
+        }
+        let n = (self.data.len() - self.pos).min(buf.len()).min(1000);
+        buf[..n].copy_from_slice(&self.data[self.pos..(self.pos + n)]);
+        self.pos += n;
+        Poll::Ready(Ok(n))
+    }
+}
+
+/// A stream that returns `Pending` before every write, and fails after `fail_at` bytes.
+#[derive(Clone, Default)]
+struct Sink {
+    data: Arc<Mutex<Vec<u8>>>,
+    closed: Arc<Mutex<bool>>,
+    fail_at: Option<usize>,
+    ready: bool,
+}
+
+impl AsyncWrite for Sink {
+    fn poll_write(
+        mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.ready = !self.ready;
+        if !self.ready {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        let mut data = self.data.lock().unwrap();
+        if self.fail_at.map_or(false, |n| data.len() >= n) {
+            return Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, "write failed")));
+        }
+        let n = buf.len().min(777);
+        data.extend_from_slice(&buf[..n]);
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        *self.closed.lock().unwrap() = true;
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[test]
+fn test_reader_eof() {
+    let data = test_data();
+    let (mut reader, pump) = async_io::reader(Source::new(data.clone(), None), 4096);
+    let consumer = thread::spawn(move || {
+        let mut result = Vec::new();
+        reader.read_to_end(&mut result).map(|_| result)
+    });
+    block_on(pump).unwrap();
+    assert_eq!(consumer.join().unwrap().unwrap(), data);
+}
+
+#[test]
+fn test_reader_error() {
+    let (mut reader, pump) = async_io::reader(Source::new(test_data(), Some(10_000)), 4096);
+    let consumer = thread::spawn(move || reader.read_to_end(&mut Vec::new()));
+    assert_eq!(block_on(pump).unwrap_err().kind(), io::ErrorKind::Other);
+    assert_eq!(consumer.join().unwrap().unwrap_err().kind(), io::ErrorKind::Other);
+}
+
+#[test]
+fn test_reader_dropped() {
+    let (mut reader, pump) = async_io::reader(Source::new(test_data(), None), 4096);
+    let consumer = thread::spawn(move || reader.read_to_end(&mut Vec::new()));
+    drop(pump);
+    assert_eq!(consumer.join().unwrap().unwrap_err().kind(), io::ErrorKind::BrokenPipe);
+
+    // Dropping the reader completes the pump without reading the whole stream.
+    let (reader, pump) = async_io::reader(Source::new(test_data(), None), 4096);
+    drop(reader);
+    block_on(pump).unwrap();
+}
+
+#[test]
+fn test_writer_eof() {
+    let data = test_data();
+    let sink = Sink::default();
+    let (mut writer, pump) = async_io::writer(sink.clone(), 4096);
+    let producer = {
+        let data = data.clone();
+        thread::spawn(move || {
+            writer.write_all(&data)?;
+            writer.flush()
+        })
+    };
+    block_on(pump).unwrap();
+    producer.join().unwrap().unwrap();
+    assert_eq!(*sink.data.lock().unwrap(), data);
+    assert!(*sink.closed.lock().unwrap());
+}
+
+#[test]
+fn test_writer_error() {
+    let sink = Sink {
+        fail_at: Some(10_000),
+        ..Sink::default()
+    };
+    let (mut writer, pump) = async_io::writer(sink.clone(), 4096);
+    let producer = thread::spawn(move || writer.write_all(&test_data()));
+    assert_eq!(block_on(pump).unwrap_err().kind(), io::ErrorKind::Other);
+    assert_eq!(producer.join().unwrap().unwrap_err().kind(), io::ErrorKind::Other);
+    assert!(!*sink.closed.lock().unwrap());
+}
+
+#[test]
+fn test_writer_dropped() {
+    let sink = Sink::default();
+    let (mut writer, pump) = async_io::writer(sink.clone(), 4096);
+    let producer = thread::spawn(move || writer.write_all(&test_data()));
+    drop(pump);
+    assert_eq!(producer.join().unwrap().unwrap_err().kind(), io::ErrorKind::BrokenPipe);
+    assert!(sink.data.lock().unwrap().is_empty());
+}
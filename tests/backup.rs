@@ -0,0 +1,41 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use gpgme::backup::Protection;
+
+use self::support::passphrase_cb;
+
+#[macro_use]
+mod support;
+
+test_case! {
+    test_backup_restore(test) {
+        let mut bundle = Vec::new();
+        let manifest = test.create_context().with_passphrase_provider(passphrase_cb, |ctx| {
+            fail_if_err!(ctx.backup(&mut bundle, Protection::Passphrase(b"backup")))
+        });
+        assert!(!bundle.is_empty());
+        let created = manifest.created().duration_since(UNIX_EPOCH).unwrap();
+        assert_eq!(created.subsec_nanos(), 0);
+        assert!(manifest.created() <= SystemTime::now());
+        let alfa = manifest
+            .keys()
+            .iter()
+            .find(|k| k.user_id().contains("alfa@example.net"))
+            .unwrap();
+        assert!(alfa.has_secret());
+
+        let mut ctx = test.create_context();
+        assert!(ctx.restore(&bundle, Some(b"wrong"), true).is_err());
+        let restored = fail_if_err!(ctx.restore(&bundle, Some(b"backup"), true));
+        assert_eq!(restored.manifest, manifest);
+        assert_eq!(restored.manifest.created(), manifest.created());
+        assert!(restored.import.is_none());
+
+        let restored = ctx.with_passphrase_provider(passphrase_cb, |ctx| {
+            fail_if_err!(ctx.restore(&bundle, Some(b"backup"), false))
+        });
+        let import = restored.import.unwrap();
+        assert!(import.considered() as usize >= manifest.keys().len());
+        assert_eq!(import.imported(), 0);
+    },
+}
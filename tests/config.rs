@@ -0,0 +1,58 @@
+use gpgme::{config::ContextConfig, Error, KeyListMode, PinentryMode, Protocol};
+
+#[macro_use]
+mod support;
+
+test_case! {
+    test_config_build(_test) {
+        let config = ContextConfig::new(Protocol::OpenPgp)
+            .armor(true)
+            .text_mode(true)
+            .pinentry_mode(PinentryMode::Loopback)
+            .key_list_mode(KeyListMode::LOCAL | KeyListMode::SIGS)
+            .sender("alfa@example.net")
+            .signer("A0FF4590BB6122EDEF6E3C542D727CC768697734");
+        let ctx = fail_if_err!(config.build());
+        assert!(ctx.armor());
+        assert!(ctx.text_mode());
+        assert_eq!(ctx.pinentry_mode(), PinentryMode::Loopback);
+        assert!(ctx.key_list_mode().contains(KeyListMode::SIGS));
+        assert_eq!(ctx.sender(), Ok("alfa@example.net"));
+        assert_eq!(ctx.signers().count(), 1);
+
+        let err = ContextConfig::new(Protocol::OpenPgp).signer("").build().unwrap_err();
+        assert_eq!(err.code(), Error::INV_VALUE.code());
+    },
+}
+
+#[test]
+fn test_config_validate() {
+    assert!(ContextConfig::default().validate().is_ok());
+    let err = ContextConfig::new(Protocol::Default).validate().unwrap_err();
+    assert_eq!(err.code(), Error::UNSUPPORTED_PROTOCOL.code());
+}
+
+#[cfg(all(feature = "serde", feature = "serde_json"))]
+#[test]
+fn test_config_json() {
+    let config: ContextConfig = serde_json::from_str(
+        r#"{
+            "protocol": "openpgp",
+            "armor": true,
+            "pinentry_mode": "loopback",
+            "key_list_mode": ["local", "with-secret"],
+            "flags": {"full-status": "1"}
+        }"#,
+    )
+    .unwrap();
+    let expected = ContextConfig::new(Protocol::OpenPgp)
+        .armor(true)
+        .pinentry_mode(PinentryMode::Loopback)
+        .key_list_mode(KeyListMode::LOCAL | KeyListMode::WITH_SECRET)
+        .flag("full-status", "1");
+    assert_eq!(config, expected);
+
+    let json = serde_json::to_string(&config).unwrap();
+    assert_eq!(serde_json::from_str::<ContextConfig>(&json).unwrap(), expected);
+    assert!(serde_json::from_str::<ContextConfig>(r#"{"protocol": "pgp"}"#).is_err());
+}
@@ -0,0 +1,51 @@
+use gpgme::{
+    dns::{self, OpenPgpKeyRecord, PkaRecord},
+    Fingerprint,
+};
+
+#[test]
+fn test_openpgpkey_owner() {
+    // Example from RFC 7929, section 3.
+    assert_eq!(
+        dns::openpgpkey_owner("hugh@example.com").unwrap(),
+        "c93f1e400f26708f98cb19d936620da35eec8f72e57f9eec01c1afd6._openpgpkey.example.com."
+    );
+    assert!(dns::openpgpkey_owner("example.com").is_err());
+    assert!(dns::openpgpkey_owner("hugh@").is_err());
+}
+
+#[test]
+fn test_pka_owner() {
+    assert_eq!(
+        dns::pka_owner("Alice@example.org").unwrap(),
+        "kei1q4tipxxu1yj79k9kfukdhfy631xe._pka.example.org."
+    );
+}
+
+#[test]
+fn test_openpgpkey_record() {
+    let record = OpenPgpKeyRecord::new("hugh@example.com", &b"\x99\x01\x0d"[..]).unwrap();
+    assert_eq!(
+        record.to_string(),
+        "c93f1e400f26708f98cb19d936620da35eec8f72e57f9eec01c1afd6._openpgpkey.example.com. \
+         IN OPENPGPKEY (\n\tmQEN )"
+    );
+    assert_eq!(
+        record.to_generic(),
+        "c93f1e400f26708f98cb19d936620da35eec8f72e57f9eec01c1afd6._openpgpkey.example.com. \
+         IN TYPE61 \\# 3 (\n\t99010D )"
+    );
+}
+
+#[test]
+fn test_pka_record() {
+    let fpr: Fingerprint = "A0FEF7A44A150EF7C35FB91F33F2C51C9B01A27C".parse().unwrap();
+    let record = PkaRecord::new("alice@example.org", fpr)
+        .unwrap()
+        .with_uri("finger:alice@example.org");
+    assert_eq!(
+        record.to_string(),
+        "kei1q4tipxxu1yj79k9kfukdhfy631xe._pka.example.org. IN TXT \
+         \"v=pka1;fpr=A0FEF7A44A150EF7C35FB91F33F2C51C9B01A27C;uri=finger:alice@example.org\""
+    );
+}
@@ -0,0 +1,51 @@
+use gpgme::{document, EncryptFlags, Error};
+use serde_json::json;
+
+use self::support::passphrase_cb;
+
+#[macro_use]
+mod support;
+
+test_case! {
+    test_encrypt_decrypt(test) {
+        let original = json!({
+            "user": "admin",
+            "password": "hunter2",
+            "servers": [{"host": "a.example.net", "token": 42}],
+            "note": "-----BEGIN PGP MESSAGE-----\nnot encrypted\n-----END PGP MESSAGE-----",
+        });
+        let mut doc = original.clone();
+        let mut ctx = test.create_context();
+        let key = fail_if_err!(ctx.find_keys(Some("alfa@example.net"))).nth(0).unwrap().unwrap();
+        fail_if_err!(document::encrypt_with_flags(
+            &mut ctx,
+            Some(&key),
+            &mut doc,
+            EncryptFlags::ALWAYS_TRUST,
+            |path| path == "password" || path.ends_with(".token")
+        ));
+        assert_eq!(doc["user"], "admin");
+        assert_eq!(doc["servers"][0]["host"], "a.example.net");
+        assert!(doc["password"].as_str().unwrap().starts_with("-----BEGIN PGP MESSAGE-----"));
+        assert!(doc["servers"][0]["token"].is_string());
+        assert_eq!(doc["note"], original["note"]);
+        assert_eq!(doc["gpgme"]["encrypted"], json!(["password", "servers[0].token"]));
+        let recipients = fail_if_err!(document::recipients(&doc));
+        assert_eq!(recipients, [key.to_fingerprint().unwrap()]);
+        drop(ctx);
+
+        test.create_context().with_passphrase_provider(passphrase_cb, |ctx| {
+            let mut tampered = doc.clone();
+            tampered["user"] = json!("root");
+            assert_eq!(document::decrypt(ctx, &mut tampered).err(), Some(Error::CHECKSUM));
+            assert_eq!(tampered["user"], "root");
+
+            let mut tampered = doc.clone();
+            tampered["gpgme"]["encrypted"] = json!(["password"]);
+            assert_eq!(document::decrypt(ctx, &mut tampered).err(), Some(Error::CHECKSUM));
+
+            fail_if_err!(document::decrypt(ctx, &mut doc));
+            assert_eq!(doc, original);
+        });
+    },
+}
@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+use gpgme::expiry::{Capabilities, ExpiryReport};
+
+use self::support::passphrase_cb;
+
+#[macro_use]
+mod support;
+
+const FPR: &str = "A0FF4590BB6122EDEF6E3C542D727CC768697734";
+const DAY: u64 = 24 * 60 * 60;
+
+test_case! {
+    test_expiry_report(test) {
+        let mut ctx = test.create_context();
+        let report = fail_if_err!(ExpiryReport::scan(&mut ctx, Duration::from_secs(365 * DAY)));
+        assert!(report.is_empty(), "{:?}", report);
+
+        let key = fail_if_err!(ctx.get_key(FPR));
+        ctx.with_passphrase_provider(passphrase_cb, |ctx| {
+            fail_if_err!(ctx.set_expire(&key, Some(Duration::from_secs(7 * DAY)), None::<&str>));
+        });
+
+        let report = fail_if_err!(ExpiryReport::scan(&mut ctx, Duration::from_secs(DAY)));
+        assert!(report.is_empty(), "{:?}", report);
+
+        let report = fail_if_err!(ExpiryReport::scan(&mut ctx, Duration::from_secs(30 * DAY)));
+        assert_eq!(report.entries().len(), 1);
+        let entry = &report.entries()[0];
+        assert_eq!(entry.fingerprint(), FPR);
+        assert!(entry.is_primary());
+        assert!(entry.has_secret());
+        assert!(!entry.is_expired());
+        assert!(entry.capabilities().contains(Capabilities::SIGN));
+        assert_eq!(report.expired().count(), 0);
+        assert_eq!(report.affecting(Capabilities::SIGN).count(), 1);
+        assert_eq!(report.affecting(Capabilities::ENCRYPT).count(), 0);
+
+        // Periods that cannot be represented must not overflow.
+        let report = fail_if_err!(ExpiryReport::scan(
+            &mut ctx,
+            Duration::from_secs(u64::max_value())
+        ));
+        assert_eq!(report.entries().len(), 1);
+    },
+}
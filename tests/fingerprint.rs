@@ -0,0 +1,49 @@
+use gpgme::{Fingerprint, KeyId};
+
+const FPR: &str = "A0FEF7A44A150EF7C35FB91F33F2C51C9B01A27C";
+
+#[test]
+fn test_fingerprint_normalization() {
+    let fpr: Fingerprint = "0xa0fe f7a4 4a15 0ef7 c35f  b91f 33f2 c51c 9b01 a27c"
+        .parse()
+        .unwrap();
+    assert_eq!(fpr.as_str(), FPR);
+    assert_eq!(fpr.version(), 4);
+    assert_eq!(fpr, FPR.to_lowercase().as_str());
+    assert_eq!(
+        fpr.to_string(),
+        "A0FE F7A4 4A15 0EF7 C35F  B91F 33F2 C51C 9B01 A27C"
+    );
+}
+
+#[test]
+fn test_fingerprint_rejects_invalid() {
+    assert!(Fingerprint::new("").is_err());
+    assert!(Fingerprint::new("33F2C51C9B01A27C").is_err());
+    assert!(Fingerprint::new(&FPR[1..]).is_err());
+    assert!(Fingerprint::new(&FPR.replace('A', "G")).is_err());
+    assert!(Fingerprint::new(&[FPR, "00"].concat()).is_err());
+}
+
+#[test]
+fn test_v5_fingerprint() {
+    let hex = "19347BC9872464025F99DF3EC2E0000ED9884892E1F7B3EA4C94009159569B54";
+    let fpr = Fingerprint::new(hex).unwrap();
+    assert_eq!(fpr.version(), 5);
+    assert_eq!(fpr.key_id(), "19347BC987246402");
+}
+
+#[test]
+fn test_key_id() {
+    let fpr = Fingerprint::new(FPR).unwrap();
+    let id = KeyId::new("0x33f2c51c9b01a27c").unwrap();
+    assert_eq!(fpr.key_id(), id);
+    assert!(fpr.matches(&id));
+    assert!(id.matches(&fpr));
+    assert_eq!(KeyId::from(&fpr), id);
+    assert_eq!(id.to_string(), "33F2 C51C 9B01 A27C");
+
+    // Short key ids are not accepted.
+    assert!(KeyId::new("9B01A27C").is_err());
+    assert!(!fpr.matches(&KeyId::new("0000000000000000").unwrap()));
+}
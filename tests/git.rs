@@ -0,0 +1,79 @@
+use gpgme::git::{self, Status};
+
+use self::support::passphrase_cb;
+
+#[macro_use]
+mod support;
+
+const COMMIT: &[u8] = b"tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\n\
+                        author A U Thor <author@example.com> 1112912053 -0700\n\
+                        committer C O Mitter <committer@example.com> 1112912053 -0700\n\
+                        \n\
+                        Initial commit\n";
+
+const TAG: &[u8] = b"object 4b825dc642cb6eb9a060e54bf8d69288fbee4904\n\
+                     type commit\n\
+                     tag v1.0\n\
+                     tagger C O Mitter <committer@example.com> 1112912053 -0700\n\
+                     \n\
+                     Version 1.0\n";
+
+test_case! {
+    test_sign_verify_commit(test) {
+        let mut signed = None;
+        test.create_context().with_passphrase_provider(passphrase_cb, |ctx| {
+            let (commit, _) = fail_if_err!(git::sign_commit(ctx, COMMIT));
+            signed = Some(commit);
+        });
+        let signed = signed.unwrap();
+        let (payload, signature) = git::split_signature(&signed).unwrap();
+        assert_eq!(payload, COMMIT);
+        assert!(signature.starts_with(b"-----BEGIN PGP SIGNATURE-----\n"));
+
+        let mut ctx = test.create_context();
+        let verification = fail_if_err!(git::verify(&mut ctx, &signed));
+        assert_ne!(verification.status(), Status::Bad);
+        assert!(verification.fingerprint().is_some());
+    },
+    test_sign_verify_tag(test) {
+        let mut signed = None;
+        test.create_context().with_passphrase_provider(passphrase_cb, |ctx| {
+            let (tag, _) = fail_if_err!(git::sign_tag(ctx, TAG));
+            signed = Some(tag);
+        });
+        let mut signed = signed.unwrap();
+        let mut ctx = test.create_context();
+        assert_ne!(fail_if_err!(git::verify(&mut ctx, &signed)).status(), Status::Bad);
+
+        signed[TAG.len() - 2] = b'1';
+        assert_eq!(fail_if_err!(git::verify(&mut ctx, &signed)).status(), Status::Bad);
+    },
+}
+
+#[test]
+fn test_split_commit_signature() {
+    let signed = b"tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\n\
+                   gpgsig -----BEGIN PGP SIGNATURE-----\n \n iQEz\n -----END PGP SIGNATURE-----\n\
+                   mergetag object 1\n continued\n\
+                   \n\
+                   message\n";
+    let (payload, signature) = git::split_signature(signed).unwrap();
+    assert_eq!(
+        payload,
+        &b"tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\n\
+           mergetag object 1\n continued\n\
+           \n\
+           message\n"[..]
+    );
+    assert_eq!(
+        signature,
+        &b"-----BEGIN PGP SIGNATURE-----\n\niQEz\n-----END PGP SIGNATURE-----\n"[..]
+    );
+}
+
+#[test]
+fn test_split_unsigned() {
+    assert!(git::split_signature(COMMIT).is_none());
+    assert!(git::split_signature(TAG).is_none());
+    assert_eq!(Status::None.to_string(), "N");
+}
@@ -0,0 +1,51 @@
+use std::io::prelude::*;
+
+use gpgme::{hashing::HashingData, Error, HashAlgorithm};
+
+use self::support::passphrase_cb;
+
+#[macro_use]
+mod support;
+
+const SHA256: &str = "a591a6d40bf420404a011733cfb7b190d62c65bf0bcda32b57b277d9ad9f146e";
+
+#[test]
+fn test_hashing_write() {
+    let mut buffer = Vec::new();
+    let algos = [HashAlgorithm::Sha1, HashAlgorithm::Sha256];
+    let mut data = HashingData::new(&mut buffer, &algos).unwrap();
+    data.write_all(b"Hello ").unwrap();
+    data.write_all(b"World").unwrap();
+    let digests = data.finish();
+    assert_eq!(digests.len(), 11);
+    assert_eq!(digests.get_hex(HashAlgorithm::Sha256).unwrap(), SHA256);
+    assert_eq!(
+        digests.get_hex(HashAlgorithm::Sha1).unwrap(),
+        "0a4d55a8d778e5022fab701977c5d840bbc486d0"
+    );
+    assert!(digests.get(HashAlgorithm::Sha512).is_none());
+    assert_eq!(buffer, b"Hello World");
+
+    let err = HashingData::new(&mut buffer, &[HashAlgorithm::Md5]).unwrap_err();
+    assert_eq!(err.code(), Error::DIGEST_ALGO.code());
+}
+
+test_case! {
+    test_hashing_encrypt_decrypt(test) {
+        let mut ciphertext = Vec::new();
+        let mut plaintext = fail_if_err!(HashingData::sha256("Hello World"));
+        test.create_context().with_passphrase_provider(passphrase_cb, |ctx| {
+            fail_if_err!(ctx.encrypt_symmetric(&mut plaintext, &mut ciphertext));
+        });
+        assert_eq!(plaintext.finish().get_hex(HashAlgorithm::Sha256).unwrap(), SHA256);
+
+        let mut decrypted = Vec::new();
+        let mut sink = fail_if_err!(HashingData::sha256(&mut decrypted));
+        test.create_context().with_passphrase_provider(passphrase_cb, |ctx| {
+            fail_if_err!(ctx.decrypt(&ciphertext, &mut sink));
+        });
+        let digests = sink.finish();
+        assert_eq!(digests.get_hex(HashAlgorithm::Sha256).unwrap(), SHA256);
+        assert_eq!(decrypted, b"Hello World");
+    },
+}
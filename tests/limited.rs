@@ -0,0 +1,44 @@
+use std::io::prelude::*;
+
+use gpgme::{data::Limited, Error};
+
+use self::support::passphrase_cb;
+
+#[macro_use]
+mod support;
+
+#[test]
+fn test_limited_write() {
+    let mut sink = Limited::new(Vec::new(), 5);
+    assert_eq!(sink.write(b"abc").unwrap(), 3);
+    assert_eq!(sink.write(b"defg").unwrap(), 2);
+    assert!(!sink.is_exceeded());
+    let err = Error::from(sink.write_all(b"h").unwrap_err());
+    assert_eq!(err.code(), Error::EFBIG.code());
+    assert!(sink.is_exceeded());
+    assert_eq!(sink.written(), 5);
+    assert_eq!(sink.into_inner(), b"abcde");
+}
+
+test_case! {
+    test_decrypt_limited(test) {
+        let plaintext = vec![b'A'; 1 << 20];
+        let mut ciphertext = Vec::new();
+        test.create_context().with_passphrase_provider(passphrase_cb, |ctx| {
+            fail_if_err!(ctx.encrypt_symmetric(&plaintext, &mut ciphertext));
+        });
+        assert!(ciphertext.len() < plaintext.len() / 100);
+
+        test.create_context().with_passphrase_provider(passphrase_cb, |ctx| {
+            let mut sink = Limited::new(Vec::new(), 4096);
+            let err = ctx.decrypt(&ciphertext, &mut sink).unwrap_err();
+            assert_eq!(err.code(), Error::EFBIG.code());
+            assert!(sink.is_exceeded());
+            assert!(sink.get_ref().len() <= 4096);
+
+            let mut sink = Limited::new(Vec::new(), plaintext.len() as u64);
+            fail_if_err!(ctx.decrypt(&ciphertext, &mut sink));
+            assert_eq!(sink.into_inner(), plaintext);
+        });
+    },
+}
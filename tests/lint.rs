@@ -0,0 +1,45 @@
+use std::time::Duration;
+
+use gpgme::{
+    lint::{Issue, Linter, Severity},
+    HashAlgorithm,
+};
+
+#[macro_use]
+mod support;
+
+const FPR: &str = "A0FF4590BB6122EDEF6E3C542D727CC768697734";
+
+test_case! {
+    test_lint_self_signatures(test) {
+        let mut ctx = test.create_context();
+        let key = fail_if_err!(ctx.get_key(FPR));
+        let findings = fail_if_err!(Linter::new().lint_with_context(&mut ctx, &key));
+
+        // The DSA demo key certifies its user ids using SHA-1, which is only found by
+        // parsing the issuer of each self-signature in the exported key.
+        let weak: Vec<_> = findings
+            .iter()
+            .filter(|f| *f.issue() == Issue::WeakSelfSignature(HashAlgorithm::Sha1))
+            .collect();
+        assert!(weak.iter().all(|f| f.severity() == Severity::Error));
+        assert!(weak
+            .iter()
+            .any(|f| f.user_id().map_or(false, |u| u.contains("<alfa@example.net>"))));
+    },
+    test_lint_expiry_horizon(test) {
+        let mut ctx = test.create_context();
+        let key = fail_if_err!(ctx.get_key(FPR));
+        let findings = Linter::new()
+            .expiry_horizon(Some(Duration::from_secs(u64::max_value())))
+            .lint(&key);
+        assert!(findings.iter().any(|f| *f.issue() == Issue::NoExpiration));
+        assert!(!findings.iter().any(|f| match *f.issue() {
+            Issue::ExpiresTooLate(_) => true,
+            _ => false,
+        }));
+
+        let findings = Linter::new().expiry_horizon(None).lint(&key);
+        assert!(!findings.iter().any(|f| *f.issue() == Issue::NoExpiration));
+    },
+}
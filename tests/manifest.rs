@@ -0,0 +1,66 @@
+use std::fs;
+
+use gpgme::manifest::{self, FileStatus, Manifest};
+use tempdir::TempDir;
+
+use self::support::passphrase_cb;
+
+#[macro_use]
+mod support;
+
+const HELLO_SHA256: &str = "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03";
+
+test_case! {
+    test_sign_verify(test) {
+        let dir = TempDir::new("manifest").unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("a.txt"), b"hello\n").unwrap();
+        fs::write(dir.path().join("sub/b.txt"), b"world\n").unwrap();
+        let manifest = fail_if_err!(Manifest::from_dir(dir.path()));
+        let paths = manifest.entries().iter().map(|e| e.path()).collect::<Vec<_>>();
+        assert_eq!(paths, ["a.txt", "sub/b.txt"]);
+
+        let mut signed = None;
+        test.create_context().with_passphrase_provider(passphrase_cb, |ctx| {
+            let (text, _) = fail_if_err!(manifest.sign(ctx));
+            signed = Some(text);
+        });
+        let signed = signed.unwrap();
+
+        let mut ctx = test.create_context();
+        let verified = fail_if_err!(manifest::verify(&mut ctx, &signed, dir.path()));
+        assert_eq!(verified.manifest, manifest);
+        assert!(verified.is_ok());
+
+        fs::write(dir.path().join("a.txt"), b"changed\n").unwrap();
+        fs::remove_file(dir.path().join("sub/b.txt")).unwrap();
+        let verified = fail_if_err!(manifest::verify(&mut ctx, &signed, dir.path()));
+        assert!(!verified.is_ok());
+        let failures = verified.failures().map(|f| f.status()).collect::<Vec<_>>();
+        assert_eq!(failures, [FileStatus::Mismatch, FileStatus::Missing]);
+    },
+}
+
+#[test]
+fn test_text_round_trip() {
+    let mut manifest = Manifest::new();
+    manifest.add_reader("./dir/hello.txt", &b"hello\n"[..]).unwrap();
+    manifest.add_reader("back\\slash", &b"hello\n"[..]).unwrap();
+    let text = manifest.to_text();
+    assert_eq!(
+        text,
+        format!(
+            "{0}  dir/hello.txt\n\\{0}  back\\\\slash\n",
+            HELLO_SHA256
+        )
+    );
+    assert_eq!(Manifest::parse(&text).unwrap(), manifest);
+}
+
+#[test]
+fn test_parse_rejects_unsafe_paths() {
+    assert!(Manifest::parse(&format!("{}  ../etc/passwd\n", HELLO_SHA256)).is_err());
+    assert!(Manifest::parse(&format!("{}  /etc/passwd\n", HELLO_SHA256)).is_err());
+    assert!(Manifest::parse(&format!("{} hello.txt\n", HELLO_SHA256)).is_err());
+    assert!(Manifest::parse(&format!("{} *hello.txt\n", HELLO_SHA256)).is_ok());
+}
@@ -0,0 +1,91 @@
+use gpgme::{self, mime};
+
+use self::support::passphrase_cb;
+
+#[macro_use]
+mod support;
+
+const ENTITY: &[u8] = b"Content-Type: text/plain; charset=utf-8\n\nHello, World!\n";
+
+test_case! {
+    test_sign_verify(test) {
+        let mut message = None;
+        test.create_context().with_passphrase_provider(passphrase_cb, |ctx| {
+            let (msg, _) = fail_if_err!(mime::sign(ctx, ENTITY));
+            message = Some(msg);
+        });
+        let message = message.unwrap();
+        assert!(message.content_type().starts_with("multipart/signed;"));
+        assert!(message.content_type().contains("micalg=\"pgp-"));
+
+        // Simulate a transport converting line endings.
+        let bytes = String::from_utf8(message.to_bytes()).unwrap().replace("\r\n", "\n");
+        let mut ctx = test.create_context();
+        let verified = fail_if_err!(mime::verify(&mut ctx, bytes.as_bytes()));
+        assert_eq!(verified.entity, mime::canonicalize(ENTITY));
+        let sig = verified.result.signatures().next().unwrap();
+        fail_if_err!(sig.status());
+    },
+    test_encrypt_decrypt(test) {
+        let mut ctx = test.create_context();
+        let key = fail_if_err!(ctx.find_keys(Some("alfa@example.net"))).nth(0).unwrap().unwrap();
+        let (message, _) = fail_if_err!(mime::encrypt_with_flags(
+            &mut ctx,
+            Some(&key),
+            ENTITY,
+            gpgme::EncryptFlags::ALWAYS_TRUST
+        ));
+        assert!(message.content_type().starts_with("multipart/encrypted;"));
+        drop(ctx);
+
+        test.create_context().with_passphrase_provider(passphrase_cb, |ctx| {
+            match fail_if_err!(mime::process(ctx, &message.to_bytes())) {
+                mime::Processed::Decrypted(d) => assert_eq!(d.entity, mime::canonicalize(ENTITY)),
+                mime::Processed::Verified(_) => panic!("message was not decrypted"),
+            }
+        });
+    },
+}
+
+#[test]
+fn test_parse_signed() {
+    let message = b"Content-Type: multipart/signed; micalg=pgp-sha256;\n\
+                    \tprotocol=\"application/pgp-signature\"; boundary=\"b;1\"\n\
+                    \n\
+                    preamble\n\
+                    --b;1\n\
+                    Content-Type: text/plain\n\
+                    \n\
+                    signed\n\
+                    \n\
+                    --b;1\n\
+                    Content-Type: application/pgp-signature\n\
+                    \n\
+                    SIG\n\
+                    --b;1--\n";
+    match mime::parse(message).unwrap() {
+        mime::ParsedMessage::Signed {
+            entity,
+            signature,
+            micalg,
+        } => {
+            assert_eq!(entity, b"Content-Type: text/plain\r\n\r\nsigned\r\n");
+            assert_eq!(signature, b"SIG");
+            assert_eq!(micalg.as_ref().map(|s| &**s), Some("pgp-sha256"));
+        }
+        other => panic!("unexpected message: {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_other() {
+    let message = b"Content-Type: text/plain\r\n\r\nHello\r\n";
+    assert_eq!(
+        mime::parse(message).unwrap(),
+        mime::ParsedMessage::Other {
+            mime_type: "text/plain".to_owned()
+        }
+    );
+    let message = b"Content-Type: multipart/encrypted; protocol=\"application/pgp-encrypted\"\r\n\r\n";
+    assert!(mime::parse(message).is_err());
+}
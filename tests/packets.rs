@@ -0,0 +1,86 @@
+use gpgme::{
+    packets::{self, Body, Parser, Tag},
+    Error,
+};
+
+#[test]
+fn test_parse_armored_keys() {
+    let packets = packets::parse(&include_bytes!("./data/pubdemo.asc")[..]).unwrap();
+    assert_eq!(packets[0].tag(), Tag::PublicKey);
+    match packets[0].body() {
+        Body::Key {
+            primary,
+            secret,
+            fingerprint,
+            key_id,
+            bits,
+            ..
+        } => {
+            assert!(*primary && !*secret);
+            assert_eq!(
+                fingerprint.as_ref().unwrap(),
+                "A0FF4590BB6122EDEF6E3C542D727CC768697734"
+            );
+            assert_eq!(key_id.as_ref().unwrap(), "2D727CC768697734");
+            assert_eq!(*bits, Some(1024));
+        }
+        body => panic!("unexpected body: {:?}", body),
+    }
+    assert!(packets.iter().any(|p| {
+        *p.body() == Body::UserId("Alfa Test (demo key) <alfa@example.net>".to_owned())
+    }));
+    assert!(packets.iter().any(|p| match p.body() {
+        Body::Signature { issuer, subpackets, .. } => {
+            (issuer.as_ref().map(|i| &**i) == Some("2D727CC768697734"))
+                && subpackets.iter().any(|s| s.hashed && (s.kind == 2))
+        }
+        _ => false,
+    }));
+}
+
+#[test]
+fn test_parse_message() {
+    // A PKESK packet followed by a SEIPD packet using a partial body length.
+    let mut message = vec![0x84, 0x0c, 0x03];
+    message.extend_from_slice(&[0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0]);
+    message.extend_from_slice(&[0x01, 0x00, 0x00]);
+    message.extend_from_slice(&[0xd2, 0xe1, 0x01, 0x02]);
+    message.extend_from_slice(&[0x03, 0x04, 0x05, 0x06]);
+
+    let packets = Parser::new(&message[..]).collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(packets.len(), 2);
+    match packets[0].body() {
+        Body::PublicKeyEncryptedSessionKey { recipient, .. } => {
+            assert_eq!(recipient.as_ref().unwrap(), "123456789ABCDEF0");
+        }
+        body => panic!("unexpected body: {:?}", body),
+    }
+    assert_eq!(packets[1].offset(), 14);
+    assert!(packets[1].is_partial());
+    assert_eq!(packets[1].length(), 5);
+
+    message.truncate(message.len() - 1);
+    assert_eq!(packets::parse(&message[..]).err(), Some(Error::TRUNCATED));
+}
+
+#[test]
+fn test_parse_chunk_size() {
+    // SEIPD version 2 packets using AES-256 and OCB, with the chunk size octet last.
+    let seipd = |octet: u8| [0xd2, 0x06, 0x02, 0x09, 0x02, octet, 0x00, 0x00];
+    let packets = packets::parse(&seipd(0x10)[..]).unwrap();
+    match packets[0].body() {
+        Body::SymEncryptedIntegrityProtectedData { chunk_size, .. } => {
+            assert_eq!(*chunk_size, Some(1 << 22));
+        }
+        body => panic!("unexpected body: {:?}", body),
+    }
+    for &octet in &[0x11, 0x3a, 0xff] {
+        let packets = packets::parse(&seipd(octet)[..]).unwrap();
+        assert_eq!(*packets[0].body(), Body::Unknown);
+    }
+
+    let aead = [0xd4, 0x05, 0x01, 0x09, 0x02, 0xff, 0x00];
+    let packets = packets::parse(&aead[..]).unwrap();
+    assert_eq!(packets[0].tag(), Tag::AeadEncryptedData);
+    assert_eq!(*packets[0].body(), Body::Unknown);
+}
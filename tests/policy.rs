@@ -0,0 +1,94 @@
+use std::time::Duration;
+
+use gpgme::{
+    policy::{VerifyPolicy, Violation},
+    KeyAlgorithm, SignatureSummary,
+};
+use tempdir::TempDir;
+
+use self::support::passphrase_cb;
+
+#[macro_use]
+mod support;
+
+const FPR: &str = "A0FF4590BB6122EDEF6E3C542D727CC768697734";
+
+test_case! {
+    test_policy_evaluate(test) {
+        let mut ctx = test.create_context();
+        let key = fail_if_err!(ctx.get_secret_key(FPR));
+        fail_if_err!(ctx.add_signer(&key));
+        let mut signed = Vec::new();
+        ctx.with_passphrase_provider(passphrase_cb, |ctx| {
+            fail_if_err!(ctx.sign_normal("Hello World", &mut signed));
+        });
+        let result = fail_if_err!(ctx.verify_opaque(&signed, &mut Vec::new()));
+        let hash = result.signatures().next().unwrap().hash_algorithm();
+
+        let base = VerifyPolicy::new()
+            .allow_hash(hash)
+            .require_summary(SignatureSummary::empty());
+        let report = base.evaluate_with_context(&mut ctx, &result);
+        assert!(report.is_accepted(), "{}", report);
+
+        let report = base
+            .clone()
+            .min_key_size(KeyAlgorithm::Dsa, 2048)
+            .evaluate_with_context(&mut ctx, &result);
+        assert!(!report.is_accepted());
+        assert_eq!(report.signatures()[0].violations(), [Violation::WeakKey {
+            algorithm: KeyAlgorithm::Dsa,
+            length: 1024,
+            required: 2048,
+        }]);
+
+        // Without the key, the size is unknown rather than taken from another key.
+        let home = TempDir::new("gpgme-policy").unwrap();
+        let mut empty = test.create_context();
+        fail_if_err!(empty.set_engine_home_dir(home.path().to_str().unwrap()));
+        let unknown = fail_if_err!(empty.verify_opaque(&signed, &mut Vec::new()));
+        assert!(unknown.signatures().next().unwrap().key().is_none());
+        let report = base
+            .clone()
+            .min_key_size(KeyAlgorithm::Dsa, 1024)
+            .evaluate_with_context(&mut empty, &unknown);
+        assert!(report.signatures()[0].violations().contains(&Violation::UnknownKeySize));
+
+        let created = result.signatures().next().unwrap().creation_time().unwrap();
+        let report = base
+            .clone()
+            .max_age(Duration::from_secs(60))
+            .evaluate_at(&result, created + Duration::from_secs(120));
+        assert_eq!(report.signatures()[0].violations(), [Violation::TooOld(
+            Duration::from_secs(120)
+        )]);
+        let report = base
+            .clone()
+            .max_age(Duration::from_secs(60))
+            .evaluate_at(&result, created - Duration::from_secs(60));
+        assert!(report.is_accepted(), "{}", report);
+        let report = base
+            .clone()
+            .max_age(Duration::from_secs(60))
+            .evaluate_at(&result, created - Duration::from_secs(3600));
+        assert_eq!(report.signatures()[0].violations(), [Violation::CreatedInFuture(
+            Duration::from_secs(3600)
+        )]);
+
+        let report = base.clone().allow_signer(FPR.to_lowercase()).evaluate(&result);
+        assert!(report.is_accepted(), "{}", report);
+        let report = base
+            .clone()
+            .allow_signer("0000000000000000000000000000000000000000")
+            .evaluate(&result);
+        assert_eq!(report.signatures()[0].violations(), [Violation::UnknownSigner]);
+
+        let report = base.clone().ban_hash(hash).evaluate(&result);
+        assert_eq!(report.signatures()[0].violations(), [Violation::BannedHash(hash)]);
+
+        let report = base.clone().min_signatures(2).evaluate(&result);
+        assert!(!report.is_accepted());
+        assert_eq!(report.accepted_count(), 1);
+        assert!(report.into_result().is_err());
+    },
+}
@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+use gpgme::{pool::ContextPool, Context, Protocol, SignatureNotationFlags};
+
+#[macro_use]
+mod support;
+
+test_case! {
+    test_pool_reset_and_bound(_test) {
+        let pool = ContextPool::new(2, || {
+            let mut ctx = Context::from_protocol(Protocol::OpenPgp)?;
+            ctx.set_armor(true);
+            Ok(ctx)
+        });
+
+        let mut first = fail_if_err!(pool.get());
+        let key = fail_if_err!(first.get_secret_key("A0FF4590BB6122EDEF6E3C542D727CC768697734"));
+        fail_if_err!(first.add_signer(&key));
+        fail_if_err!(first.set_sender("alfa@example.net"));
+        fail_if_err!(first.add_signature_notation(
+            "test@example.net",
+            "value",
+            SignatureNotationFlags::empty()
+        ));
+
+        let second = fail_if_err!(pool.get());
+        assert_eq!(pool.in_use(), 2);
+        assert!(fail_if_err!(pool.try_get()).is_none());
+        assert!(fail_if_err!(pool.get_timeout(Duration::from_millis(10))).is_none());
+        drop(first);
+        second.discard();
+        assert_eq!(pool.in_use(), 0);
+
+        // Timeouts that cannot be represented as a deadline must not panic.
+        let forever = Duration::from_secs(u64::max_value());
+        assert!(fail_if_err!(pool.get_timeout(forever)).is_some());
+
+        let ctx = fail_if_err!(pool.get());
+        assert!(ctx.armor());
+        assert_eq!(ctx.signers().count(), 0);
+        assert_eq!(ctx.signature_notations().count(), 0);
+        assert!(ctx.sender_raw().is_none());
+    },
+}
@@ -0,0 +1,63 @@
+use gpgme::{
+    resolver::{KeyResolver, RejectionReason, ResolutionStatus},
+    Validity,
+};
+
+#[macro_use]
+mod support;
+
+const FPR: &str = "A0FF4590BB6122EDEF6E3C542D727CC768697734";
+
+test_case! {
+    test_resolve_address(test) {
+        let mut ctx = test.create_context();
+        let resolution = fail_if_err!(KeyResolver::new()
+            .min_validity(Validity::Unknown)
+            .encrypt_to_self(false)
+            .resolve(&mut ctx, &["Alfa Test <ALFA@example.net>"]));
+        assert!(resolution.is_complete());
+        assert!(resolution.sender().is_none());
+        let recipient = &resolution.recipients()[0];
+        assert_eq!(recipient.address(), "ALFA@example.net");
+        assert_eq!(recipient.key().unwrap().fingerprint(), Ok(FPR));
+        assert_eq!(resolution.keys().count(), 1);
+        assert_eq!(resolution.problems().count(), 0);
+    },
+    test_resolve_unknown_address(test) {
+        let mut ctx = test.create_context();
+        let resolution = fail_if_err!(KeyResolver::new()
+            .encrypt_to_self(false)
+            .resolve(&mut ctx, &["alfa@example.net", "nobody@example.net"]));
+        assert!(!resolution.is_complete());
+        assert_eq!(resolution.keys().count(), 0);
+        assert_eq!(resolution.problems().count(), 2);
+
+        // The imported keys are not certified, so they do not reach the default validity.
+        match *resolution.recipients()[0].status() {
+            ResolutionStatus::Unresolved(ref rejected) => {
+                assert_eq!(rejected.len(), 1);
+                assert_eq!(rejected[0].fingerprint(), Some(FPR));
+                match rejected[0].reason() {
+                    RejectionReason::InsufficientValidity(_) => (),
+                    reason => panic!("unexpected rejection: {}", reason),
+                }
+            }
+            ref status => panic!("unexpected status: {:?}", status),
+        }
+        match *resolution.recipients()[1].status() {
+            ResolutionStatus::Unresolved(ref rejected) => assert!(rejected.is_empty()),
+            ref status => panic!("unexpected status: {:?}", status),
+        }
+    },
+    test_resolve_sender(test) {
+        let mut ctx = test.create_context();
+        fail_if_err!(ctx.set_sender("alfa@example.net"));
+        let resolution = fail_if_err!(KeyResolver::new()
+            .min_validity(Validity::Unknown)
+            .resolve(&mut ctx, &["alfa@example.net"]));
+        assert!(resolution.is_complete());
+        let sender = resolution.sender().unwrap();
+        assert_eq!(sender.key().unwrap().fingerprint(), Ok(FPR));
+        assert_eq!(resolution.keys().count(), 1);
+    },
+}
@@ -0,0 +1,54 @@
+use std::io::prelude::*;
+use std::io::SeekFrom;
+
+use gpgme::secure::SecureBuffer;
+
+use self::support::passphrase_cb;
+
+#[macro_use]
+mod support;
+
+#[test]
+fn test_write_read_seek() {
+    let mut buffer = SecureBuffer::new();
+    assert_eq!(buffer.capacity(), 0);
+    let data = vec![0x5a; 1000];
+    buffer.write_all(&data).unwrap();
+    assert_eq!(buffer.as_bytes(), &data[..]);
+    assert!(buffer.capacity() >= data.len());
+    // Buffers occupy whole pages of their own.
+    assert_eq!(buffer.capacity() % 4096, 0);
+    assert_eq!(buffer.as_bytes().as_ptr() as usize % 4096, 0);
+
+    buffer.seek(SeekFrom::Start(2)).unwrap();
+    buffer.write_all(b"abc").unwrap();
+    assert_eq!(buffer.seek(SeekFrom::End(-1)).unwrap(), 999);
+    assert!(buffer.seek(SeekFrom::Current(-1000)).is_err());
+
+    buffer.set_position(0);
+    let mut out = Vec::new();
+    buffer.read_to_end(&mut out).unwrap();
+    assert_eq!(&out[..6], b"\x5a\x5aabc\x5a");
+
+    buffer.truncate(5);
+    assert_eq!(buffer.as_str().unwrap(), "ZZabc");
+    buffer.clear();
+    assert!(buffer.is_empty());
+    assert!(format!("{:?}", SecureBuffer::from(&b"hunter2"[..])).find("hunter2").is_none());
+}
+
+test_case! {
+    test_secure_encrypt_decrypt(test) {
+        let mut plaintext = SecureBuffer::from(&b"Hello World"[..]);
+        let mut ciphertext = Vec::new();
+        test.create_context().with_passphrase_provider(passphrase_cb, |ctx| {
+            fail_if_err!(ctx.encrypt_symmetric(&mut plaintext, &mut ciphertext));
+        });
+
+        let mut decrypted = SecureBuffer::new();
+        test.create_context().with_passphrase_provider(passphrase_cb, |ctx| {
+            fail_if_err!(ctx.decrypt(&ciphertext, &mut decrypted));
+        });
+        assert_eq!(decrypted.as_bytes(), plaintext.as_bytes());
+    },
+}
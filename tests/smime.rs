@@ -0,0 +1,122 @@
+use gpgme::{
+    smime::{self, ParsedMessage, SmimeType},
+    Error,
+};
+
+const OID_SIGNED_DATA: &[u8] = b"\x06\x09\x2a\x86\x48\x86\xf7\x0d\x01\x07\x02";
+const OID_ENVELOPED_DATA: &[u8] = b"\x06\x09\x2a\x86\x48\x86\xf7\x0d\x01\x07\x03";
+
+fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    assert!(content.len() < 0x80);
+    let mut result = vec![tag, content.len() as u8];
+    result.extend_from_slice(content);
+    result
+}
+
+fn signed_data(signer_infos: &[u8]) -> Vec<u8> {
+    let mut content = tlv(0x02, b"\x01");
+    content.extend(tlv(0x31, b""));
+    content.extend(tlv(0x30, OID_SIGNED_DATA));
+    content.extend(tlv(0x31, signer_infos));
+    let mut info = OID_SIGNED_DATA.to_vec();
+    info.extend(tlv(0xa0, &tlv(0x30, &content)));
+    tlv(0x30, &info)
+}
+
+#[test]
+fn test_detect() {
+    let enveloped = tlv(0x30, OID_ENVELOPED_DATA);
+    assert_eq!(SmimeType::detect(&enveloped), Some(SmimeType::EnvelopedData));
+    assert_eq!(SmimeType::detect(&signed_data(b"")), Some(SmimeType::CertsOnly));
+    assert_eq!(
+        SmimeType::detect(&signed_data(&tlv(0x30, b"\x02\x01\x01"))),
+        Some(SmimeType::SignedData)
+    );
+
+    // Indefinite length encoding.
+    let mut ber = vec![0x30, 0x80];
+    ber.extend_from_slice(OID_ENVELOPED_DATA);
+    ber.extend_from_slice(&[0, 0]);
+    assert_eq!(SmimeType::detect(&ber), Some(SmimeType::EnvelopedData));
+
+    assert_eq!(SmimeType::detect(b""), None);
+    assert_eq!(SmimeType::detect(&tlv(0x30, b"\x02\x01\x01")), None);
+    assert_eq!(SmimeType::detect(&enveloped[..5]), None);
+    assert_eq!(SmimeType::detect(b"\x30\x84\xff\xff\xff\xff"), None);
+}
+
+#[test]
+fn test_detect_deep_nesting() {
+    let mut ber = Vec::new();
+    for _ in 0..100_000 {
+        ber.extend_from_slice(&[0x30, 0x80]);
+    }
+    for _ in 0..100_000 {
+        ber.extend_from_slice(&[0, 0]);
+    }
+    assert_eq!(SmimeType::detect(&ber), None);
+
+    let mut message = b"Content-Type: application/pkcs7-mime\r\n\r\n".to_vec();
+    message.extend_from_slice(&ber);
+    assert_eq!(smime::parse(&message), Err(Error::INV_VALUE));
+}
+
+#[test]
+fn test_parse_opaque() {
+    let message = b"Content-Type: application/pkcs7-mime; smime-type=enveloped-data\n\
+        Content-Transfer-Encoding: base64\n\nMAsGCSqGSIb3DQEHAw==\n";
+    assert_eq!(smime::parse(message), Ok(ParsedMessage::Opaque {
+        smime_type: SmimeType::EnvelopedData,
+        data: tlv(0x30, OID_ENVELOPED_DATA),
+    }));
+
+    // The type is detected from the content if the parameter is missing.
+    let message = b"Content-Type: application/x-pkcs7-mime\n\
+        Content-Transfer-Encoding: base64\n\nMAsGCSqGSIb3DQEHAw==\n";
+    match smime::parse(message) {
+        Ok(ParsedMessage::Opaque { smime_type, .. }) => {
+            assert_eq!(smime_type, SmimeType::EnvelopedData)
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    let message = b"Content-Type: application/pkcs7-mime\n\nnot a cms object\n";
+    assert_eq!(smime::parse(message), Err(Error::INV_VALUE));
+}
+
+#[test]
+fn test_parse_signed() {
+    let message = b"Content-Type: multipart/signed; protocol=\"application/pkcs7-signature\";\n\
+        \tmicalg=SHA-256; boundary=\"xyz\"\n\
+        \n\
+        --xyz\n\
+        Content-Type: text/plain\n\
+        \n\
+        Hello, World!\n\
+        --xyz\n\
+        Content-Type: application/pkcs7-signature\n\
+        \n\
+        signature\n\
+        --xyz--\n";
+    match smime::parse(message) {
+        Ok(ParsedMessage::Signed {
+            entity,
+            signature,
+            micalg,
+        }) => {
+            assert_eq!(entity, b"Content-Type: text/plain\r\n\r\nHello, World!");
+            assert_eq!(signature, b"signature");
+            assert_eq!(micalg.as_ref().map(|s| &**s), Some("sha-256"));
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    let message = b"Content-Type: multipart/signed; protocol=\"application/pgp-signature\";\n\
+        \tboundary=\"xyz\"\n\n--xyz--\n";
+    assert_eq!(smime::parse(message), Ok(ParsedMessage::Other {
+        mime_type: "multipart/signed".to_owned(),
+    }));
+    assert_eq!(smime::parse(b"Hello, World!\n"), Ok(ParsedMessage::Other {
+        mime_type: "text/plain".to_owned(),
+    }));
+}
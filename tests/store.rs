@@ -0,0 +1,51 @@
+use gpgme::{store::PasswordStore, EncryptFlags};
+use tempdir::TempDir;
+
+use self::support::passphrase_cb;
+
+#[macro_use]
+mod support;
+
+test_case! {
+    test_insert_get_reencrypt(test) {
+        let dir = TempDir::new("store").unwrap();
+        let store = PasswordStore::new(dir.path())
+            .with_flags(EncryptFlags::ALWAYS_TRUST | EncryptFlags::NO_ENCRYPT_TO);
+        test.create_context().with_passphrase_provider(passphrase_cb, |ctx| {
+            assert!(fail_if_err!(store.init(ctx, "", &["alfa@example.net"])).is_empty());
+            fail_if_err!(store.insert(ctx, "web/example.org", b"hunter2\nuser: alfa\n"));
+            fail_if_err!(store.insert(ctx, "mail", b"secret"));
+            assert_eq!(fail_if_err!(store.get_password(ctx, "web/example.org")), "hunter2");
+            assert_eq!(fail_if_err!(store.list()), ["mail", "web/example.org"]);
+            assert_eq!(fail_if_err!(store.find("EXAMPLE")), ["web/example.org"]);
+
+            fail_if_err!(store.edit(ctx, "mail", |c| c.extend_from_slice(b"\n2")));
+            assert_eq!(fail_if_err!(store.get(ctx, "mail")), b"secret\n2");
+
+            let ids = ["alfa@example.net", "bravo@example.net"];
+            let changed = fail_if_err!(store.init(ctx, "web", &ids));
+            assert_eq!(changed, ["web/example.org"]);
+            assert!(fail_if_err!(store.reencrypt(ctx, "")).is_empty());
+            assert_eq!(fail_if_err!(store.recipients(ctx, "web")).len(), 2);
+        });
+        assert!(store.entry_path("../outside").is_err());
+    },
+    test_recipients_exact_match(test) {
+        let dir = TempDir::new("store").unwrap();
+        let store = PasswordStore::new(dir.path());
+        let mut ctx = test.create_context();
+        let fpr = "A0FF4590BB6122EDEF6E3C542D727CC768697734";
+        for id in &[fpr, "0x2D727CC768697734", "68697734", "Alfa@Example.NET"] {
+            fail_if_err!(store.init(&mut ctx, "", &[*id]));
+            let keys = fail_if_err!(store.recipients(&mut ctx, ""));
+            assert_eq!(keys.len(), 1);
+            assert_eq!(keys[0].fingerprint(), Ok(fpr));
+        }
+
+        // Partial matches of user ids must not add recipients.
+        for id in &["example.net", "alfa", "Alfa Test"] {
+            std::fs::write(dir.path().join(".gpg-id"), id).unwrap();
+            assert_eq!(store.recipients(&mut ctx, "").err(), Some(gpgme::Error::NO_PUBKEY));
+        }
+    },
+}
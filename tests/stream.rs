@@ -0,0 +1,67 @@
+use std::{
+    io::{self, prelude::*},
+    sync::{Arc, Mutex},
+};
+
+use gpgme::{EncryptFlags, PinentryMode};
+
+#[macro_use]
+mod support;
+
+#[derive(Clone, Default)]
+struct SharedSink(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+test_case! {
+    test_stream_encrypt_decrypt(test) {
+        let mut ctx = test.create_context();
+        let key = fail_if_err!(ctx.get_key("A0FF4590BB6122EDEF6E3C542D727CC768697734"));
+        let plaintext: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+
+        let mut writer = ctx.encrypt_writer_with_flags(
+            Some(&key),
+            Vec::new(),
+            EncryptFlags::ALWAYS_TRUST,
+        );
+        for chunk in plaintext.chunks(1000) {
+            fail_if_err!(writer.write_all(chunk));
+        }
+        let result = fail_if_err!(writer.finish());
+        assert!(result.invalid_recipients().next().is_none());
+        let (_, ciphertext) = writer.into_inner();
+        assert!(!ciphertext.is_empty());
+
+        let mut ctx = test.create_context();
+        fail_if_err!(ctx.set_pinentry_mode(PinentryMode::Default));
+        let mut reader = ctx.decrypt_reader(ciphertext);
+        let mut decrypted = Vec::new();
+        fail_if_err!(io::copy(&mut reader, &mut decrypted));
+        assert_eq!(decrypted, plaintext);
+        assert!(reader.finish().is_ok());
+    },
+    test_stream_encrypt_dropped(test) {
+        let mut ctx = test.create_context();
+        let key = fail_if_err!(ctx.get_key("A0FF4590BB6122EDEF6E3C542D727CC768697734"));
+        let sink = SharedSink::default();
+
+        let mut writer =
+            ctx.encrypt_writer_with_flags(Some(&key), sink.clone(), EncryptFlags::ALWAYS_TRUST);
+        fail_if_err!(writer.write_all(&[0x5a; 100_000]));
+        drop(writer);
+
+        // The truncated plaintext must not have been encrypted into a valid message.
+        let ciphertext = sink.0.lock().unwrap().clone();
+        let mut ctx = test.create_context();
+        fail_if_err!(ctx.set_pinentry_mode(PinentryMode::Default));
+        assert!(ctx.decrypt(&ciphertext, &mut Vec::new()).is_err());
+    },
+}
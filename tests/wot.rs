@@ -0,0 +1,55 @@
+use gpgme::{wot::TrustGraph, Fingerprint};
+
+use self::support::passphrase_cb;
+
+#[macro_use]
+mod support;
+
+test_case! {
+    test_trust_graph(test) {
+        let mut ctx = test.create_context();
+        let signer = fail_if_err!(ctx.find_secret_keys(Some("alfa@example.net")))
+            .nth(0)
+            .unwrap()
+            .unwrap();
+        let key = fail_if_err!(ctx.find_keys(Some("bravo@example.net"))).nth(0).unwrap().unwrap();
+        let alfa: Fingerprint = signer.to_fingerprint().unwrap();
+        let bravo: Fingerprint = key.to_fingerprint().unwrap();
+
+        let graph = fail_if_err!(TrustGraph::load(&mut ctx));
+        let count = fail_if_err!(ctx.keys()).count();
+        assert_eq!(graph.nodes().len(), count);
+        let node = graph.node(&alfa).unwrap();
+        assert_eq!(graph.node_by_id(node.key_id()).unwrap().fingerprint(), &alfa);
+        assert!(node.user_ids().iter().any(|u| u.contains("<alfa@example.net>")));
+        // Self-signatures are not edges.
+        assert!(graph
+            .certifications()
+            .iter()
+            .all(|c| c.signer() != graph.target(c).key_id()));
+        assert_eq!(graph.shortest_path(&alfa, &alfa).map(|p| p.len()), Some(0));
+        assert_eq!(graph.certifiers(&bravo, None).count(), 0);
+        assert!(graph.shortest_path(&alfa, &bravo).is_none());
+
+        fail_if_err!(ctx.add_signer(&signer));
+        ctx.with_passphrase_provider(passphrase_cb, |ctx| {
+            fail_if_err!(ctx.sign_key(&key, None::<String>, None));
+        });
+
+        let graph = fail_if_err!(TrustGraph::load(&mut ctx));
+        let certs: Vec<_> = graph.certifiers(&bravo, None).collect();
+        assert!(!certs.is_empty());
+        assert!(certs.iter().all(|c| *c.signer() == alfa.key_id()));
+        assert_eq!(graph.signer(certs[0]).unwrap().fingerprint(), &alfa);
+        assert_eq!(graph.certifications_by(&alfa).count(), certs.len());
+        assert_eq!(graph.stale_certifications_by(&alfa).count(), 0);
+
+        let path = graph.shortest_path(&alfa, &bravo).unwrap();
+        assert_eq!(path.len(), 1);
+        assert_eq!(graph.target(path[0]).fingerprint(), &bravo);
+        assert!(graph.shortest_path(&bravo, &alfa).is_none());
+        assert!(graph
+            .to_dot()
+            .contains(&format!("\"{}\" -> \"{}\"", alfa.as_str(), bravo.as_str())));
+    },
+}